@@ -0,0 +1,109 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Compositor specific actions and gestures, that can be bound in addition to
+//! the shortcuts of `com.system76.CosmicSettings.Shortcuts`.
+
+use cosmic_settings_config::shortcuts::action::{Direction, ResizeDirection};
+use serde::{Deserialize, Serialize};
+
+use crate::workspace::TilingAlgorithm;
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+// Behaviors not covered by `cosmic_settings_config::shortcuts::Action`.
+pub enum CompAction {
+    MoveToScratchpad,
+    ToggleScratchpad,
+    /// Arrange the tiled windows of the active workspace with the given algorithm
+    SetTilingAlgorithm(TilingAlgorithm),
+    /// Switch the active workspace between the tiling tree and the scrollable strip of columns
+    ToggleScrolling,
+    /// Save a screenshot, optionally also copying it to the clipboard
+    Screenshot {
+        target: ScreenshotTarget,
+        #[serde(default)]
+        clipboard: bool,
+    },
+    /// Start recording the target to a file, or stop the running recording
+    ToggleRecording(RecordingTarget),
+    /// Apply the named output profile and keep preferring it, while its outputs are connected
+    ApplyOutputProfile(String),
+    /// Snap the focused floating window to a side or corner of the output,
+    /// cycling through 1/2, 1/3 and 2/3 of the output when repeated
+    SnapFloating(SnapPosition),
+    /// Move the focused floating window by `floating_nudge_step` pixels
+    NudgeFloating(Direction),
+    /// Grow or shrink the focused floating window at an edge by `floating_nudge_step` pixels
+    ResizeFloating {
+        direction: ResizeDirection,
+        edge: Direction,
+    },
+    /// Center the focused floating window on its output
+    CenterFloating,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapPosition {
+    Top,
+    TopRight,
+    Right,
+    BottomRight,
+    Bottom,
+    BottomLeft,
+    Left,
+    TopLeft,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScreenshotTarget {
+    /// The output with keyboard focus, or the one the pointer is on if nothing is focused
+    Output,
+    AllOutputs,
+    /// A rectangle selected with the pointer
+    Region,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordingTarget {
+    /// The output the pointer is on
+    Output,
+    /// The active workspace of the output the pointer is on
+    Workspace,
+    /// The focused window
+    Window,
+}
+
+/// Touchpad gesture, that can be bound to an action
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Gesture {
+    /// Fingers moving in the same direction
+    Swipe { fingers: u32, direction: Direction },
+    /// Fingers moving towards (`In`) or away from each other (`Out`)
+    Pinch {
+        fingers: u32,
+        direction: PinchDirection,
+    },
+    /// Fingers resting on the touchpad without moving
+    Hold { fingers: u32 },
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PinchDirection {
+    In,
+    Out,
+}
+
+impl Gesture {
+    pub fn fingers(&self) -> u32 {
+        match self {
+            Gesture::Swipe { fingers, .. }
+            | Gesture::Pinch { fingers, .. }
+            | Gesture::Hold { fingers } => *fingers,
+        }
+    }
+}
@@ -31,6 +31,8 @@ pub struct InputConfig {
     pub tap_config: Option<TapConfig>,
     #[serde(skip_serializing_if = "Option::is_none", default)]
     pub map_to_output: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub tablet: Option<TabletConfig>,
 }
 
 #[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
@@ -49,6 +51,28 @@ pub struct ScrollConfig {
     pub scroll_factor: Option<f64>,
 }
 
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct TabletConfig {
+    /// Part of the tablet mapped to the output, defaults to the whole tablet
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub area: Option<TabletArea>,
+    /// Crops the mapped area to the aspect ratio of the output, so drawings aren't stretched
+    #[serde(default)]
+    pub keep_aspect_ratio: bool,
+    /// Rotates the tablet by 180 degrees, for using it with the buttons on the right side
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub left_handed: Option<bool>,
+}
+
+/// Rectangle in fractions of the tablet size, with the origin in the top-left corner
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct TabletArea {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum DeviceState {
     Enabled,
@@ -1,10 +1,16 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
 use cosmic_config::{cosmic_config_derive::CosmicConfigEntry, CosmicConfigEntry};
+use cosmic_settings_config::shortcuts;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+pub mod bindings;
 pub mod input;
+pub mod output;
+pub mod security;
+pub mod snapping;
+pub mod window_rules;
 pub mod workspace;
 
 #[derive(Clone, Debug, PartialEq, CosmicConfigEntry)]
@@ -14,6 +20,8 @@ pub struct CosmicCompConfig {
     pub input_default: input::InputConfig,
     pub input_touchpad: input::InputConfig,
     pub input_devices: HashMap<String, input::InputConfig>,
+    /// Seat names by input device name, unlisted devices use their udev `WL_SEAT`
+    pub input_seats: HashMap<String, String>,
     pub xkb_config: XkbConfig,
     /// Autotiling enabled
     pub autotile: bool,
@@ -31,6 +39,30 @@ pub struct CosmicCompConfig {
     pub focus_follows_cursor_delay: u64,
     /// Let X11 applications scale themselves
     pub descale_xwayland: bool,
+    /// Rules applied to newly mapped windows, later rules take precedence
+    pub window_rules: Vec<window_rules::WindowRule>,
+    /// Privileges granted to newly connected clients
+    pub security_rules: Vec<security::SecurityRule>,
+    /// Ask the user before clients may capture the screen, and show an indicator while they do
+    pub screencopy_consent: bool,
+    /// Where built-in screenshot actions save their images
+    pub screenshot: ScreenshotConfig,
+    /// Where and how fast built-in screen recordings are taken
+    pub recording: RecordingConfig,
+    /// Named output configurations, applied by matching the connected displays
+    pub output_profiles: Vec<output::OutputProfile>,
+    /// User-defined zones to snap floating windows to
+    pub snapping_zones: snapping::SnappingZonesConfig,
+    /// Pixels floating windows are moved or resized by through keyboard actions
+    pub floating_nudge_step: u32,
+    /// Replacing the tiles of terminals with the windows launched from them
+    pub swallowing: SwallowingConfig,
+    /// Layout of server-side window headers
+    pub decorations: DecorationsConfig,
+    /// Key bindings for compositor specific actions, defaults are used if unset
+    pub bindings: Option<Vec<(shortcuts::Binding, bindings::CompAction)>>,
+    /// Touchpad gesture bindings, defaults depending on the workspace layout are used if unset
+    pub gestures: Option<Vec<(bindings::Gesture, shortcuts::Action)>>,
 }
 
 impl Default for CosmicCompConfig {
@@ -52,6 +84,7 @@ impl Default for CosmicCompConfig {
                 ..Default::default()
             },
             input_devices: Default::default(),
+            input_seats: Default::default(),
             xkb_config: Default::default(),
             autotile: Default::default(),
             autotile_behavior: Default::default(),
@@ -60,6 +93,18 @@ impl Default for CosmicCompConfig {
             cursor_follows_focus: false,
             focus_follows_cursor_delay: 250,
             descale_xwayland: false,
+            window_rules: Vec::new(),
+            security_rules: security::default_security_rules(),
+            screencopy_consent: false,
+            screenshot: Default::default(),
+            recording: Default::default(),
+            output_profiles: Vec::new(),
+            snapping_zones: Default::default(),
+            floating_nudge_step: 32,
+            swallowing: Default::default(),
+            decorations: Default::default(),
+            bindings: None,
+            gestures: None,
         }
     }
 }
@@ -98,6 +143,146 @@ impl Default for XkbConfig {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ScreenshotConfig {
+    /// Defaults to the XDG pictures directory
+    #[serde(default)]
+    pub directory: Option<String>,
+    /// `{name}` is replaced with the window title or output name,
+    /// `{timestamp}` with the local time of the screenshot
+    #[serde(default = "default_screenshot_filename")]
+    pub filename_template: String,
+}
+
+impl Default for ScreenshotConfig {
+    fn default() -> ScreenshotConfig {
+        ScreenshotConfig {
+            directory: None,
+            filename_template: default_screenshot_filename(),
+        }
+    }
+}
+
+fn default_screenshot_filename() -> String {
+    String::from("{name}_{timestamp}.png")
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct RecordingConfig {
+    /// Defaults to the XDG videos directory
+    #[serde(default)]
+    pub directory: Option<String>,
+    /// `{name}` is replaced with the recorded output, workspace or window,
+    /// `{timestamp}` with the local time the recording started
+    #[serde(default = "default_recording_filename")]
+    pub filename_template: String,
+    /// Frames per second
+    #[serde(default = "default_recording_frame_rate")]
+    pub frame_rate: u32,
+}
+
+impl Default for RecordingConfig {
+    fn default() -> RecordingConfig {
+        RecordingConfig {
+            directory: None,
+            filename_template: default_recording_filename(),
+            frame_rate: default_recording_frame_rate(),
+        }
+    }
+}
+
+fn default_recording_filename() -> String {
+    String::from("{name}_{timestamp}.apng")
+}
+
+fn default_recording_frame_rate() -> u32 {
+    30
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SwallowingConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// App ids of terminals, whose tiles may be taken over by their child processes
+    #[serde(default = "default_swallowing_terminals")]
+    pub terminals: Vec<String>,
+    /// App ids of windows, that never swallow their terminal.
+    /// Terminals launched from terminals are always exempt.
+    #[serde(default)]
+    pub exceptions: Vec<String>,
+}
+
+impl Default for SwallowingConfig {
+    fn default() -> SwallowingConfig {
+        SwallowingConfig {
+            enabled: false,
+            terminals: default_swallowing_terminals(),
+            exceptions: Vec::new(),
+        }
+    }
+}
+
+fn default_swallowing_terminals() -> Vec<String> {
+    [
+        "com.system76.CosmicTerm",
+        "Alacritty",
+        "foot",
+        "kitty",
+        "org.wezfurlong.wezterm",
+        "org.gnome.Console",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct DecorationsConfig {
+    /// Buttons left of the title, in order
+    #[serde(default)]
+    pub left_buttons: Vec<TitlebarButton>,
+    /// Buttons right of the title, in order.
+    /// Minimize and maximize are additionally hidden, if disabled in the desktop settings.
+    #[serde(default = "default_right_buttons")]
+    pub right_buttons: Vec<TitlebarButton>,
+    /// Use a smaller header height
+    #[serde(default)]
+    pub compact: bool,
+    /// Don't draw headers for tiled windows. Stacks keep their tab bar.
+    #[serde(default)]
+    pub hide_tiled_titlebars: bool,
+}
+
+impl Default for DecorationsConfig {
+    fn default() -> DecorationsConfig {
+        DecorationsConfig {
+            left_buttons: Vec::new(),
+            right_buttons: default_right_buttons(),
+            compact: false,
+            hide_tiled_titlebars: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum TitlebarButton {
+    Menu,
+    Minimize,
+    Maximize,
+    Close,
+    Sticky,
+    /// Keeps the window above other floating windows
+    AlwaysOnTop,
+}
+
+fn default_right_buttons() -> Vec<TitlebarButton> {
+    vec![
+        TitlebarButton::Minimize,
+        TitlebarButton::Maximize,
+        TitlebarButton::Close,
+    ]
+}
+
 fn default_repeat_rate() -> u32 {
     25
 }
@@ -0,0 +1,160 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use serde::{Deserialize, Serialize};
+
+/// A named set of output settings, applied when all of its outputs are connected
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutputProfile {
+    pub name: String,
+    /// Apply the profile on hotplug, when its outputs get connected.
+    /// Otherwise it is only applied through the `ApplyOutputProfile` action.
+    #[serde(default = "default_true")]
+    pub automatic: bool,
+    pub outputs: Vec<ProfileOutput>,
+}
+
+/// Settings of one output of a profile, unset fields keep their current value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileOutput {
+    #[serde(rename = "match")]
+    pub matches: OutputMatch,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Index of the output of this profile, that should be mirrored
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub mirroring: Option<usize>,
+    /// Resolution and refresh rate in mHz
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub mode: Option<((i32, i32), Option<u32>)>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub scale: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub transform: Option<OutputTransform>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub position: Option<(u32, u32)>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub vrr: Option<bool>,
+}
+
+/// Matches outputs by all of the set fields, `*` matches any sequence of characters
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputMatch {
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub make: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub serial: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub connector: Option<String>,
+}
+
+impl OutputMatch {
+    pub fn matches(&self, make: &str, model: &str, serial: Option<&str>, connector: &str) -> bool {
+        self.make
+            .as_deref()
+            .map_or(true, |pattern| wildcard_match(pattern, make))
+            && self
+                .model
+                .as_deref()
+                .map_or(true, |pattern| wildcard_match(pattern, model))
+            && self.serial.as_deref().map_or(true, |pattern| {
+                serial.is_some_and(|serial| wildcard_match(pattern, serial))
+            })
+            && self
+                .connector
+                .as_deref()
+                .map_or(true, |pattern| wildcard_match(pattern, connector))
+    }
+
+    /// Number of characters matched literally, used to prefer more specific matches
+    pub fn specificity(&self) -> usize {
+        [&self.make, &self.model, &self.serial, &self.connector]
+            .into_iter()
+            .flatten()
+            .map(|pattern| pattern.chars().filter(|c| *c != '*').count())
+            .sum()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OutputTransform {
+    Normal,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    Flipped,
+    Flipped90,
+    Flipped180,
+    Flipped270,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    // without any `*` the pattern has to match exactly
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts = parts.collect::<Vec<_>>();
+    let Some(last) = parts.pop() else {
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(idx) => rest = &rest[idx + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("DP-1", "DP-1"));
+        assert!(!wildcard_match("DP-1", "DP-10"));
+        assert!(!wildcard_match("DP-1", "eDP-1"));
+
+        assert!(wildcard_match("*", ""));
+        assert!(wildcard_match("*", "anything"));
+        assert!(wildcard_match("DP-*", "DP-1"));
+        assert!(wildcard_match("DP-*", "DP-"));
+        assert!(!wildcard_match("DP-*", "eDP-1"));
+        assert!(wildcard_match("*-1", "HDMI-A-1"));
+        assert!(!wildcard_match("*-1", "HDMI-A-2"));
+        assert!(wildcard_match("D*l*U2720Q", "Dell U2720Q"));
+        assert!(!wildcard_match("D*l*U2720Q", "Dell U2721Q"));
+    }
+
+    #[test]
+    fn test_wildcard_match_no_overlap() {
+        // prefix and suffix can't share characters
+        assert!(!wildcard_match("ab*ba", "aba"));
+        assert!(wildcard_match("ab*ba", "abba"));
+        assert!(!wildcard_match("a*a*a", "aa"));
+        assert!(wildcard_match("a*a*a", "aaa"));
+    }
+
+    #[test]
+    fn test_output_match() {
+        let matches = OutputMatch {
+            make: Some(String::from("Dell*")),
+            serial: Some(String::from("ABC*")),
+            ..Default::default()
+        };
+        assert!(matches.matches("Dell Inc.", "U2720Q", Some("ABC123"), "DP-1"));
+        assert!(!matches.matches("Dell Inc.", "U2720Q", Some("XYZ123"), "DP-1"));
+        // a serial pattern never matches outputs without one
+        assert!(!matches.matches("Dell Inc.", "U2720Q", None, "DP-1"));
+        assert_eq!(matches.specificity(), 7);
+        assert!(OutputMatch::default().matches("", "", None, "DP-1"));
+    }
+}
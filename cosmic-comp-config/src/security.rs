@@ -0,0 +1,157 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Access to privileged protocols, that clients have to be granted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Privilege {
+    /// Capturing outputs, workspaces and windows
+    Screencopy,
+    /// Listing and controlling the windows of other clients
+    ToplevelManagement,
+    /// Listing and controlling workspaces
+    Workspaces,
+    /// Changing the output configuration
+    OutputConfiguration,
+    /// Emulating keyboard input and acting as an input method
+    VirtualKeyboard,
+    /// Placing surfaces on the layer shell, e.g. panels and backgrounds
+    LayerShell,
+    /// Locking the session
+    SessionLock,
+    /// Creating activation tokens, that aren't tied to user input
+    Activation,
+}
+
+impl Privilege {
+    pub const ALL: [Privilege; 8] = [
+        Privilege::Screencopy,
+        Privilege::ToplevelManagement,
+        Privilege::Workspaces,
+        Privilege::OutputConfiguration,
+        Privilege::VirtualKeyboard,
+        Privilege::LayerShell,
+        Privilege::SessionLock,
+        Privilege::Activation,
+    ];
+}
+
+/// Grants privileges to the clients matching all of the set fields
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SecurityRule {
+    /// Sandbox engine of the client's security context
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub sandbox_engine: Option<String>,
+    /// App id of the client's security context
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub app_id: Option<String>,
+    /// Path of the client's executable, as found through its peer credentials
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub executable: Option<String>,
+    pub privileges: Vec<Privilege>,
+}
+
+impl SecurityRule {
+    pub fn matches(
+        &self,
+        sandbox_engine: Option<&str>,
+        app_id: Option<&str>,
+        executable: Option<&Path>,
+    ) -> bool {
+        // a rule without any condition must not grant privileges to everyone
+        if self.sandbox_engine.is_none() && self.app_id.is_none() && self.executable.is_none() {
+            return false;
+        }
+
+        self.sandbox_engine
+            .as_deref()
+            .map_or(true, |engine| sandbox_engine == Some(engine))
+            && self.app_id.as_deref().map_or(true, |id| app_id == Some(id))
+            && self
+                .executable
+                .as_deref()
+                .map_or(true, |path| executable == Some(Path::new(path)))
+    }
+}
+
+pub fn default_security_rules() -> Vec<SecurityRule> {
+    vec![SecurityRule {
+        sandbox_engine: Some("com.system76.CosmicPanel".into()),
+        app_id: None,
+        executable: None,
+        privileges: Privilege::ALL.to_vec(),
+    }]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn rule(
+        sandbox_engine: Option<&str>,
+        app_id: Option<&str>,
+        executable: Option<&str>,
+    ) -> SecurityRule {
+        SecurityRule {
+            sandbox_engine: sandbox_engine.map(Into::into),
+            app_id: app_id.map(Into::into),
+            executable: executable.map(Into::into),
+            privileges: vec![Privilege::Screencopy],
+        }
+    }
+
+    #[test]
+    fn test_empty_rule_matches_nothing() {
+        let rule = rule(None, None, None);
+        assert!(!rule.matches(None, None, None));
+        assert!(!rule.matches(
+            Some("flatpak"),
+            Some("org.example.App"),
+            Some(Path::new("/usr/bin/app"))
+        ));
+    }
+
+    #[test]
+    fn test_all_conditions_must_match() {
+        let rule = rule(Some("flatpak"), Some("org.example.App"), None);
+        assert!(rule.matches(Some("flatpak"), Some("org.example.App"), None));
+        assert!(rule.matches(
+            Some("flatpak"),
+            Some("org.example.App"),
+            Some(Path::new("/usr/bin/app"))
+        ));
+        assert!(!rule.matches(Some("flatpak"), Some("org.example.Other"), None));
+        assert!(!rule.matches(Some("snap"), Some("org.example.App"), None));
+        assert!(!rule.matches(None, Some("org.example.App"), None));
+    }
+
+    #[test]
+    fn test_executable() {
+        let rule = rule(None, None, Some("/usr/bin/app"));
+        assert!(rule.matches(None, None, Some(Path::new("/usr/bin/app"))));
+        assert!(rule.matches(Some("flatpak"), None, Some(Path::new("/usr/bin/app"))));
+        assert!(!rule.matches(None, None, Some(Path::new("/usr/bin/other"))));
+        // an unknown executable never matches
+        assert!(!rule.matches(None, None, None));
+    }
+
+    #[test]
+    fn test_default_rules() {
+        let rules = default_security_rules();
+        let privileges = |engine| {
+            rules
+                .iter()
+                .filter(|rule| rule.matches(engine, None, None))
+                .flat_map(|rule| rule.privileges.iter().copied())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            privileges(Some("com.system76.CosmicPanel")),
+            Privilege::ALL.to_vec()
+        );
+        assert!(privileges(Some("flatpak")).is_empty());
+        assert!(privileges(None).is_empty());
+    }
+}
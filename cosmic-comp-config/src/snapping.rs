@@ -0,0 +1,168 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Snapping zones, revealed by holding `modifier` while moving a floating window
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SnappingZonesConfig {
+    #[serde(default)]
+    pub modifier: ZoneModifier,
+    /// Layout for outputs without an entry in `outputs`
+    #[serde(default)]
+    pub default_layout: Option<ZoneLayout>,
+    /// Layouts by output connector name, e.g. `DP-1`
+    #[serde(default)]
+    pub outputs: HashMap<String, ZoneLayout>,
+}
+
+impl SnappingZonesConfig {
+    pub fn layout(&self, output: &str) -> Option<&ZoneLayout> {
+        self.outputs.get(output).or(self.default_layout.as_ref())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ZoneModifier {
+    #[default]
+    Shift,
+    Ctrl,
+    Alt,
+    Super,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ZoneLayout {
+    /// `columns` times `rows` zones of equal size
+    Grid { columns: u32, rows: u32 },
+    /// Full height columns with the given widths in percent, e.g. `[25, 50, 25]`
+    Columns(Vec<f32>),
+    /// Arbitrary, possibly overlapping zones. Earlier zones take precedence.
+    Zones(Vec<Zone>),
+}
+
+/// Rectangle in percent of the usable area of an output
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Zone {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl ZoneLayout {
+    pub fn zones(&self) -> Vec<Zone> {
+        match self {
+            ZoneLayout::Grid { columns, rows } => {
+                let (width, height) = (100. / *columns as f32, 100. / *rows as f32);
+                (0..*rows)
+                    .flat_map(|row| {
+                        (0..*columns).map(move |column| Zone {
+                            x: column as f32 * width,
+                            y: row as f32 * height,
+                            width,
+                            height,
+                        })
+                    })
+                    .collect()
+            }
+            ZoneLayout::Columns(widths) => {
+                // widths not adding up to 100 are scaled to fill the output
+                let total = widths.iter().filter(|w| **w > 0.).sum::<f32>();
+                let mut x = 0.;
+                widths
+                    .iter()
+                    .filter(|w| **w > 0.)
+                    .map(|w| {
+                        let width = w / total * 100.;
+                        let zone = Zone {
+                            x,
+                            y: 0.,
+                            width,
+                            height: 100.,
+                        };
+                        x += width;
+                        zone
+                    })
+                    .collect()
+            }
+            ZoneLayout::Zones(zones) => zones
+                .iter()
+                .filter(|zone| zone.width > 0. && zone.height > 0.)
+                .copied()
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_zone(zone: Zone, x: f32, y: f32, width: f32, height: f32) {
+        for (value, expected) in [
+            (zone.x, x),
+            (zone.y, y),
+            (zone.width, width),
+            (zone.height, height),
+        ] {
+            assert!((value - expected).abs() < 0.001, "{zone:?}");
+        }
+    }
+
+    #[test]
+    fn test_grid_zones() {
+        let zones = ZoneLayout::Grid {
+            columns: 3,
+            rows: 2,
+        }
+        .zones();
+        assert_eq!(zones.len(), 6);
+        assert_zone(zones[0], 0., 0., 33.333, 50.);
+        assert_zone(zones[2], 66.667, 0., 33.333, 50.);
+        assert_zone(zones[3], 0., 50., 33.333, 50.);
+        assert_zone(zones[5], 66.667, 50., 33.333, 50.);
+    }
+
+    #[test]
+    fn test_empty_grid() {
+        let zones = ZoneLayout::Grid {
+            columns: 0,
+            rows: 2,
+        }
+        .zones();
+        assert!(zones.is_empty());
+    }
+
+    #[test]
+    fn test_column_zones() {
+        let zones = ZoneLayout::Columns(vec![25., 50., 25.]).zones();
+        assert_eq!(zones.len(), 3);
+        assert_zone(zones[0], 0., 0., 25., 100.);
+        assert_zone(zones[1], 25., 0., 50., 100.);
+        assert_zone(zones[2], 75., 0., 25., 100.);
+    }
+
+    #[test]
+    fn test_column_zones_are_scaled() {
+        let zones = ZoneLayout::Columns(vec![1., 0., -2., 3.]).zones();
+        assert_eq!(zones.len(), 2);
+        assert_zone(zones[0], 0., 0., 25., 100.);
+        assert_zone(zones[1], 25., 0., 75., 100.);
+
+        assert!(ZoneLayout::Columns(vec![0., -1.]).zones().is_empty());
+    }
+
+    #[test]
+    fn test_empty_zones_are_skipped() {
+        let zone = |width, height| Zone {
+            x: 10.,
+            y: 10.,
+            width,
+            height,
+        };
+        let zones = ZoneLayout::Zones(vec![zone(0., 50.), zone(50., 50.), zone(50., -1.)]).zones();
+        assert_eq!(zones, vec![zone(50., 50.)]);
+    }
+}
@@ -0,0 +1,68 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use serde::{Deserialize, Serialize};
+
+// Note: For the following values, None is used to represent "not set by this rule",
+// in which case the default behavior or an earlier matching rule applies.
+/// Rule applied to newly mapped windows
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct WindowRule {
+    /// Regex matched against the app_id, matches any app_id if unset
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub appid: Option<String>,
+    /// Regex matched against the title, matches any title if unset
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub title: Option<String>,
+    /// Workspace number to open the window on, starting at 1
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub workspace: Option<u32>,
+    /// Connector name of the output to open the window on
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub output: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub floating: Option<bool>,
+    /// Initial geometry of floating windows, relative to the output
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub geometry: Option<WindowRuleGeometry>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub sticky: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub maximized: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub fullscreen: Option<bool>,
+    /// Opacity between 0.0 and 1.0
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub opacity: Option<f32>,
+    /// Don't give the window keyboard focus, when it is mapped
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub no_focus: Option<bool>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct WindowRuleGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl WindowRule {
+    /// Overrides every value of `self`, that is set in `other`
+    pub fn merge(&mut self, other: &WindowRule) {
+        fn set<T: Clone>(value: &mut Option<T>, other: &Option<T>) {
+            if other.is_some() {
+                *value = other.clone();
+            }
+        }
+
+        set(&mut self.workspace, &other.workspace);
+        set(&mut self.output, &other.output);
+        set(&mut self.floating, &other.floating);
+        set(&mut self.geometry, &other.geometry);
+        set(&mut self.sticky, &other.sticky);
+        set(&mut self.maximized, &other.maximized);
+        set(&mut self.fullscreen, &other.fullscreen);
+        set(&mut self.opacity, &other.opacity);
+        set(&mut self.no_focus, &other.no_focus);
+    }
+}
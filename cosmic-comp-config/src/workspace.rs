@@ -11,6 +11,12 @@ pub struct WorkspaceConfig {
     pub workspace_mode: WorkspaceMode,
     #[serde(default = "default_workspace_layout")]
     pub workspace_layout: WorkspaceLayout,
+    /// Workspaces that always exist, in addition to the dynamically created ones
+    #[serde(default)]
+    pub named_workspaces: Vec<NamedWorkspace>,
+    /// Algorithm used to arrange tiled windows on new workspaces
+    #[serde(default)]
+    pub tiling_algorithm: TilingAlgorithm,
 }
 
 impl Default for WorkspaceConfig {
@@ -18,6 +24,8 @@ impl Default for WorkspaceConfig {
         Self {
             workspace_mode: WorkspaceMode::OutputBound,
             workspace_layout: WorkspaceLayout::Vertical,
+            named_workspaces: Vec::new(),
+            tiling_algorithm: TilingAlgorithm::Manual,
         }
     }
 }
@@ -33,3 +41,40 @@ pub enum WorkspaceLayout {
     Vertical,
     Horizontal,
 }
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NamedWorkspace {
+    pub name: String,
+    /// Connector name of the output the workspace is pinned to,
+    /// ignored in global mode, where every output has the workspace
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub output: Option<String>,
+    /// Whether tiling is enabled initially, falls back to the autotile setting if unset
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub tiling: Option<bool>,
+    /// Orientation used when splitting the first tiled window
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub orientation: Option<TilingOrientation>,
+    /// Algorithm used to arrange tiled windows, falls back to the global setting if unset
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub algorithm: Option<TilingAlgorithm>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TilingOrientation {
+    Horizontal,
+    Vertical,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TilingAlgorithm {
+    /// Windows are arranged manually in a tree of splits
+    #[default]
+    Manual,
+    /// The first `masters` windows share `ratio` of the width, the remaining ones are stacked next to them
+    MasterStack { masters: u32, ratio: f32 },
+    /// Every window takes half of the space left by the previous one, alternating the orientation
+    Spiral,
+    /// Windows are distributed evenly over `count` columns
+    Columns { count: u32 },
+}
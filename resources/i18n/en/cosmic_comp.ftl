@@ -20,4 +20,10 @@ window-menu-close-all = Close all windows
 window-menu-resize-edge-top = Top
 window-menu-resize-edge-left = Left
 window-menu-resize-edge-right = Right
-window-menu-resize-edge-bottom = Bottom
\ No newline at end of file
+window-menu-resize-edge-bottom = Bottom
+screencopy-consent = { $app } wants to capture your screen
+screencopy-unknown-app = An unknown app
+screencopy-allow-once = Allow once
+screencopy-allow-always = Always allow
+screencopy-deny = Deny
+screencopy-indicator = Screen is being captured
@@ -0,0 +1,499 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::{
+    backend::{kms::software_renderer, render::CLEAR_COLOR},
+    config::{OutputConfig, OutputState},
+    state::{BackendData, Common},
+    utils::prelude::*,
+};
+use anyhow::{Context, Result};
+use smithay::{
+    backend::{
+        allocator::Fourcc,
+        renderer::{
+            damage::{OutputDamageTracker, RenderOutputResult},
+            element::{
+                surface::{render_elements_from_surface_tree, WaylandSurfaceRenderElement},
+                Kind,
+            },
+            glow::GlowRenderer,
+            pixman::PixmanRenderer,
+            Bind, Offscreen,
+        },
+    },
+    desktop::{layer_map_for_output, PopupManager},
+    output::{Mode, Output, PhysicalProperties, Scale, Subpixel},
+    reexports::{
+        calloop::{
+            timer::{TimeoutAction, Timer},
+            EventLoop, LoopHandle,
+        },
+        pixman::Image,
+        wayland_protocols::wp::presentation_time::server::wp_presentation_feedback,
+        wayland_server::{protocol::wl_surface::WlSurface, DisplayHandle},
+    },
+    utils::{Buffer as BufferCoords, Logical, Point, Size, Transform},
+    wayland::{seat::WaylandFocus, shell::wlr_layer::Layer},
+};
+use std::{borrow::BorrowMut, cell::RefCell, time::Duration};
+use tracing::{error, info, warn};
+
+use super::render::init_shaders;
+
+/// Virtual outputs created when `COSMIC_HEADLESS_OUTPUTS` is unset
+const DEFAULT_OUTPUTS: &str = "1920x1080";
+const REFRESH_RATE: i32 = 60_000;
+
+#[derive(Debug)]
+pub struct HeadlessState {
+    /// Renders the virtual outputs, without requiring any gpu or EGL implementation
+    pub renderer: PixmanRenderer,
+    /// Used for screen capture, if llvmpipe is available
+    pub software_renderer: Option<GlowRenderer>,
+    surfaces: Vec<Surface>,
+}
+
+#[derive(Debug)]
+pub struct Surface {
+    output: Output,
+    damage_tracker: OutputDamageTracker,
+    buffer: Option<(Image<'static, 'static>, Size<i32, BufferCoords>)>,
+    dirty: bool,
+}
+
+/// Description of a single virtual output, as parsed from `COSMIC_HEADLESS_OUTPUTS`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeadlessOutput {
+    pub size: (i32, i32),
+    pub scale: f64,
+}
+
+/// Parses a comma separated list of `<width>x<height>[@<scale>]` output descriptions
+pub fn parse_outputs(spec: &str) -> Result<Vec<HeadlessOutput>> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|desc| !desc.is_empty())
+        .map(|desc| {
+            let (size, scale) = match desc.split_once('@') {
+                Some((size, scale)) => (
+                    size,
+                    scale
+                        .parse::<f64>()
+                        .with_context(|| format!("Invalid output scale: {}", scale))?,
+                ),
+                None => (desc, 1.0),
+            };
+            let (w, h) = size
+                .split_once('x')
+                .with_context(|| format!("Invalid output size: {}", size))?;
+            let w = w
+                .parse::<i32>()
+                .with_context(|| format!("Invalid output width: {}", w))?;
+            let h = h
+                .parse::<i32>()
+                .with_context(|| format!("Invalid output height: {}", h))?;
+            anyhow::ensure!(w > 0 && h > 0, "Output size must not be empty: {}", desc);
+            anyhow::ensure!(scale > 0.0, "Output scale must be positive: {}", desc);
+            Ok(HeadlessOutput {
+                size: (w, h),
+                scale,
+            })
+        })
+        .collect::<Result<Vec<_>>>()
+        .and_then(|outputs| {
+            anyhow::ensure!(!outputs.is_empty(), "No headless outputs configured");
+            Ok(outputs)
+        })
+}
+
+impl HeadlessState {
+    pub fn add_output(
+        &mut self,
+        desc: HeadlessOutput,
+        position: (i32, i32),
+        handle: &LoopHandle<'static, State>,
+    ) -> Result<Output> {
+        let name = format!("HEADLESS-{}", self.surfaces.len());
+        let props = PhysicalProperties {
+            size: (0, 0).into(),
+            subpixel: Subpixel::Unknown,
+            make: "COSMIC".to_string(),
+            model: name.clone(),
+        };
+        let mode = Mode {
+            size: desc.size.into(),
+            refresh: REFRESH_RATE,
+        };
+        let output = Output::new(name, props);
+        output.add_mode(mode);
+        output.set_preferred(mode);
+        output.change_current_state(
+            Some(mode),
+            Some(Transform::Normal),
+            Some(Scale::Fractional(desc.scale)),
+            Some(position.into()),
+        );
+        output.user_data().insert_if_missing(|| {
+            RefCell::new(OutputConfig {
+                mode: (desc.size, Some(REFRESH_RATE as u32)),
+                scale: desc.scale,
+                position: (position.0 as u32, position.1 as u32),
+                ..Default::default()
+            })
+        });
+
+        // Drive frames at a fixed rate, as there is no display to wait for
+        let interval = Duration::from_secs_f64(1_000.0 / REFRESH_RATE as f64);
+        let output_ref = output.clone();
+        handle
+            .insert_source(Timer::from_duration(interval), move |_, _, state| {
+                let headless_state = state.backend.headless();
+                if let Some(surface) = headless_state
+                    .surfaces
+                    .iter_mut()
+                    .find(|s| s.output == output_ref)
+                {
+                    if surface.dirty {
+                        surface.dirty = false;
+                        if let Err(err) =
+                            surface.render_output(&mut headless_state.renderer, &mut state.common)
+                        {
+                            error!(?err, "Error rendering.");
+                        }
+                    }
+                    TimeoutAction::ToDuration(interval)
+                } else {
+                    TimeoutAction::Drop
+                }
+            })
+            .map_err(|_| anyhow::anyhow!("Failed to add output to event loop"))?;
+
+        self.surfaces.push(Surface {
+            damage_tracker: OutputDamageTracker::from_output(&output),
+            output: output.clone(),
+            buffer: None,
+            dirty: true,
+        });
+
+        Ok(output)
+    }
+
+    pub fn schedule_render(&mut self, output: &Output) {
+        if let Some(surface) = self.surfaces.iter_mut().find(|s| s.output == *output) {
+            surface.dirty = true;
+        }
+    }
+
+    pub fn apply_config_for_outputs(
+        &mut self,
+        test_only: bool,
+    ) -> Result<Vec<Output>, anyhow::Error> {
+        // virtual outputs can take on any mode, so there is nothing to reject
+        if !test_only {
+            for surface in &mut self.surfaces {
+                surface.dirty = true;
+            }
+        }
+        Ok(self.surfaces.iter().map(|s| s.output.clone()).collect())
+    }
+}
+
+impl Surface {
+    #[profiling::function]
+    pub fn render_output(
+        &mut self,
+        renderer: &mut PixmanRenderer,
+        state: &mut Common,
+    ) -> Result<()> {
+        let enabled = self
+            .output
+            .user_data()
+            .get::<RefCell<OutputConfig>>()
+            .map(|config| config.borrow().enabled == OutputState::Enabled)
+            .unwrap_or(true);
+        if !enabled {
+            return Ok(());
+        }
+
+        let size = self
+            .output
+            .current_mode()
+            .map(|mode| mode.size)
+            .with_context(|| "Output has no mode")?
+            .to_logical(1)
+            .to_buffer(1, Transform::Normal);
+        let age = match self.buffer.as_ref() {
+            Some((_, buffer_size)) if *buffer_size == size => 1,
+            _ => {
+                let buffer = Offscreen::<Image>::create_buffer(renderer, Fourcc::Argb8888, size)
+                    .with_context(|| "Failed to allocate offscreen buffer")?;
+                self.buffer = Some((buffer, size));
+                0
+            }
+        };
+        let buffer = self.buffer.as_ref().unwrap().0.clone();
+        renderer
+            .bind(buffer)
+            .with_context(|| "Failed to bind offscreen buffer")?;
+
+        let elements = output_elements(renderer, state, &self.output);
+        match self
+            .damage_tracker
+            .render_output(renderer, age, &elements, CLEAR_COLOR)
+        {
+            Ok(RenderOutputResult { damage, states, .. }) => {
+                state.send_frames(&self.output, None);
+                state.update_primary_output(&self.output, &states);
+                state.send_dmabuf_feedback(&self.output, &states, |_| None);
+                if damage.is_some() {
+                    let mut output_presentation_feedback = state
+                        .shell
+                        .read()
+                        .unwrap()
+                        .take_presentation_feedback(&self.output, &states);
+                    output_presentation_feedback.presented(
+                        state.clock.now(),
+                        self.output
+                            .current_mode()
+                            .map(|mode| Duration::from_secs_f64(1_000.0 / mode.refresh as f64))
+                            .unwrap_or_default(),
+                        0,
+                        wp_presentation_feedback::Kind::empty(),
+                    );
+                }
+            }
+            Err(err) => {
+                self.buffer = None;
+                anyhow::bail!("Rendering failed: {}", err);
+            }
+        };
+
+        Ok(())
+    }
+}
+
+pub fn init_backend(
+    _dh: &DisplayHandle,
+    event_loop: &mut EventLoop<State>,
+    state: &mut State,
+) -> Result<()> {
+    let outputs = parse_outputs(
+        &std::env::var("COSMIC_HEADLESS_OUTPUTS").unwrap_or_else(|_| DEFAULT_OUTPUTS.into()),
+    )
+    .context("Failed to parse COSMIC_HEADLESS_OUTPUTS")?;
+
+    let renderer = PixmanRenderer::new().context("Failed to initialize pixman renderer")?;
+    // Screen capture relies on our gles shaders, which are only available through llvmpipe
+    let software_renderer = match software_renderer().and_then(|mut renderer| {
+        init_shaders(renderer.borrow_mut())?;
+        Ok(renderer)
+    }) {
+        Ok(renderer) => Some(renderer),
+        Err(err) => {
+            warn!(?err, "No software renderer, screen capture is unavailable.");
+            None
+        }
+    };
+    info!("Initialized headless backend.");
+
+    state.backend = BackendData::Headless(HeadlessState {
+        renderer,
+        software_renderer,
+        surfaces: Vec::new(),
+    });
+
+    let handle = event_loop.handle();
+    let mut x = 0;
+    let mut added = Vec::new();
+    for desc in outputs {
+        let output = state
+            .backend
+            .headless()
+            .add_output(desc, (x, 0), &handle)
+            .with_context(|| "Failed to create wl_output")?;
+        x += (desc.size.0 as f64 / desc.scale).ceil() as i32;
+        added.push(output);
+    }
+
+    state
+        .common
+        .output_configuration_state
+        .add_heads(added.iter());
+    {
+        for output in &added {
+            state.common.add_output(output);
+        }
+        state.common.config.read_outputs(
+            &mut state.common.output_configuration_state,
+            &mut state.backend,
+            &state.common.shell,
+            &state.common.event_loop_handle,
+            &mut state.common.workspace_state.update(),
+            &state.common.xdg_activation_state,
+            state.common.startup_done.clone(),
+        );
+        state.common.refresh();
+    }
+    state.launch_xwayland(None);
+
+    Ok(())
+}
+
+/// Surfaces visible on `output`, front to back.
+///
+/// Unlike the regular render path this draws client content only, as server-side
+/// decorations and indicators rely on gles shaders.
+fn output_elements(
+    renderer: &mut PixmanRenderer,
+    state: &Common,
+    output: &Output,
+) -> Vec<WaylandSurfaceRenderElement<PixmanRenderer>> {
+    let scale = output.current_scale().fractional_scale();
+    let mut elements = Vec::new();
+
+    let layers = |elements: &mut Vec<_>, renderer: &mut PixmanRenderer, layer: Layer| {
+        let layer_map = layer_map_for_output(output);
+        for surface in layer_map.layers_on(layer).rev() {
+            if let Some(geo) = layer_map.layer_geometry(surface) {
+                elements.extend(surface_elements(
+                    renderer,
+                    surface.wl_surface(),
+                    geo.loc,
+                    scale,
+                ));
+            }
+        }
+    };
+
+    if let Some(session_lock) = state.shell.read().unwrap().session_lock.as_ref() {
+        if let Some(surface) = session_lock.surfaces.get(output) {
+            elements.extend(surface_elements(
+                renderer,
+                surface.wl_surface(),
+                (0, 0).into(),
+                scale,
+            ));
+        }
+        return elements;
+    }
+
+    layers(&mut elements, renderer, Layer::Overlay);
+    {
+        let shell = state.shell.read().unwrap();
+        let workspace = shell.active_space(output);
+        if let Some(fullscreen) = workspace.fullscreen.as_ref() {
+            if let Some(surface) = fullscreen.surface.wl_surface() {
+                elements.extend(surface_elements(
+                    renderer,
+                    &surface,
+                    Point::from((0, 0)) - fullscreen.surface.geometry().loc,
+                    scale,
+                ));
+            }
+            return elements;
+        }
+
+        // panels are drawn above windows, unless a window is fullscreen
+        layers(&mut elements, renderer, Layer::Top);
+
+        let sticky = shell
+            .workspaces
+            .sets
+            .get(output)
+            .into_iter()
+            .flat_map(|set| {
+                set.sticky_layer
+                    .mapped()
+                    .filter_map(|mapped| Some((mapped, set.sticky_layer.element_geometry(mapped)?)))
+            });
+        let mapped = workspace
+            .mapped()
+            .filter_map(|mapped| Some((mapped, workspace.element_geometry(mapped)?)));
+        for (mapped, geo) in sticky.chain(mapped).collect::<Vec<_>>() {
+            if mapped.is_minimized() {
+                continue;
+            }
+            let window = mapped.active_window();
+            let Some(surface) = window.wl_surface() else {
+                continue;
+            };
+            let location = geo.loc.as_logical() - mapped.geometry().loc
+                + mapped.active_window_offset()
+                - window.geometry().loc;
+            elements.extend(surface_elements(renderer, &surface, location, scale));
+        }
+    }
+    layers(&mut elements, renderer, Layer::Bottom);
+    layers(&mut elements, renderer, Layer::Background);
+
+    elements
+}
+
+fn surface_elements(
+    renderer: &mut PixmanRenderer,
+    surface: &WlSurface,
+    location: Point<i32, Logical>,
+    scale: f64,
+) -> Vec<WaylandSurfaceRenderElement<PixmanRenderer>> {
+    let scale = smithay::utils::Scale::from(scale);
+    let location = location.to_physical_precise_round(scale);
+
+    let mut elements = PopupManager::popups_for_surface(surface)
+        .flat_map(|(popup, popup_offset)| {
+            let offset = (popup_offset - popup.geometry().loc)
+                .to_f64()
+                .to_physical(scale)
+                .to_i32_round();
+            render_elements_from_surface_tree(
+                renderer,
+                popup.wl_surface(),
+                location + offset,
+                scale,
+                1.0,
+                Kind::Unspecified,
+            )
+        })
+        .collect::<Vec<_>>();
+    elements.extend(render_elements_from_surface_tree(
+        renderer,
+        surface,
+        location,
+        scale,
+        1.0,
+        Kind::Unspecified,
+    ));
+    elements
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_outputs, HeadlessOutput};
+
+    #[test]
+    fn test_parse_outputs() {
+        assert_eq!(
+            parse_outputs("1920x1080").unwrap(),
+            vec![HeadlessOutput {
+                size: (1920, 1080),
+                scale: 1.0
+            }]
+        );
+        assert_eq!(
+            parse_outputs("1920x1080, 2560x1440@1.5,").unwrap(),
+            vec![
+                HeadlessOutput {
+                    size: (1920, 1080),
+                    scale: 1.0
+                },
+                HeadlessOutput {
+                    size: (2560, 1440),
+                    scale: 1.5
+                }
+            ]
+        );
+        assert!(parse_outputs("").is_err());
+        assert!(parse_outputs("1920").is_err());
+        assert!(parse_outputs("0x1080").is_err());
+        assert!(parse_outputs("1920x1080@0").is_err());
+    }
+}
@@ -268,6 +268,7 @@ impl State {
             self.common.startup_done.clone(),
         );
         self.common.refresh();
+        self.update_lid_state();
 
         Ok(())
     }
@@ -374,6 +375,7 @@ impl State {
                 self.common.remove_output(&output);
             }
             self.common.refresh();
+            self.update_lid_state();
         }
 
         Ok(())
@@ -419,6 +421,7 @@ impl State {
                 self.common.startup_done.clone(),
             );
             self.common.refresh();
+            self.update_lid_state();
         } else {
             self.common.output_configuration_state.update();
         }
@@ -584,7 +587,7 @@ fn create_output_for_conn(drm: &mut DrmDevice, conn: connector::Handle) -> Resul
     let edid_info = drm_helpers::edid_info(drm, conn);
     let (phys_w, phys_h) = conn_info.size().unwrap_or((0, 0));
 
-    Ok(Output::new(
+    let output = Output::new(
         interface,
         PhysicalProperties {
             size: (phys_w as i32, phys_h as i32).into(),
@@ -605,7 +608,12 @@ fn create_output_for_conn(drm: &mut DrmDevice, conn: connector::Handle) -> Resul
                 .map(|info| info.model.clone())
                 .unwrap_or_else(|_| String::from("Unknown")),
         },
-    ))
+    );
+    if let Some(serial) = edid_info.ok().and_then(|info| info.serial) {
+        output.set_serial_number(serial);
+    }
+
+    Ok(output)
 }
 
 fn populate_modes(
@@ -169,6 +169,7 @@ pub fn interface_name(device: &impl ControlDevice, connector: connector::Handle)
 pub struct EdidInfo {
     pub model: String,
     pub manufacturer: String,
+    pub serial: Option<String>,
 }
 
 pub fn edid_info(device: &impl ControlDevice, connector: connector::Handle) -> Result<EdidInfo> {
@@ -178,6 +179,7 @@ pub fn edid_info(device: &impl ControlDevice, connector: connector::Handle) -> R
     let edid_info = device.get_property(edid_prop)?;
     let mut manufacturer = "Unknown".into();
     let mut model = "Unknown".into();
+    let mut serial = None;
     let props = device.get_properties(connector)?;
     let (ids, vals) = props.as_props_and_values();
     for (&id, &val) in ids.iter().zip(vals.iter()) {
@@ -209,6 +211,22 @@ pub fn edid_info(device: &impl ControlDevice, connector: connector::Handle) -> R
                     } else {
                         format!("{}", edid.product.product_code)
                     };
+                    serial = if let Some(MonitorDescriptor::SerialNumber(serial)) = edid
+                        .descriptors
+                        .0
+                        .iter()
+                        .find(|x| matches!(x, MonitorDescriptor::SerialNumber(_)))
+                    {
+                        let mut serial = serial.clone();
+                        if let Some(idx) = serial.find('\0') {
+                            serial.truncate(idx);
+                        }
+                        Some(serial.trim().to_string())
+                    } else if edid.product.serial_number != 0 {
+                        Some(format!("{}", edid.product.serial_number))
+                    } else {
+                        None
+                    };
                 }
             }
             break;
@@ -218,6 +236,7 @@ pub fn edid_info(device: &impl ControlDevice, connector: connector::Handle) -> R
     Ok(EdidInfo {
         model,
         manufacturer,
+        serial,
     })
 }
 
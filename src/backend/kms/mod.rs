@@ -137,6 +137,7 @@ pub fn init_backend(
             warn!("Failed to add device {}: {:?}", path.display(), err);
         }
     }
+    state.refresh_lid_state();
 
     Ok(())
 }
@@ -209,7 +210,7 @@ fn determine_primary_gpu(seat: String) -> Option<DrmNode> {
 }
 
 /// Create `GlowRenderer` for `EGL_MESA_device_software` device, if present
-fn software_renderer() -> anyhow::Result<GlowRenderer> {
+pub(crate) fn software_renderer() -> anyhow::Result<GlowRenderer> {
     let mut devices = EGLDevice::enumerate()?;
     let device = devices
         .find(|device| {
@@ -317,6 +318,7 @@ impl State {
                 state.common.startup_done.clone(),
             );
             state.common.refresh();
+            state.refresh_lid_state();
         });
         loop_signal.wakeup();
     }
@@ -714,6 +716,9 @@ impl KmsState {
                 if !test_only {
                     if mirrored_output != surface.output.mirroring() {
                         surface.set_mirroring(mirrored_output.clone());
+                    } else if mirrored_output.is_some() {
+                        // the mirroring mode might have changed
+                        surface.schedule_render();
                     }
                 }
             }
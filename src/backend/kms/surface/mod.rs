@@ -5,6 +5,7 @@ use crate::{
         element::{CosmicElement, DamageElement},
         init_shaders, workspace_elements, CursorMode, ElementFilter, GlMultiRenderer, CLEAR_COLOR,
     },
+    config::MirroringMode,
     shell::Shell,
     state::SurfaceDmabufFeedback,
     utils::{prelude::*, quirks::workspace_overview_is_open},
@@ -64,7 +65,9 @@ use smithay::{
         },
         wayland_server::protocol::wl_surface::WlSurface,
     },
-    utils::{Buffer as BufferCoords, Clock, Monotonic, Physical, Rectangle, Size, Transform},
+    utils::{
+        Buffer as BufferCoords, Clock, Monotonic, Physical, Point, Rectangle, Size, Transform,
+    },
     wayland::{
         dmabuf::{get_dmabuf, DmabufFeedbackBuilder},
         seat::WaylandFocus,
@@ -181,6 +184,38 @@ impl MirroringState {
     }
 }
 
+/// Area and scaling to fit the mirrored output of size `src` onto a target of size `dst`
+fn mirroring_constraints(
+    mode: MirroringMode,
+    src: Size<i32, Physical>,
+    dst: Size<i32, Physical>,
+) -> (Rectangle<i32, Physical>, ConstrainScaleBehavior) {
+    let full = Rectangle::from_loc_and_size((0, 0), dst);
+    match mode {
+        MirroringMode::Letterbox => (full, ConstrainScaleBehavior::Fit),
+        MirroringMode::Stretch => (full, ConstrainScaleBehavior::Stretch),
+        MirroringMode::Crop => (full, ConstrainScaleBehavior::Zoom),
+        MirroringMode::IntegerScale => {
+            if src.w <= 0 || src.h <= 0 || dst.w <= 0 || dst.h <= 0 {
+                return (full, ConstrainScaleBehavior::Fit);
+            }
+            let factor = (dst.w / src.w).min(dst.h / src.h);
+            let size = if factor >= 1 {
+                Size::from((src.w * factor, src.h * factor))
+            } else {
+                // shrink by an integer divisor instead
+                let divisor = ((src.w + dst.w - 1) / dst.w).max((src.h + dst.h - 1) / dst.h);
+                Size::from((src.w / divisor, src.h / divisor))
+            };
+            let loc = Point::from(((dst.w - size.w) / 2, (dst.h - size.h) / 2));
+            (
+                Rectangle::from_loc_and_size(loc, size),
+                ConstrainScaleBehavior::Fit,
+            )
+        }
+    }
+}
+
 pub type GbmDrmCompositor = DrmCompositor<
     GbmAllocator<DrmDeviceFd>,
     GbmDevice<DrmDeviceFd>,
@@ -1033,21 +1068,23 @@ impl SurfaceThreadState {
                 Kind::Unspecified,
             );
             let texture_geometry = texture_elem.geometry(1.0.into());
+            let (constrain, behavior) = mirroring_constraints(
+                self.output.mirroring_mode(),
+                texture_geometry.size,
+                self.output
+                    .geometry()
+                    .size
+                    .as_logical()
+                    .to_f64()
+                    .to_physical(self.output.current_scale().fractional_scale())
+                    .to_i32_round(),
+            );
             elements = constrain_render_elements(
                 std::iter::once(texture_elem),
                 (0, 0),
-                Rectangle::from_loc_and_size(
-                    (0, 0),
-                    self.output
-                        .geometry()
-                        .size
-                        .as_logical()
-                        .to_f64()
-                        .to_physical(self.output.current_scale().fractional_scale())
-                        .to_i32_round(),
-                ),
+                constrain,
                 texture_geometry,
-                ConstrainScaleBehavior::Fit,
+                behavior,
                 ConstrainAlign::CENTER,
                 1.0,
             )
@@ -1428,3 +1465,63 @@ fn get_surface_dmabuf_feedback(
         scanout_feedback,
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn constraints(
+        mode: MirroringMode,
+        src: (i32, i32),
+        dst: (i32, i32),
+    ) -> (Rectangle<i32, Physical>, ConstrainScaleBehavior) {
+        mirroring_constraints(mode, Size::from(src), Size::from(dst))
+    }
+
+    #[test]
+    fn test_scaling_modes_use_whole_target() {
+        let full = Rectangle::from_loc_and_size((0, 0), (2560, 1440));
+        let (rect, behavior) = constraints(MirroringMode::Letterbox, (1920, 1200), (2560, 1440));
+        assert_eq!(rect, full);
+        assert!(matches!(behavior, ConstrainScaleBehavior::Fit));
+        let (rect, behavior) = constraints(MirroringMode::Stretch, (1920, 1200), (2560, 1440));
+        assert_eq!(rect, full);
+        assert!(matches!(behavior, ConstrainScaleBehavior::Stretch));
+        let (rect, behavior) = constraints(MirroringMode::Crop, (1920, 1200), (2560, 1440));
+        assert_eq!(rect, full);
+        assert!(matches!(behavior, ConstrainScaleBehavior::Zoom));
+    }
+
+    #[test]
+    fn test_integer_scale_up() {
+        let (rect, _) = constraints(MirroringMode::IntegerScale, (1280, 720), (3840, 2160));
+        assert_eq!(rect, Rectangle::from_loc_and_size((0, 0), (3840, 2160)));
+
+        // 1.33x only fits once, centered
+        let (rect, _) = constraints(MirroringMode::IntegerScale, (1920, 1080), (2560, 1440));
+        assert_eq!(rect, Rectangle::from_loc_and_size((320, 180), (1920, 1080)));
+
+        // limited by the height
+        let (rect, _) = constraints(MirroringMode::IntegerScale, (800, 600), (3440, 1440));
+        assert_eq!(rect, Rectangle::from_loc_and_size((920, 120), (1600, 1200)));
+    }
+
+    #[test]
+    fn test_integer_scale_down() {
+        let (rect, _) = constraints(MirroringMode::IntegerScale, (3840, 2160), (1920, 1080));
+        assert_eq!(rect, Rectangle::from_loc_and_size((0, 0), (1920, 1080)));
+
+        // halved, as 1/1.33 isn't an integer divisor
+        let (rect, _) = constraints(MirroringMode::IntegerScale, (2560, 1440), (1920, 1080));
+        assert_eq!(rect, Rectangle::from_loc_and_size((320, 180), (1280, 720)));
+    }
+
+    #[test]
+    fn test_integer_scale_empty() {
+        let (rect, behavior) = constraints(MirroringMode::IntegerScale, (0, 0), (1920, 1080));
+        assert_eq!(rect, Rectangle::from_loc_and_size((0, 0), (1920, 1080)));
+        assert!(matches!(behavior, ConstrainScaleBehavior::Fit));
+        let (rect, _) = constraints(MirroringMode::IntegerScale, (1920, 1080), (0, 0));
+        assert_eq!(rect, Rectangle::from_loc_and_size((0, 0), (0, 0)));
+    }
+}
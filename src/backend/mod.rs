@@ -7,6 +7,7 @@ use tracing::{info, warn};
 
 pub mod render;
 
+pub mod headless;
 pub mod kms;
 pub mod winit;
 pub mod x11;
@@ -22,6 +23,7 @@ pub fn init_backend_auto(
         Ok(x) if x == "x11" => x11::init_backend(dh, event_loop, state),
         Ok(x) if x == "winit" => winit::init_backend(dh, event_loop, state),
         Ok(x) if x == "kms" => kms::init_backend(dh, event_loop, state),
+        Ok(x) if x == "headless" => headless::init_backend(dh, event_loop, state),
         Ok(_) => unimplemented!("There is no backend with this identifier"),
         Err(_) => {
             if std::env::var_os("DISPLAY").is_some()
@@ -56,7 +58,7 @@ pub fn init_backend_auto(
             &mut state.common.seat_state,
             &output,
             &state.common.config,
-            "seat-0".into(),
+            crate::shell::DEFAULT_SEAT_NAME.into(),
         );
         state
             .common
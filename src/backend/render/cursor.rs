@@ -4,6 +4,7 @@ use crate::utils::prelude::*;
 use smithay::{
     backend::{
         allocator::Fourcc,
+        input::TabletToolDescriptor,
         renderer::{
             element::{
                 memory::{MemoryRenderBuffer, MemoryRenderBufferRenderElement},
@@ -24,7 +25,11 @@ use smithay::{
     },
     wayland::compositor::{get_role, with_states},
 };
-use std::{collections::HashMap, io::Read, sync::Mutex};
+use std::{
+    collections::HashMap,
+    io::Read,
+    sync::{Arc, Mutex},
+};
 use tracing::warn;
 use xcursor::{
     parser::{parse_xcursor, Image},
@@ -191,6 +196,76 @@ where
     )
 }
 
+/// Cursors of a seat's tablet tools.
+///
+/// While any tool is in proximity, these are drawn instead of the pointer cursor.
+/// Tools keep their entry after leaving proximity, so their cursor state doesn't have to be reloaded.
+#[derive(Default)]
+pub struct TabletToolCursors(Mutex<HashMap<TabletToolDescriptor, TabletToolCursor>>);
+
+#[derive(Clone)]
+pub struct TabletToolCursor {
+    pub location: Point<f64, Global>,
+    pub image: CursorImageStatus,
+    in_proximity: bool,
+    state: Arc<CursorState>,
+}
+
+impl TabletToolCursors {
+    pub fn update_location(&self, tool: &TabletToolDescriptor, location: Point<f64, Global>) {
+        let mut cursors = self.0.lock().unwrap();
+        let cursor = cursors
+            .entry(tool.clone())
+            .or_insert_with(|| TabletToolCursor {
+                location,
+                image: CursorImageStatus::default_named(),
+                in_proximity: true,
+                state: Arc::new(CursorState::default()),
+            });
+        if !cursor.in_proximity {
+            cursor.image = CursorImageStatus::default_named();
+            cursor.in_proximity = true;
+        }
+        cursor.location = location;
+    }
+
+    /// Sets the image of the tool, returns `false` if the tool isn't in proximity
+    pub fn set_image(&self, tool: &TabletToolDescriptor, image: CursorImageStatus) -> bool {
+        match self.0.lock().unwrap().get_mut(tool) {
+            Some(cursor) if cursor.in_proximity => {
+                cursor.image = image;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn remove(&self, tool: &TabletToolDescriptor) {
+        if let Some(cursor) = self.0.lock().unwrap().get_mut(tool) {
+            cursor.in_proximity = false;
+        }
+    }
+
+    /// Cursors of the tools currently in proximity
+    pub fn cursors(&self) -> Vec<TabletToolCursor> {
+        self.0
+            .lock()
+            .unwrap()
+            .values_mut()
+            .filter(|cursor| cursor.in_proximity)
+            .map(|cursor| {
+                // reset the cursor if the surface is no longer alive
+                if let CursorImageStatus::Surface(ref surface) = cursor.image {
+                    if !surface.alive() {
+                        cursor.image = CursorImageStatus::default_named();
+                    }
+                }
+                cursor.clone()
+            })
+            .collect()
+    }
+}
+
 pub type CursorState = Mutex<CursorStateInner>;
 pub struct CursorStateInner {
     current_cursor: Option<CursorIcon>,
@@ -283,6 +358,64 @@ where
         CursorImageStatus::Named(named_cursor) => Some(named_cursor),
         _ => None,
     });
+    draw_cursor_image(
+        renderer,
+        state,
+        named_cursor,
+        &cursor_status,
+        location,
+        scale,
+        time,
+        draw_default,
+    )
+}
+
+/// Draws the cursor of a tablet tool, as set by the client the tool is hovering
+#[profiling::function]
+pub fn draw_tablet_tool_cursor<R>(
+    renderer: &mut R,
+    cursor: &TabletToolCursor,
+    location: Point<f64, Logical>,
+    scale: Scale<f64>,
+    time: Time<Monotonic>,
+    draw_default: bool,
+) -> Vec<(CursorRenderElement<R>, Point<i32, BufferCoords>)>
+where
+    R: Renderer + ImportMem + ImportAll,
+    <R as Renderer>::TextureId: Send + Clone + 'static,
+{
+    let mut state_ref = cursor.state.lock().unwrap();
+
+    let named_cursor = match cursor.image {
+        CursorImageStatus::Named(named_cursor) => Some(named_cursor),
+        _ => None,
+    };
+    draw_cursor_image(
+        renderer,
+        &mut state_ref,
+        named_cursor,
+        &cursor.image,
+        location,
+        scale,
+        time,
+        draw_default,
+    )
+}
+
+fn draw_cursor_image<R>(
+    renderer: &mut R,
+    state: &mut CursorStateInner,
+    named_cursor: Option<CursorIcon>,
+    cursor_status: &CursorImageStatus,
+    location: Point<f64, Logical>,
+    scale: Scale<f64>,
+    time: Time<Monotonic>,
+    draw_default: bool,
+) -> Vec<(CursorRenderElement<R>, Point<i32, BufferCoords>)>
+where
+    R: Renderer + ImportMem + ImportAll,
+    <R as Renderer>::TextureId: Send + Clone + 'static,
+{
     if let Some(current_cursor) = named_cursor {
         if !draw_default && current_cursor == CursorIcon::Default {
             return Vec::new();
@@ -332,7 +465,7 @@ where
             ),
             hotspot,
         )];
-    } else if let CursorImageStatus::Surface(ref wl_surface) = cursor_status {
+    } else if let CursorImageStatus::Surface(wl_surface) = cursor_status {
         return draw_surface_cursor(renderer, wl_surface, location.to_i32_round(), scale);
     } else {
         Vec::new()
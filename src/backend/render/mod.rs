@@ -15,7 +15,7 @@ use crate::{
     shell::{
         element::CosmicMappedKey,
         focus::target::WindowGroup,
-        grabs::{SeatMenuGrabState, SeatMoveGrabState},
+        grabs::{SeatMenuGrabState, SeatMoveGrabState, SeatRegionGrabState},
         layout::tiling::ANIMATION_DURATION,
         CosmicMappedRenderElement, OverviewMode, SeatExt, SessionLock, Trigger, WorkspaceDelta,
         WorkspaceRenderElement,
@@ -60,7 +60,7 @@ use smithay::{
             TextureFilter,
         },
     },
-    desktop::{layer_map_for_output, PopupManager},
+    desktop::{layer_map_for_output, space::SpaceElement, PopupManager},
     input::Seat,
     output::{Output, OutputNoMode},
     utils::{IsAlive, Logical, Monotonic, Physical, Point, Rectangle, Scale, Time, Transform},
@@ -430,7 +430,8 @@ where
         let location = pointer.current_location() - output.current_location().to_f64();
 
         if mode != CursorMode::None {
-            elements.extend(
+            let tool_cursors = seat.tablet_tool_cursors().cursors();
+            let cursors = if tool_cursors.is_empty() {
                 cursor::draw_cursor(
                     renderer,
                     &seat,
@@ -439,15 +440,28 @@ where
                     now,
                     mode != CursorMode::NotDefault,
                 )
-                .into_iter()
-                .map(|(elem, hotspot)| {
-                    CosmicElement::Cursor(RelocateRenderElement::from_element(
-                        elem,
-                        Point::from((-hotspot.x, -hotspot.y)),
-                        Relocate::Relative,
-                    ))
-                }),
-            );
+            } else {
+                tool_cursors
+                    .iter()
+                    .flat_map(|tool_cursor| {
+                        cursor::draw_tablet_tool_cursor(
+                            renderer,
+                            tool_cursor,
+                            tool_cursor.location.as_logical() - output.current_location().to_f64(),
+                            scale.into(),
+                            now,
+                            mode != CursorMode::NotDefault,
+                        )
+                    })
+                    .collect()
+            };
+            elements.extend(cursors.into_iter().map(|(elem, hotspot)| {
+                CosmicElement::Cursor(RelocateRenderElement::from_element(
+                    elem,
+                    Point::from((-hotspot.x, -hotspot.y)),
+                    Relocate::Relative,
+                ))
+            }));
         }
 
         if !exclude_dnd_icon {
@@ -484,6 +498,18 @@ where
         {
             elements.extend(grab_elements.into_iter().map(Into::into));
         }
+
+        if let Some(grab_elements) = seat
+            .user_data()
+            .get::<SeatRegionGrabState>()
+            .unwrap()
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|state| state.render::<CosmicMappedRenderElement<R>, R>(renderer, output))
+        {
+            elements.extend(grab_elements.into_iter().map(Into::into));
+        }
     }
 
     elements
@@ -622,6 +648,28 @@ where
 
     let shell = shell.read().unwrap();
 
+    // keep the capture indicator above everything, including the lock screen,
+    // but out of the captured images themselves
+    if element_filter == ElementFilter::All {
+        if let Some(indicator) = shell.capture_indicator() {
+            let scale = output.current_scale().fractional_scale();
+            let size = indicator.bbox().size;
+            let location =
+                Point::<i32, Logical>::from(((output.geometry().size.w - size.w) / 2, 8));
+            elements.p_elements.extend(
+                indicator
+                    .render_elements::<CosmicMappedRenderElement<R>>(
+                        renderer,
+                        location.to_physical_precise_round(scale),
+                        scale.into(),
+                        1.0,
+                    )
+                    .into_iter()
+                    .map(Into::into),
+            );
+        }
+    }
+
     // If session locked, only show session lock surfaces
     if let Some(session_lock) = &shell.session_lock {
         elements.p_elements.extend(
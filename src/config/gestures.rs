@@ -0,0 +1,45 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+pub use cosmic_comp_config::bindings::{Gesture, PinchDirection};
+use cosmic_comp_config::workspace::WorkspaceLayout;
+use cosmic_settings_config::shortcuts::{self, action::Direction};
+
+/// Looks up the action bound to `gesture`
+pub fn gesture_action(
+    bindings: &[(Gesture, shortcuts::Action)],
+    gesture: Gesture,
+) -> Option<shortcuts::Action> {
+    bindings
+        .iter()
+        .find(|(g, _)| *g == gesture)
+        .map(|(_, action)| action.clone())
+}
+
+/// Gesture bindings used, if none are configured.
+///
+/// Workspace switching directions are given for natural scrolling and get inverted otherwise.
+pub fn default_gesture_bindings(
+    workspace_layout: WorkspaceLayout,
+) -> Vec<(Gesture, shortcuts::Action)> {
+    let (next, previous) = match workspace_layout {
+        WorkspaceLayout::Horizontal => (Direction::Left, Direction::Right),
+        WorkspaceLayout::Vertical => (Direction::Up, Direction::Down),
+    };
+
+    vec![
+        (
+            Gesture::Swipe {
+                fingers: 4,
+                direction: next,
+            },
+            shortcuts::Action::NextWorkspace,
+        ),
+        (
+            Gesture::Swipe {
+                fingers: 4,
+                direction: previous,
+            },
+            shortcuts::Action::PreviousWorkspace,
+        ),
+    ]
+}
@@ -1,5 +1,5 @@
 use smithay::reexports::input::{
-    Device as InputDevice, DeviceConfigError, ScrollMethod, SendEventsMode,
+    Device as InputDevice, DeviceCapability, DeviceConfigError, ScrollMethod, SendEventsMode,
 };
 use tracing::warn;
 
@@ -80,6 +80,7 @@ pub fn for_device(device: &InputDevice) -> InputConfig {
             None
         },
         map_to_output: None,
+        tablet: None,
     }
 }
 
@@ -168,7 +169,13 @@ pub fn update_device(
             config_set_error(device, "disable-while-typing", dwt, err, is_default);
         }
     }
-    if let Some((left, is_default)) = config!(|x| x.left_handed) {
+    // a tablet is held independently from the mouse, so it doesn't follow the general setting
+    let left_handed = if device.has_capability(DeviceCapability::TabletTool) {
+        config!(|x| x.tablet.as_ref().and_then(|tablet| tablet.left_handed))
+    } else {
+        config!(|x| x.left_handed)
+    };
+    if let Some((left, is_default)) = left_handed {
         if let Err(err) = device.config_left_handed_set(left) {
             config_set_error(device, "left-handed", left, err, is_default);
         }
@@ -1,15 +1,20 @@
+pub use cosmic_comp_config::bindings::{
+    CompAction, RecordingTarget, ScreenshotTarget, SnapPosition,
+};
 use cosmic_comp_config::workspace::WorkspaceLayout;
 use cosmic_settings_config::shortcuts::State as KeyState;
-use cosmic_settings_config::shortcuts::{self, Modifiers, Shortcuts};
+use cosmic_settings_config::shortcuts::{self, Binding, Modifiers, Shortcuts};
 use smithay::input::keyboard::ModifiersState;
 use xkbcommon::xkb;
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Action {
     /// Behaviors managed internally by cosmic-comp.
     Private(PrivateAction),
     /// Behaviors managed via cosmic-settings.
     Shortcut(shortcuts::Action),
+    /// Behaviors specific to cosmic-comp, bound via `com.system76.CosmicComp`.
+    Comp(CompAction),
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -23,6 +28,28 @@ pub enum PrivateAction {
     ),
 }
 
+/// Bindings for [`CompAction`]s used, if none are configured.
+pub fn default_comp_bindings() -> Vec<(Binding, CompAction)> {
+    vec![
+        (
+            Binding {
+                modifiers: Modifiers::new().logo().shift(),
+                key: Some(xkb::Keysym::minus),
+                description: None,
+            },
+            CompAction::MoveToScratchpad,
+        ),
+        (
+            Binding {
+                modifiers: Modifiers::new().logo(),
+                key: Some(xkb::Keysym::minus),
+                description: None,
+            },
+            CompAction::ToggleScratchpad,
+        ),
+    ]
+}
+
 pub fn add_default_bindings(shortcuts: &mut Shortcuts, workspace_layout: WorkspaceLayout) {
     let (
         workspace_previous,
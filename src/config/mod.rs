@@ -1,10 +1,13 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
 use crate::{
-    shell::Shell,
+    shell::{restore::SessionState, Shell},
     state::{BackendData, State},
-    wayland::protocols::{
-        output_configuration::OutputConfigurationState, workspace::WorkspaceUpdateGuard,
+    wayland::{
+        handlers::screencopy::ScreencopyPermissions,
+        protocols::{
+            output_configuration::OutputConfigurationState, workspace::WorkspaceUpdateGuard,
+        },
     },
 };
 use cosmic_config::{ConfigGet, CosmicConfigEntry};
@@ -26,22 +29,35 @@ pub use smithay::{
     utils::{Logical, Physical, Point, Size, Transform},
 };
 use std::{
+    borrow::Cow,
     cell::RefCell,
     collections::{BTreeMap, HashMap},
     fs::OpenOptions,
     path::PathBuf,
     sync::{atomic::AtomicBool, Arc, RwLock},
 };
-use tracing::{error, warn};
+use tracing::{error, info, warn};
 
+pub mod gestures;
 mod input_config;
 pub mod key_bindings;
-pub use key_bindings::{Action, PrivateAction};
+mod output_profiles;
+pub use gestures::{Gesture, PinchDirection};
+pub use key_bindings::{
+    Action, CompAction, PrivateAction, RecordingTarget, ScreenshotTarget, SnapPosition,
+};
 mod types;
 pub use self::types::*;
 use cosmic::config::CosmicTk;
 use cosmic_comp_config::{
-    input::InputConfig, workspace::WorkspaceConfig, CosmicCompConfig, TileBehavior, XkbConfig,
+    input::{InputConfig, TabletConfig},
+    output::OutputProfile,
+    security::SecurityRule,
+    snapping::SnappingZonesConfig,
+    window_rules::WindowRule,
+    workspace::{WorkspaceConfig, WorkspaceLayout},
+    CosmicCompConfig, DecorationsConfig, RecordingConfig, ScreenshotConfig, SwallowingConfig,
+    TileBehavior, XkbConfig,
 };
 
 #[derive(Debug)]
@@ -58,11 +74,18 @@ pub struct Config {
     pub tiling_exceptions: Vec<ApplicationException>,
     /// System actions from `com.system76.CosmicSettings.Shortcuts`
     pub system_actions: BTreeMap<shortcuts::action::System, String>,
+    /// Output profile selected through `CompAction::ApplyOutputProfile`
+    pub active_output_profile: Option<String>,
+    /// Profile last applied and the outputs it was applied to, so it isn't reapplied over
+    /// manual changes, until the connected outputs change
+    pub applied_output_profile: Option<(String, Vec<OutputInfo>)>,
 }
 
 #[derive(Debug)]
 pub struct DynamicConfig {
     outputs: (Option<PathBuf>, OutputsConfig),
+    session: (Option<PathBuf>, SessionState),
+    screencopy: (Option<PathBuf>, ScreencopyPermissions),
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -98,6 +121,21 @@ pub enum OutputState {
     Mirroring(String),
 }
 
+/// How a mirrored output is fit onto a target with a different resolution
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MirroringMode {
+    /// Scale to fit, keeping the aspect ratio and filling the rest with black bars
+    #[default]
+    Letterbox,
+    /// Scale to fill the whole target, ignoring the aspect ratio
+    Stretch,
+    /// Scale to fill the whole target, keeping the aspect ratio and cutting off the edges
+    Crop,
+    /// Scale by the largest integer factor that fits, centered on the target
+    IntegerScale,
+}
+
 fn default_enabled() -> OutputState {
     OutputState::Enabled
 }
@@ -114,6 +152,8 @@ pub struct OutputConfig {
     pub enabled: OutputState,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub max_bpc: Option<u32>,
+    #[serde(default)]
+    pub mirroring_mode: MirroringMode,
 }
 
 impl Default for OutputConfig {
@@ -126,6 +166,7 @@ impl Default for OutputConfig {
             position: (0, 0),
             enabled: OutputState::Enabled,
             max_bpc: None,
+            mirroring_mode: MirroringMode::default(),
         }
     }
 }
@@ -290,6 +331,8 @@ impl Config {
             shortcuts,
             system_actions,
             tiling_exceptions,
+            active_output_profile: None,
+            applied_output_profile: None,
         }
     }
 
@@ -297,10 +340,42 @@ impl Config {
         let output_path =
             xdg.and_then(|base| base.place_state_file("cosmic-comp/outputs.ron").ok());
         let outputs = Self::load_outputs(&output_path);
+        let session_path =
+            xdg.and_then(|base| base.place_state_file("cosmic-comp/session.ron").ok());
+        let session = Self::load_state::<SessionState>(&session_path, "session state");
+        let screencopy_path =
+            xdg.and_then(|base| base.place_state_file("cosmic-comp/screencopy.ron").ok());
+        let screencopy =
+            Self::load_state::<ScreencopyPermissions>(&screencopy_path, "screencopy permissions");
 
         DynamicConfig {
             outputs: (output_path, outputs),
+            session: (session_path, session),
+            screencopy: (screencopy_path, screencopy),
+        }
+    }
+
+    fn load_state<T: Default + serde::de::DeserializeOwned>(
+        path: &Option<PathBuf>,
+        name: &str,
+    ) -> T {
+        if let Some(path) = path.as_ref() {
+            if path.exists() {
+                match ron::de::from_reader::<_, T>(
+                    OpenOptions::new().read(true).open(path).unwrap(),
+                ) {
+                    Ok(state) => return state,
+                    Err(err) => {
+                        warn!(?err, "Failed to read {}, resetting..", name);
+                        if let Err(err) = std::fs::remove_file(path) {
+                            error!(?err, "Failed to remove {}.", name);
+                        }
+                    }
+                };
+            }
         }
+
+        T::default()
     }
 
     fn load_outputs(path: &Option<PathBuf>) -> OutputsConfig {
@@ -368,7 +443,18 @@ impl Config {
             .map(Into::<crate::config::OutputInfo>::into)
             .collect::<Vec<_>>();
         infos.sort();
-        if let Some(configs) = self.dynamic_conf.outputs().config.get(&infos).cloned() {
+        let configs = self.dynamic_conf.outputs().config.get(&infos).cloned();
+        let profile = output_profiles::select_profile(
+            &self.cosmic_conf.output_profiles,
+            &outputs,
+            self.active_output_profile.as_deref(),
+        );
+        let profile_match = profile
+            .as_ref()
+            .map(|(profile, _)| (profile.name.clone(), infos.clone()));
+        let profile = profile.filter(|_| profile_match != self.applied_output_profile);
+        self.applied_output_profile = profile_match;
+        if configs.is_some() || profile.is_some() {
             let known_good_configs = outputs
                 .iter()
                 .map(|output| {
@@ -381,18 +467,39 @@ impl Config {
                 })
                 .collect::<Vec<_>>();
 
-            let mut found_outputs = Vec::new();
-            for (name, output_config) in infos.iter().map(|o| &o.connector).zip(configs.into_iter())
-            {
-                let output = outputs.iter().find(|o| &o.name() == name).unwrap().clone();
-                let enabled = output_config.enabled.clone();
-                *output
-                    .user_data()
-                    .get::<RefCell<OutputConfig>>()
-                    .unwrap()
-                    .borrow_mut() = output_config;
-                found_outputs.push((output.clone(), enabled));
+            let has_configs = configs.is_some();
+            if let Some(configs) = configs {
+                for (name, output_config) in
+                    infos.iter().map(|o| &o.connector).zip(configs.into_iter())
+                {
+                    let output = outputs.iter().find(|o| &o.name() == name).unwrap();
+                    *output
+                        .user_data()
+                        .get::<RefCell<OutputConfig>>()
+                        .unwrap()
+                        .borrow_mut() = output_config;
+                }
+            }
+            if let Some((profile, matched)) = profile {
+                info!(profile = %profile.name, "Applying output profile.");
+                output_profiles::apply_profile(profile, &matched);
+                if !has_configs {
+                    output_profiles::place_unlisted(&matched, &outputs);
+                }
             }
+            let mut found_outputs = outputs
+                .iter()
+                .map(|output| {
+                    let enabled = output
+                        .user_data()
+                        .get::<RefCell<OutputConfig>>()
+                        .unwrap()
+                        .borrow()
+                        .enabled
+                        .clone();
+                    (output.clone(), enabled)
+                })
+                .collect::<Vec<_>>();
 
             if let Err(err) = backend.apply_config_for_outputs(
                 false,
@@ -532,6 +639,30 @@ impl Config {
         )
     }
 
+    pub fn tablet_config(&self, device: &InputDevice) -> Option<&TabletConfig> {
+        let (device_config, default_config) = self.get_device_config(device);
+        Some(input_config::get_config(device_config, default_config, |x| x.tablet.as_ref())?.0)
+    }
+
+    /// Key bindings for compositor specific actions
+    pub fn comp_bindings(&self) -> Cow<'_, [(shortcuts::Binding, CompAction)]> {
+        match self.cosmic_conf.bindings.as_deref() {
+            Some(bindings) => Cow::Borrowed(bindings),
+            None => Cow::Owned(key_bindings::default_comp_bindings()),
+        }
+    }
+
+    /// Touchpad gesture bindings
+    pub fn gesture_bindings(&self) -> Cow<'_, [(Gesture, shortcuts::Action)]> {
+        match self.cosmic_conf.gestures.as_deref() {
+            Some(bindings) => Cow::Borrowed(bindings),
+            // default gestures depend on the workspace layout
+            None => Cow::Owned(gestures::default_gesture_bindings(
+                self.cosmic_conf.workspaces.workspace_layout,
+            )),
+        }
+    }
+
     fn get_device_config(&self, device: &InputDevice) -> (Option<&InputConfig>, &InputConfig) {
         let default_config = if device.config_tap_finger_count() > 0 {
             &self.cosmic_conf.input_touchpad
@@ -588,6 +719,22 @@ impl DynamicConfig {
     pub fn outputs_mut(&mut self) -> PersistenceGuard<'_, OutputsConfig> {
         PersistenceGuard(self.outputs.0.clone(), &mut self.outputs.1)
     }
+
+    pub fn session(&self) -> &SessionState {
+        &self.session.1
+    }
+
+    pub fn session_mut(&mut self) -> PersistenceGuard<'_, SessionState> {
+        PersistenceGuard(self.session.0.clone(), &mut self.session.1)
+    }
+
+    pub fn screencopy(&self) -> &ScreencopyPermissions {
+        &self.screencopy.1
+    }
+
+    pub fn screencopy_mut(&mut self) -> PersistenceGuard<'_, ScreencopyPermissions> {
+        PersistenceGuard(self.screencopy.0.clone(), &mut self.screencopy.1)
+    }
 }
 
 fn get_config<T: Default + serde::de::DeserializeOwned>(
@@ -691,6 +838,92 @@ fn config_changed(config: cosmic_config::Config, keys: Vec<String>, state: &mut
                     state.common.update_config();
                 }
             }
+            "bindings" => {
+                state.common.config.cosmic_conf.bindings = get_config(&config, "bindings");
+            }
+            "gestures" => {
+                state.common.config.cosmic_conf.gestures = get_config(&config, "gestures");
+            }
+            "input_seats" => {
+                let new = get_config::<HashMap<String, String>>(&config, "input_seats");
+                if new != state.common.config.cosmic_conf.input_seats {
+                    state.common.config.cosmic_conf.input_seats = new;
+                    // move already connected devices over to their new seats
+                    if let BackendData::Kms(ref kms_state) = &state.backend {
+                        let devices = kms_state
+                            .input_devices
+                            .values()
+                            .cloned()
+                            .collect::<Vec<_>>();
+                        for device in devices {
+                            state.remove_input_device(&device);
+                            state.add_input_device(&device);
+                        }
+                    }
+                    state.common.shell.write().unwrap().remove_empty_seats();
+                }
+            }
+            "window_rules" => {
+                let new = get_config::<Vec<WindowRule>>(&config, "window_rules");
+                if new != state.common.config.cosmic_conf.window_rules {
+                    state.common.config.cosmic_conf.window_rules = new;
+                    state
+                        .common
+                        .shell
+                        .write()
+                        .unwrap()
+                        .update_window_rules(state.common.config.cosmic_conf.window_rules.iter());
+                }
+            }
+            "screenshot" => {
+                state.common.config.cosmic_conf.screenshot =
+                    get_config::<ScreenshotConfig>(&config, "screenshot");
+            }
+            "recording" => {
+                state.common.config.cosmic_conf.recording =
+                    get_config::<RecordingConfig>(&config, "recording");
+            }
+            "screencopy_consent" => {
+                state.common.config.cosmic_conf.screencopy_consent =
+                    get_config::<bool>(&config, "screencopy_consent");
+            }
+            "floating_nudge_step" => {
+                state.common.config.cosmic_conf.floating_nudge_step =
+                    get_config::<u32>(&config, "floating_nudge_step");
+            }
+            "snapping_zones" => {
+                state.common.config.cosmic_conf.snapping_zones =
+                    get_config::<SnappingZonesConfig>(&config, "snapping_zones");
+            }
+            "swallowing" => {
+                let new = get_config::<SwallowingConfig>(&config, "swallowing");
+                if new != state.common.config.cosmic_conf.swallowing {
+                    state.common.config.cosmic_conf.swallowing = new.clone();
+                    state.common.shell.write().unwrap().update_swallowing(new);
+                }
+            }
+            "decorations" => {
+                let new = get_config::<DecorationsConfig>(&config, "decorations");
+                if new != state.common.config.cosmic_conf.decorations {
+                    state.common.config.cosmic_conf.decorations = new.clone();
+                    state
+                        .common
+                        .shell
+                        .write()
+                        .unwrap()
+                        .update_decorations(new, &state.common.xdg_activation_state);
+                }
+            }
+            "output_profiles" => {
+                // applied on the next hotplug or through `CompAction::ApplyOutputProfile`
+                state.common.config.cosmic_conf.output_profiles =
+                    get_config::<Vec<OutputProfile>>(&config, "output_profiles");
+            }
+            "security_rules" => {
+                // only affects clients connecting from now on
+                state.common.config.cosmic_conf.security_rules =
+                    get_config::<Vec<SecurityRule>>(&config, "security_rules");
+            }
             "descale_xwayland" => {
                 let new = get_config::<bool>(&config, "descale_xwayland");
                 if new != state.common.config.cosmic_conf.descale_xwayland {
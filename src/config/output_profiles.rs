@@ -0,0 +1,343 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use cosmic_comp_config::output::{OutputProfile, OutputTransform, ProfileOutput};
+use smithay::{
+    output::Output,
+    utils::{Logical, Size, Transform},
+};
+
+use super::{OutputConfig, OutputState};
+use crate::utils::prelude::OutputExt;
+
+fn to_transform(transform: OutputTransform) -> Transform {
+    match transform {
+        OutputTransform::Normal => Transform::Normal,
+        OutputTransform::Rotate90 => Transform::_90,
+        OutputTransform::Rotate180 => Transform::_180,
+        OutputTransform::Rotate270 => Transform::_270,
+        OutputTransform::Flipped => Transform::Flipped,
+        OutputTransform::Flipped90 => Transform::Flipped90,
+        OutputTransform::Flipped180 => Transform::Flipped180,
+        OutputTransform::Flipped270 => Transform::Flipped270,
+    }
+}
+
+fn matches(profile_output: &ProfileOutput, output: &Output) -> bool {
+    let physical = output.physical_properties();
+    profile_output.matches.matches(
+        &physical.make,
+        &physical.model,
+        output.serial_number().as_deref(),
+        &output.name(),
+    )
+}
+
+/// Assigns a distinct connected output to every output of the profile, if possible.
+///
+/// Connected outputs not part of the profile are ignored, so a profile also matches
+/// any superset of its displays.
+pub fn match_profile(profile: &OutputProfile, outputs: &[Output]) -> Option<Vec<Output>> {
+    fn assign(profile: &[ProfileOutput], outputs: &[Output], assigned: &mut Vec<Output>) -> bool {
+        let Some((first, rest)) = profile.split_first() else {
+            return true;
+        };
+        for output in outputs {
+            if assigned.contains(output) || !matches(first, output) {
+                continue;
+            }
+            assigned.push(output.clone());
+            if assign(rest, outputs, assigned) {
+                return true;
+            }
+            assigned.pop();
+        }
+        false
+    }
+
+    if profile.outputs.is_empty() {
+        return None;
+    }
+    let mut assigned = Vec::with_capacity(profile.outputs.len());
+    assign(&profile.outputs, outputs, &mut assigned).then_some(assigned)
+}
+
+/// Picks the profile to apply for the connected outputs.
+///
+/// A matching `preferred` profile always wins, otherwise the automatic profile
+/// covering the most outputs with the most specific matches is used.
+/// Ties are resolved by the order of the profiles.
+pub fn select_profile<'a>(
+    profiles: &'a [OutputProfile],
+    outputs: &[Output],
+    preferred: Option<&str>,
+) -> Option<(&'a OutputProfile, Vec<Output>)> {
+    if let Some(profile) = preferred.and_then(|name| profiles.iter().find(|p| p.name == name)) {
+        if let Some(matched) = match_profile(profile, outputs) {
+            return Some((profile, matched));
+        }
+    }
+
+    profiles
+        .iter()
+        .rev()
+        .filter(|profile| profile.automatic)
+        .filter_map(|profile| Some((profile, match_profile(profile, outputs)?)))
+        .max_by_key(|(profile, _)| {
+            (
+                profile.outputs.len(),
+                profile
+                    .outputs
+                    .iter()
+                    .map(|output| output.matches.specificity())
+                    .sum::<usize>(),
+            )
+        })
+}
+
+/// Overrides the configs of the matched outputs with the settings of the profile
+pub fn apply_profile(profile: &OutputProfile, matched: &[Output]) {
+    for (profile_output, output) in profile.outputs.iter().zip(matched.iter()) {
+        let mut config = output.config_mut();
+        config.enabled = if !profile_output.enabled {
+            OutputState::Disabled
+        } else {
+            match profile_output
+                .mirroring
+                .and_then(|idx| matched.get(idx))
+                .filter(|mirrored| *mirrored != output)
+            {
+                Some(mirrored) => OutputState::Mirroring(mirrored.name()),
+                None => OutputState::Enabled,
+            }
+        };
+        if let Some(mode) = profile_output.mode {
+            config.mode = mode;
+        }
+        if let Some(scale) = profile_output.scale {
+            config.scale = scale;
+        }
+        if let Some(transform) = profile_output.transform {
+            config.transform = to_transform(transform);
+        }
+        if let Some(position) = profile_output.position {
+            config.position = position;
+        }
+        if let Some(vrr) = profile_output.vrr {
+            config.vrr = vrr;
+        }
+    }
+}
+
+/// Places the enabled outputs not covered by the profile in a row to the right of the profile's outputs.
+///
+/// Only used for output sets without a stored config, where they would otherwise keep
+/// whatever position they were created with and might overlap the profile's outputs.
+pub fn place_unlisted(matched: &[Output], outputs: &[Output]) {
+    let mut right_edge = matched
+        .iter()
+        .map(|output| output.config())
+        .filter(|config| config.enabled == OutputState::Enabled)
+        .map(|config| config.position.0 as i32 + logical_size(&config).w)
+        .max()
+        .unwrap_or(0);
+    for output in outputs.iter().filter(|output| !matched.contains(output)) {
+        let mut config = output.config_mut();
+        if config.enabled != OutputState::Enabled {
+            continue;
+        }
+        config.position = (right_edge.max(0) as u32, 0);
+        right_edge += logical_size(&config).w;
+    }
+}
+
+fn logical_size(config: &OutputConfig) -> Size<i32, Logical> {
+    config
+        .transform
+        .transform_size(config.mode_size())
+        .to_f64()
+        .to_logical(config.scale)
+        .to_i32_round()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmic_comp_config::output::OutputMatch;
+    use smithay::output::{PhysicalProperties, Subpixel};
+    use std::cell::RefCell;
+
+    fn output(connector: &str, model: &str) -> Output {
+        let output = Output::new(
+            connector.to_string(),
+            PhysicalProperties {
+                size: (0, 0).into(),
+                subpixel: Subpixel::Unknown,
+                make: String::from("Make"),
+                model: model.to_string(),
+            },
+        );
+        output
+            .user_data()
+            .insert_if_missing(|| RefCell::new(OutputConfig::default()));
+        output
+    }
+
+    fn profile_output(connector: Option<&str>, model: Option<&str>) -> ProfileOutput {
+        ProfileOutput {
+            matches: OutputMatch {
+                connector: connector.map(Into::into),
+                model: model.map(Into::into),
+                ..Default::default()
+            },
+            enabled: true,
+            mirroring: None,
+            mode: None,
+            scale: None,
+            transform: None,
+            position: None,
+            vrr: None,
+        }
+    }
+
+    fn profile(name: &str, automatic: bool, outputs: Vec<ProfileOutput>) -> OutputProfile {
+        OutputProfile {
+            name: name.to_string(),
+            automatic,
+            outputs,
+        }
+    }
+
+    fn names(outputs: &[Output]) -> Vec<String> {
+        outputs.iter().map(|output| output.name()).collect()
+    }
+
+    #[test]
+    fn test_match_profile_distinct_outputs() {
+        let outputs = [output("DP-1", "A"), output("DP-2", "B")];
+        let docked = profile(
+            "docked",
+            true,
+            vec![
+                profile_output(Some("DP-*"), None),
+                profile_output(Some("DP-*"), None),
+            ],
+        );
+        assert_eq!(
+            match_profile(&docked, &outputs).as_deref().map(names),
+            Some(vec![String::from("DP-1"), String::from("DP-2")])
+        );
+        // one output can't satisfy both entries
+        assert_eq!(match_profile(&docked, &outputs[..1]), None);
+        assert_eq!(
+            match_profile(&profile("empty", true, Vec::new()), &outputs),
+            None
+        );
+    }
+
+    #[test]
+    fn test_match_profile_backtracks() {
+        let outputs = [output("DP-1", "B"), output("DP-2", "A")];
+        // the catch-all entry must not take the only output matching the second one
+        let profile = profile(
+            "backtrack",
+            true,
+            vec![profile_output(None, None), profile_output(None, Some("B"))],
+        );
+        assert_eq!(
+            match_profile(&profile, &outputs).as_deref().map(names),
+            Some(vec![String::from("DP-2"), String::from("DP-1")])
+        );
+    }
+
+    #[test]
+    fn test_match_profile_superset() {
+        let outputs = [output("eDP-1", "Panel"), output("HDMI-A-1", "TV")];
+        let laptop = profile("laptop", true, vec![profile_output(Some("eDP-1"), None)]);
+        assert_eq!(
+            match_profile(&laptop, &outputs).as_deref().map(names),
+            Some(vec![String::from("eDP-1")])
+        );
+    }
+
+    #[test]
+    fn test_select_profile() {
+        let outputs = [output("eDP-1", "Panel"), output("HDMI-A-1", "TV")];
+        let profiles = [
+            profile("laptop", true, vec![profile_output(Some("eDP-*"), None)]),
+            profile(
+                "presentation",
+                false,
+                vec![profile_output(Some("HDMI-A-1"), None)],
+            ),
+            profile(
+                "tv",
+                true,
+                vec![
+                    profile_output(Some("eDP-*"), None),
+                    profile_output(None, Some("TV")),
+                ],
+            ),
+            profile(
+                "any",
+                true,
+                vec![profile_output(None, None), profile_output(None, None)],
+            ),
+        ];
+        let selected = |preferred| {
+            select_profile(&profiles, &outputs, preferred).map(|(profile, _)| profile.name.clone())
+        };
+
+        // most outputs, then most specific
+        assert_eq!(selected(None), Some(String::from("tv")));
+        // manual profiles are only used when asked for
+        assert_eq!(
+            selected(Some("presentation")),
+            Some(String::from("presentation"))
+        );
+        // unless they don't match
+        assert_eq!(
+            select_profile(&profiles, &outputs[..1], Some("presentation"))
+                .map(|(profile, _)| profile.name.clone()),
+            Some(String::from("laptop"))
+        );
+    }
+
+    #[test]
+    fn test_select_profile_ties_keep_order() {
+        let outputs = [output("DP-1", "A")];
+        let profiles = [
+            profile("first", true, vec![profile_output(Some("DP-1"), None)]),
+            profile("second", true, vec![profile_output(Some("DP-1"), None)]),
+        ];
+        assert_eq!(
+            select_profile(&profiles, &outputs, None).map(|(profile, _)| profile.name.clone()),
+            Some(String::from("first"))
+        );
+    }
+
+    #[test]
+    fn test_place_unlisted() {
+        let outputs = [
+            output("eDP-1", "Panel"),
+            output("DP-1", "A"),
+            output("DP-2", "B"),
+            output("DP-3", "C"),
+        ];
+        {
+            let mut config = outputs[0].config_mut();
+            config.mode = ((2880, 1800), None);
+            config.scale = 2.0;
+            config.position = (100, 0);
+        }
+        outputs[1].config_mut().mode = ((1080, 1920), None);
+        outputs[1].config_mut().transform = Transform::_90;
+        outputs[2].config_mut().enabled = OutputState::Disabled;
+        outputs[3].config_mut().mode = ((1920, 1080), None);
+
+        place_unlisted(&outputs[..1], &outputs);
+        assert_eq!(outputs[0].config().position, (100, 0));
+        assert_eq!(outputs[1].config().position, (1540, 0));
+        assert_eq!(outputs[2].config().position, (0, 0));
+        assert_eq!(outputs[3].config().position, (3460, 0));
+    }
+}
@@ -1,12 +1,12 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
 use crate::{
-    config::{Action, PrivateAction},
+    config::{Action, CompAction, PrivateAction, ScreenshotTarget},
     shell::{
-        focus::target::KeyboardFocusTarget, layout::tiling::SwapWindowGrab, FocusResult,
-        InvalidWorkspaceIndex, MoveResult, SeatExt, Trigger, WorkspaceDelta,
+        focus::target::KeyboardFocusTarget, grabs::RegionGrab, layout::tiling::SwapWindowGrab,
+        FocusResult, InvalidWorkspaceIndex, MoveResult, SeatExt, Trigger, WorkspaceDelta,
     },
-    utils::prelude::*,
+    utils::{prelude::*, screenshot::screenshot_region},
     wayland::{
         handlers::xdg_activation::ActivationContext, protocols::workspace::WorkspaceUpdateGuard,
     },
@@ -16,8 +16,11 @@ use cosmic_config::ConfigSet;
 use cosmic_settings_config::shortcuts;
 use cosmic_settings_config::shortcuts::action::{Direction, FocusDirection};
 use smithay::{
-    input::{pointer::MotionEvent, Seat},
-    utils::{Point, Serial},
+    input::{
+        pointer::{Focus, GrabStartData as PointerGrabStartData, MotionEvent},
+        Seat,
+    },
+    utils::{Point, Serial, SERIAL_COUNTER},
 };
 use tracing::error;
 #[cfg(not(feature = "debug"))]
@@ -27,6 +30,8 @@ use std::{os::unix::process::CommandExt, thread};
 
 use super::gestures;
 
+const BTN_LEFT: u32 = 0x110;
+
 impl State {
     pub fn handle_action(
         &mut self,
@@ -54,6 +59,8 @@ impl State {
             Action::Shortcut(action) => self
                 .handle_shortcut_action(action, seat, serial, time, pattern, direction, propagate),
 
+            Action::Comp(action) => self.handle_comp_action(action, seat, serial),
+
             Action::Private(PrivateAction::Escape) => {
                 {
                     let mut shell = self.common.shell.write().unwrap();
@@ -92,11 +99,158 @@ impl State {
         }
     }
 
-    pub fn handle_swipe_action(&mut self, action: gestures::SwipeAction, seat: &Seat<State>) {
-        use gestures::SwipeAction;
+    pub fn handle_comp_action(&mut self, action: CompAction, seat: &Seat<State>, serial: Serial) {
+        match action {
+            CompAction::MoveToScratchpad => {
+                let Some(focused_output) = seat.focused_output() else {
+                    return;
+                };
+                let mut shell = self.common.shell.write().unwrap();
+                let workspace = shell.active_space(&focused_output);
+                let focus_stack = workspace.focus_stack.get(seat);
+                let focused_window = focus_stack.last().cloned();
+                if let Some(window) = focused_window {
+                    shell.move_to_scratchpad(&window, seat);
+                }
+            }
+
+            CompAction::ToggleScratchpad => {
+                let res = self.common.shell.write().unwrap().toggle_scratchpad(seat);
+                if let Some(new_focus) = res {
+                    Shell::set_focus(self, Some(&new_focus), seat, Some(serial), false);
+                }
+            }
+
+            CompAction::SetTilingAlgorithm(algorithm) => {
+                let Some(focused_output) = seat.focused_output() else {
+                    return;
+                };
+                let mut shell = self.common.shell.write().unwrap();
+                let workspace = shell.active_space_mut(&focused_output);
+                workspace.tiling_layer.set_algorithm(algorithm);
+            }
+
+            CompAction::ToggleScrolling => {
+                let Some(focused_output) = seat.focused_output() else {
+                    return;
+                };
+                let mut shell = self.common.shell.write().unwrap();
+                let workspace = shell.active_space_mut(&focused_output);
+                workspace.toggle_scrolling(seat);
+            }
+
+            CompAction::Screenshot { target, clipboard } => match target {
+                ScreenshotTarget::Output => {
+                    let output = seat.focused_or_active_output();
+                    screenshot_region(self, output.geometry(), &output.name(), clipboard, seat);
+                }
+                ScreenshotTarget::AllOutputs => {
+                    let region = self
+                        .common
+                        .shell
+                        .read()
+                        .unwrap()
+                        .outputs()
+                        .map(|output| output.geometry())
+                        .reduce(|a, b| a.merge(b));
+                    if let Some(region) = region {
+                        screenshot_region(self, region, "all", clipboard, seat);
+                    }
+                }
+                ScreenshotTarget::Region => {
+                    let Some(pointer) = seat.get_pointer() else {
+                        return;
+                    };
+                    let grab = RegionGrab::new(
+                        PointerGrabStartData {
+                            focus: None,
+                            button: BTN_LEFT,
+                            location: pointer.current_location(),
+                        },
+                        seat,
+                        clipboard,
+                    );
+                    pointer.set_grab(self, grab, serial, Focus::Clear);
+                }
+            },
+
+            CompAction::ToggleRecording(target) => self.toggle_recording(target, seat),
+
+            CompAction::ApplyOutputProfile(name) => {
+                if !self
+                    .common
+                    .config
+                    .cosmic_conf
+                    .output_profiles
+                    .iter()
+                    .any(|profile| profile.name == name)
+                {
+                    tracing::warn!(profile = %name, "Unknown output profile.");
+                    return;
+                }
+
+                self.common.config.active_output_profile = Some(name);
+                // apply it again, even if it already was
+                self.common.config.applied_output_profile = None;
+                self.common.config.read_outputs(
+                    &mut self.common.output_configuration_state,
+                    &mut self.backend,
+                    &self.common.shell,
+                    &self.common.event_loop_handle,
+                    &mut self.common.workspace_state.update(),
+                    &self.common.xdg_activation_state,
+                    self.common.startup_done.clone(),
+                );
+                self.common.refresh();
+            }
+
+            CompAction::SnapFloating(position) => {
+                self.common
+                    .shell
+                    .write()
+                    .unwrap()
+                    .with_focused_floating(seat, |layer, mapped| {
+                        layer.snap_element(mapped, position.into())
+                    });
+            }
+
+            CompAction::NudgeFloating(direction) => {
+                let step = self.common.config.cosmic_conf.floating_nudge_step as i32;
+                self.common
+                    .shell
+                    .write()
+                    .unwrap()
+                    .with_focused_floating(seat, |layer, mapped| {
+                        layer.nudge_element(mapped, direction, step)
+                    });
+            }
+
+            CompAction::ResizeFloating { direction, edge } => {
+                let step = self.common.config.cosmic_conf.floating_nudge_step as i32;
+                self.common
+                    .shell
+                    .write()
+                    .unwrap()
+                    .with_focused_floating(seat, |layer, mapped| {
+                        layer.resize_element_by(mapped, direction, edge, step)
+                    });
+            }
+
+            CompAction::CenterFloating => {
+                self.common
+                    .shell
+                    .write()
+                    .unwrap()
+                    .with_focused_floating(seat, |layer, mapped| layer.center_element(mapped));
+            }
+        }
+    }
+
+    pub fn handle_gesture_action(&mut self, action: gestures::GestureAction, seat: &Seat<State>) {
+        use gestures::GestureAction;
 
         match action {
-            SwipeAction::NextWorkspace => {
+            GestureAction::NextWorkspace => {
                 let _ = to_next_workspace(
                     &mut *self.common.shell.write().unwrap(),
                     &seat,
@@ -104,7 +258,7 @@ impl State {
                     &mut self.common.workspace_state.update(),
                 );
             }
-            SwipeAction::PrevWorkspace => {
+            GestureAction::PrevWorkspace => {
                 let _ = to_previous_workspace(
                     &mut *self.common.shell.write().unwrap(),
                     &seat,
@@ -112,9 +266,31 @@ impl State {
                     &mut self.common.workspace_state.update(),
                 );
             }
+            GestureAction::Shortcut(action) => {
+                self.handle_unbound_action(Action::Shortcut(action), seat)
+            }
         }
     }
 
+    /// Runs an action, that wasn't triggered by a key binding (e.g. by a gesture or ipc)
+    pub fn handle_unbound_action(&mut self, action: Action, seat: &Seat<State>) {
+        let serial = SERIAL_COUNTER.next_serial();
+        let time = self.common.clock.now().as_millis();
+        self.handle_action(
+            action,
+            seat,
+            serial,
+            time,
+            shortcuts::Binding {
+                modifiers: Default::default(),
+                key: None,
+                description: None,
+            },
+            None,
+            true,
+        );
+    }
+
     pub fn handle_shortcut_action(
         &mut self,
         action: shortcuts::Action,
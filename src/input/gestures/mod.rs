@@ -1,10 +1,12 @@
-use cosmic_settings_config::shortcuts::action::Direction;
+use cosmic_settings_config::shortcuts::{self, action::Direction};
 use smithay::utils::{Logical, Point};
 use std::{collections::VecDeque, time::Duration};
 use tracing::trace;
 
 const HISTORY_LIMIT: Duration = Duration::from_millis(150);
 const DECELERATION_TOUCHPAD: f64 = 0.997;
+/// Relative change of the finger distance, after which a pinch is recognized
+pub const PINCH_THRESHOLD: f64 = 0.25;
 
 #[derive(Debug, Clone, Copy)]
 pub struct SwipeEvent {
@@ -12,17 +14,20 @@ pub struct SwipeEvent {
     timestamp: Duration,
 }
 
-#[derive(Debug, Clone, Copy)]
-pub enum SwipeAction {
+#[derive(Debug, Clone)]
+pub enum GestureAction {
+    /// Workspace switch following the fingers
     NextWorkspace,
     PrevWorkspace,
+    /// Any other bound action, triggered once the gesture is recognized
+    Shortcut(shortcuts::Action),
 }
 
 #[derive(Debug, Clone)]
 pub struct GestureState {
     pub fingers: u32,
     pub direction: Option<Direction>,
-    pub action: Option<SwipeAction>,
+    pub action: Option<GestureAction>,
     pub delta: f64,
     // Delta tracking inspired by Niri (GPL-3.0) https://github.com/YaLTeR/niri/tree/v0.1.3
     pub history: VecDeque<SwipeEvent>,
@@ -2,18 +2,20 @@
 
 use crate::{
     config::{
+        gestures::gesture_action,
         key_bindings::{
             cosmic_keystate_from_smithay, cosmic_modifiers_eq_smithay,
             cosmic_modifiers_from_smithay,
         },
-        Action, Config, PrivateAction,
+        Action, Config, Gesture, PinchDirection, PrivateAction,
     },
-    input::gestures::{GestureState, SwipeAction},
+    input::gestures::{GestureAction, GestureState, PINCH_THRESHOLD},
     shell::{
+        create_seat,
         focus::target::{KeyboardFocusTarget, PointerFocusTarget},
         grabs::{ReleaseMode, ResizeEdge},
         layout::{floating::ResizeGrabMarker, tiling::TilingLayout},
-        SeatExt, Trigger,
+        seat_name_for_device, SeatExt, Trigger,
     },
     utils::{prelude::*, quirks::workspace_overview_is_open},
     wayland::{
@@ -25,16 +27,16 @@ use calloop::{
     timer::{TimeoutAction, Timer},
     RegistrationToken,
 };
-use cosmic_comp_config::workspace::WorkspaceLayout;
+use cosmic_comp_config::{input::TabletArea, workspace::WorkspaceLayout};
 use cosmic_settings_config::shortcuts;
-use cosmic_settings_config::shortcuts::action::{Direction, ResizeDirection};
+use cosmic_settings_config::shortcuts::action::ResizeDirection;
 use smithay::{
     backend::input::{
         AbsolutePositionEvent, Axis, AxisSource, Device, DeviceCapability, GestureBeginEvent,
         GestureEndEvent, GesturePinchUpdateEvent as _, GestureSwipeUpdateEvent as _, InputBackend,
-        InputEvent, KeyState, PointerAxisEvent, ProximityState, TabletToolButtonEvent,
-        TabletToolEvent, TabletToolProximityEvent, TabletToolTipEvent, TabletToolTipState,
-        TouchEvent,
+        InputEvent, KeyState, PointerAxisEvent, ProximityState, Switch, SwitchState,
+        SwitchToggleEvent, TabletToolButtonEvent, TabletToolEvent, TabletToolProximityEvent,
+        TabletToolTipEvent, TabletToolTipState, TouchEvent,
     },
     desktop::{
         layer_map_for_output, space::SpaceElement, utils::under_from_surface_tree,
@@ -55,7 +57,7 @@ use smithay::{
     reexports::{
         input::Device as InputDevice, wayland_server::protocol::wl_shm::Format as ShmFormat,
     },
-    utils::{Point, Serial, SERIAL_COUNTER},
+    utils::{Point, Rectangle, Serial, SERIAL_COUNTER},
     wayland::{
         keyboard_shortcuts_inhibit::KeyboardShortcutsInhibitorSeat,
         pointer_constraints::{with_pointer_constraint, PointerConstraint},
@@ -78,6 +80,7 @@ use std::{
 
 pub mod actions;
 pub mod gestures;
+mod switches;
 
 /// Used for debouncing focus updates due to pointer motion, if after the focus change is
 /// triggered the event will cancel if the pointer moves to the original target
@@ -156,6 +159,50 @@ impl ModifiersShortcutQueue {
 }
 
 impl State {
+    /// Adds the device to its seat, creating the seat if it doesn't exist yet
+    pub fn add_input_device<D: Device + 'static>(&mut self, device: &D) {
+        let name = seat_name_for_device(&self.common.config, device);
+        let mut shell = self.common.shell.write().unwrap();
+        let seat = match shell.seats.iter().find(|seat| seat.name() == name) {
+            Some(seat) => seat.clone(),
+            None => {
+                let output = shell.seats.last_active().active_output();
+                let seat = create_seat(
+                    &self.common.display_handle,
+                    &mut self.common.seat_state,
+                    &output,
+                    &self.common.config,
+                    name,
+                );
+                shell.seats.add_seat(seat.clone());
+                seat
+            }
+        };
+
+        seat.devices().add_device(device);
+        if device.has_capability(DeviceCapability::TabletTool) {
+            seat.tablet_seat()
+                .add_tablet::<Self>(&self.common.display_handle, &TabletDescriptor::from(device));
+        }
+    }
+
+    pub fn remove_input_device<D: Device>(&mut self, device: &D) {
+        for seat in self.common.shell.read().unwrap().seats.iter() {
+            let devices = seat.devices();
+            if devices.has_device(device) {
+                devices.remove_device(device);
+                if device.has_capability(DeviceCapability::TabletTool) {
+                    seat.tablet_seat()
+                        .remove_tablet(&TabletDescriptor::from(device));
+                    if seat.tablet_seat().count_tablets() == 0 {
+                        seat.tablet_seat().clear_tools();
+                    }
+                }
+                break;
+            }
+        }
+    }
+
     pub fn process_input_event<B: InputBackend>(
         &mut self,
         event: InputEvent<B>,
@@ -165,33 +212,8 @@ impl State {
     {
         use smithay::backend::input::Event;
         match event {
-            InputEvent::DeviceAdded { device } => {
-                let shell = self.common.shell.read().unwrap();
-                let seat = shell.seats.last_active();
-                seat.devices().add_device(&device);
-                if device.has_capability(DeviceCapability::TabletTool) {
-                    seat.tablet_seat().add_tablet::<Self>(
-                        &self.common.display_handle,
-                        &TabletDescriptor::from(&device),
-                    );
-                }
-            }
-            InputEvent::DeviceRemoved { device } => {
-                for seat in &mut self.common.shell.read().unwrap().seats.iter() {
-                    let devices = seat.devices();
-                    if devices.has_device(&device) {
-                        devices.remove_device(&device);
-                        if device.has_capability(DeviceCapability::TabletTool) {
-                            seat.tablet_seat()
-                                .remove_tablet(&TabletDescriptor::from(&device));
-                            if seat.tablet_seat().count_tablets() == 0 {
-                                seat.tablet_seat().clear_tools();
-                            }
-                        }
-                        break;
-                    }
-                }
-            }
+            InputEvent::DeviceAdded { device } => self.add_input_device(&device),
+            InputEvent::DeviceRemoved { device } => self.remove_input_device(&device),
 
             InputEvent::Keyboard { event, .. } => {
                 use smithay::backend::input::KeyboardKeyEvent;
@@ -518,6 +540,24 @@ impl State {
                                                 )));
                                             }
                                         }
+
+                                        // compositor specific bindings only support regular key presses
+                                        for (binding, action) in
+                                            data.common.config.comp_bindings().iter()
+                                        {
+                                            if binding.key.is_some()
+                                                && state == KeyState::Pressed
+                                                && handle.raw_syms().contains(&binding.key.unwrap())
+                                                && cosmic_modifiers_eq_smithay(&binding.modifiers, modifiers)
+                                            {
+                                                modifiers_queue.clear();
+                                                seat.supressed_keys().add(&handle, None);
+                                                return FilterResult::Intercept(Some((
+                                                    Action::Comp(action.clone()),
+                                                    binding.clone(),
+                                                )));
+                                            }
+                                        }
                                     }
 
                                     // no binding
@@ -1138,8 +1178,20 @@ impl State {
                     .cloned();
                 if let Some(seat) = maybe_seat {
                     self.common.idle_notifier_state.notify_activity(&seat);
-                    if event.fingers() >= 3 && !workspace_overview_is_open(&seat.active_output()) {
-                        self.common.gesture_state = Some(GestureState::new(event.fingers()));
+                    let fingers = event.fingers();
+                    if fingers >= 3
+                        && !workspace_overview_is_open(&seat.active_output())
+                        && self
+                            .common
+                            .config
+                            .gesture_bindings()
+                            .iter()
+                            .any(|(gesture, _)| {
+                                matches!(gesture, Gesture::Swipe { .. })
+                                    && gesture.fingers() == fingers
+                            })
+                    {
+                        self.common.gesture_state = Some(GestureState::new(fingers));
                     } else {
                         let serial = SERIAL_COUNTER.next_serial();
                         let pointer = seat.get_pointer().unwrap();
@@ -1165,7 +1217,7 @@ impl State {
                     .cloned();
                 if let Some(seat) = maybe_seat {
                     self.common.idle_notifier_state.notify_activity(&seat);
-                    let mut activate_action: Option<SwipeAction> = None;
+                    let mut activate_action: Option<GestureAction> = None;
                     if let Some(ref mut gesture_state) = self.common.gesture_state {
                         let first_update = gesture_state.update(
                             event.delta(),
@@ -1181,57 +1233,40 @@ impl State {
                                     natural_scroll = natural;
                                 }
                             }
-                            activate_action = match gesture_state.fingers {
-                                3 => None, // TODO: 3 finger gestures
-                                4 => {
-                                    if self.common.config.cosmic_conf.workspaces.workspace_layout
-                                        == WorkspaceLayout::Horizontal
-                                    {
-                                        match gesture_state.direction {
-                                            Some(Direction::Left) => {
-                                                if natural_scroll {
-                                                    Some(SwipeAction::NextWorkspace)
-                                                } else {
-                                                    Some(SwipeAction::PrevWorkspace)
-                                                }
-                                            }
-                                            Some(Direction::Right) => {
-                                                if natural_scroll {
-                                                    Some(SwipeAction::PrevWorkspace)
-                                                } else {
-                                                    Some(SwipeAction::NextWorkspace)
-                                                }
-                                            }
-                                            _ => None, // TODO: Other actions
-                                        }
-                                    } else {
-                                        match gesture_state.direction {
-                                            Some(Direction::Up) => {
-                                                if natural_scroll {
-                                                    Some(SwipeAction::NextWorkspace)
-                                                } else {
-                                                    Some(SwipeAction::PrevWorkspace)
-                                                }
-                                            }
-                                            Some(Direction::Down) => {
-                                                if natural_scroll {
-                                                    Some(SwipeAction::PrevWorkspace)
-                                                } else {
-                                                    Some(SwipeAction::NextWorkspace)
-                                                }
-                                            }
-                                            _ => None, // TODO: Other actions
-                                        }
+                            activate_action = gesture_state
+                                .direction
+                                .and_then(|direction| {
+                                    gesture_action(
+                                        &self.common.config.gesture_bindings(),
+                                        Gesture::Swipe {
+                                            fingers: gesture_state.fingers,
+                                            direction,
+                                        },
+                                    )
+                                })
+                                .map(|action| match action {
+                                    // workspace switches follow the fingers, so they honor natural scrolling
+                                    shortcuts::Action::NextWorkspace if natural_scroll => {
+                                        GestureAction::NextWorkspace
                                     }
-                                }
-                                _ => None,
-                            };
+                                    shortcuts::Action::NextWorkspace => {
+                                        GestureAction::PrevWorkspace
+                                    }
+                                    shortcuts::Action::PreviousWorkspace if natural_scroll => {
+                                        GestureAction::PrevWorkspace
+                                    }
+                                    shortcuts::Action::PreviousWorkspace => {
+                                        GestureAction::NextWorkspace
+                                    }
+                                    action => GestureAction::Shortcut(action),
+                                });
 
-                            gesture_state.action = activate_action;
+                            gesture_state.action = activate_action.clone();
                         }
 
                         match gesture_state.action {
-                            Some(SwipeAction::NextWorkspace) | Some(SwipeAction::PrevWorkspace) => {
+                            Some(GestureAction::NextWorkspace)
+                            | Some(GestureAction::PrevWorkspace) => {
                                 self.common.shell.write().unwrap().update_workspace_delta(
                                     &seat.active_output(),
                                     gesture_state.delta,
@@ -1251,7 +1286,7 @@ impl State {
                     }
 
                     if let Some(action) = activate_action {
-                        self.handle_swipe_action(action, &seat);
+                        self.handle_gesture_action(action, &seat);
                     }
                 }
             }
@@ -1268,7 +1303,8 @@ impl State {
                     self.common.idle_notifier_state.notify_activity(&seat);
                     if let Some(ref gesture_state) = self.common.gesture_state {
                         match gesture_state.action {
-                            Some(SwipeAction::NextWorkspace) | Some(SwipeAction::PrevWorkspace) => {
+                            Some(GestureAction::NextWorkspace)
+                            | Some(GestureAction::PrevWorkspace) => {
                                 let velocity = gesture_state.velocity();
                                 let norm_velocity =
                                     if self.common.config.cosmic_conf.workspaces.workspace_layout
@@ -1312,16 +1348,29 @@ impl State {
                     .cloned();
                 if let Some(seat) = maybe_seat {
                     self.common.idle_notifier_state.notify_activity(&seat);
-                    let serial = SERIAL_COUNTER.next_serial();
-                    let pointer = seat.get_pointer().unwrap();
-                    pointer.gesture_pinch_begin(
-                        self,
-                        &GesturePinchBeginEvent {
-                            serial,
-                            time: event.time_msec(),
-                            fingers: event.fingers(),
-                        },
-                    );
+                    let fingers = event.fingers();
+                    if self
+                        .common
+                        .config
+                        .gesture_bindings()
+                        .iter()
+                        .any(|(gesture, _)| {
+                            matches!(gesture, Gesture::Pinch { .. }) && gesture.fingers() == fingers
+                        })
+                    {
+                        self.common.gesture_state = Some(GestureState::new(fingers));
+                    } else {
+                        let serial = SERIAL_COUNTER.next_serial();
+                        let pointer = seat.get_pointer().unwrap();
+                        pointer.gesture_pinch_begin(
+                            self,
+                            &GesturePinchBeginEvent {
+                                serial,
+                                time: event.time_msec(),
+                                fingers,
+                            },
+                        );
+                    }
                 }
             }
             InputEvent::GesturePinchUpdate { event, .. } => {
@@ -1335,16 +1384,45 @@ impl State {
                     .cloned();
                 if let Some(seat) = maybe_seat {
                     self.common.idle_notifier_state.notify_activity(&seat);
-                    let pointer = seat.get_pointer().unwrap();
-                    pointer.gesture_pinch_update(
-                        self,
-                        &GesturePinchUpdateEvent {
-                            time: event.time_msec(),
-                            delta: event.delta(),
-                            scale: event.scale(),
-                            rotation: event.rotation(),
-                        },
-                    );
+                    let mut activate_action: Option<GestureAction> = None;
+                    if let Some(ref mut gesture_state) = self.common.gesture_state {
+                        // pinches trigger once, as soon as the scale passes the threshold
+                        let direction = if event.scale() < 1.0 - PINCH_THRESHOLD {
+                            Some(PinchDirection::In)
+                        } else if event.scale() > 1.0 + PINCH_THRESHOLD {
+                            Some(PinchDirection::Out)
+                        } else {
+                            None
+                        };
+                        if let Some(direction) =
+                            direction.filter(|_| gesture_state.action.is_none())
+                        {
+                            activate_action = gesture_action(
+                                &self.common.config.gesture_bindings(),
+                                Gesture::Pinch {
+                                    fingers: gesture_state.fingers,
+                                    direction,
+                                },
+                            )
+                            .map(GestureAction::Shortcut);
+                            gesture_state.action = activate_action.clone();
+                        }
+                    } else {
+                        let pointer = seat.get_pointer().unwrap();
+                        pointer.gesture_pinch_update(
+                            self,
+                            &GesturePinchUpdateEvent {
+                                time: event.time_msec(),
+                                delta: event.delta(),
+                                scale: event.scale(),
+                                rotation: event.rotation(),
+                            },
+                        );
+                    }
+
+                    if let Some(action) = activate_action {
+                        self.handle_gesture_action(action, &seat);
+                    }
                 }
             }
             InputEvent::GesturePinchEnd { event, .. } => {
@@ -1358,6 +1436,9 @@ impl State {
                     .cloned();
                 if let Some(seat) = maybe_seat {
                     self.common.idle_notifier_state.notify_activity(&seat);
+                    if self.common.gesture_state.take().is_some() {
+                        return;
+                    }
                     let serial = SERIAL_COUNTER.next_serial();
                     let pointer = seat.get_pointer().unwrap();
                     pointer.gesture_pinch_end(
@@ -1381,16 +1462,26 @@ impl State {
                     .cloned();
                 if let Some(seat) = maybe_seat {
                     self.common.idle_notifier_state.notify_activity(&seat);
-                    let serial = SERIAL_COUNTER.next_serial();
-                    let pointer = seat.get_pointer().unwrap();
-                    pointer.gesture_hold_begin(
-                        self,
-                        &GestureHoldBeginEvent {
-                            serial,
-                            time: event.time_msec(),
-                            fingers: event.fingers(),
-                        },
-                    );
+                    let fingers = event.fingers();
+                    if gesture_action(
+                        &self.common.config.gesture_bindings(),
+                        Gesture::Hold { fingers },
+                    )
+                    .is_some()
+                    {
+                        self.common.gesture_state = Some(GestureState::new(fingers));
+                    } else {
+                        let serial = SERIAL_COUNTER.next_serial();
+                        let pointer = seat.get_pointer().unwrap();
+                        pointer.gesture_hold_begin(
+                            self,
+                            &GestureHoldBeginEvent {
+                                serial,
+                                time: event.time_msec(),
+                                fingers,
+                            },
+                        );
+                    }
                 }
             }
             InputEvent::GestureHoldEnd { event, .. } => {
@@ -1404,6 +1495,20 @@ impl State {
                     .cloned();
                 if let Some(seat) = maybe_seat {
                     self.common.idle_notifier_state.notify_activity(&seat);
+                    if let Some(gesture_state) = self.common.gesture_state.take() {
+                        // a cancelled hold usually turns into a swipe or pinch
+                        let action = gesture_action(
+                            &self.common.config.gesture_bindings(),
+                            Gesture::Hold {
+                                fingers: gesture_state.fingers,
+                            },
+                        )
+                        .filter(|_| !event.cancelled());
+                        if let Some(action) = action {
+                            self.handle_gesture_action(GestureAction::Shortcut(action), &seat);
+                        }
+                        return;
+                    }
                     let serial = SERIAL_COUNTER.next_serial();
                     let pointer = seat.get_pointer().unwrap();
                     pointer.gesture_hold_end(
@@ -1554,12 +1659,10 @@ impl State {
                     else {
                         return;
                     };
-                    let geometry = output.geometry();
-
-                    let position = event
-                        .position_transformed(geometry.size.as_logical())
-                        .as_global()
-                        + geometry.loc.to_f64();
+                    let position =
+                        tablet_position::<B, _>(&self.common.config, &event, output.geometry());
+                    seat.tablet_tool_cursors()
+                        .update_location(&event.tool(), position);
 
                     let under = State::surface_under(position, &output, &mut *shell)
                         .map(|(target, pos)| (target, pos.as_logical()));
@@ -1623,12 +1726,10 @@ impl State {
                     else {
                         return;
                     };
-                    let geometry = output.geometry();
-
-                    let position = event
-                        .position_transformed(geometry.size.as_logical())
-                        .as_global()
-                        + geometry.loc.to_f64();
+                    let position =
+                        tablet_position::<B, _>(&self.common.config, &event, output.geometry());
+                    seat.tablet_tool_cursors()
+                        .update_location(&event.tool(), position);
 
                     let under = State::surface_under(position, &output, &mut *shell)
                         .map(|(target, pos)| (target, pos.as_logical()));
@@ -1667,7 +1768,10 @@ impl State {
                                     )
                                 }
                             }
-                            ProximityState::Out => tool.proximity_out(event.time_msec()),
+                            ProximityState::Out => {
+                                seat.tablet_tool_cursors().remove(&event.tool());
+                                tool.proximity_out(event.time_msec());
+                            }
                         }
                     }
                 }
@@ -1717,7 +1821,14 @@ impl State {
                 }
             }
             InputEvent::Special(_) => {}
-            InputEvent::SwitchToggle { event: _ } => {}
+            InputEvent::SwitchToggle { event } => {
+                let on = event.state() == SwitchState::On;
+                match event.switch() {
+                    Some(Switch::Lid) => self.handle_lid_switch(on),
+                    Some(Switch::TabletMode) => self.handle_tablet_mode_switch(on),
+                    None => {}
+                }
+            }
         }
     }
 
@@ -1895,6 +2006,58 @@ fn mapped_output_for_device<'a, D: Device + 'static>(
     map_to_output.or_else(|| shell.builtin_output())
 }
 
+/// Maps the position of a tablet tool onto the output, honoring the tablet's area config
+fn tablet_position<B, E>(
+    config: &Config,
+    event: &E,
+    geometry: Rectangle<i32, Global>,
+) -> Point<f64, Global>
+where
+    B: InputBackend,
+    <B as InputBackend>::Device: 'static,
+    E: smithay::backend::input::Event<B> + TabletToolEvent<B>,
+{
+    use smithay::backend::input::Event;
+
+    let device = event.device();
+    let tablet = <dyn Any>::downcast_ref::<InputDevice>(&device);
+    let Some(tablet_config) = tablet.and_then(|tablet| config.tablet_config(tablet)) else {
+        return event
+            .position_transformed(geometry.size.as_logical())
+            .as_global()
+            + geometry.loc.to_f64();
+    };
+
+    let mut area = tablet_config
+        .area
+        .filter(|area| area.width > 0.0 && area.height > 0.0)
+        .unwrap_or(TabletArea {
+            x: 0.0,
+            y: 0.0,
+            width: 1.0,
+            height: 1.0,
+        });
+    if tablet_config.keep_aspect_ratio {
+        if let Some((width, height)) = tablet.and_then(|tablet| tablet.size()) {
+            let tablet_ratio = (width * area.width) / (height * area.height);
+            let output_ratio = geometry.size.w as f64 / geometry.size.h as f64;
+            // crop the right or bottom part of the area
+            if tablet_ratio > output_ratio {
+                area.width *= output_ratio / tablet_ratio;
+            } else {
+                area.height *= tablet_ratio / output_ratio;
+            }
+        }
+    }
+
+    let x = ((event.x_transformed(1) - area.x) / area.width).clamp(0.0, 1.0);
+    let y = ((event.y_transformed(1) - area.y) / area.height).clamp(0.0, 1.0);
+    Point::from((
+        geometry.loc.x as f64 + x * geometry.size.w as f64,
+        geometry.loc.y as f64 + y * geometry.size.h as f64,
+    ))
+}
+
 // FIXME: When f64::next_down reaches stable rust, use that instead
 trait NextDown {
     fn next_lower(self) -> Self;
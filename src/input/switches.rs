@@ -0,0 +1,139 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::{
+    config::{OutputConfig, OutputState},
+    state::State,
+};
+use smithay::output::Output;
+use tracing::{debug, error, warn};
+
+use std::cell::RefCell;
+
+impl State {
+    pub fn handle_lid_switch(&mut self, closed: bool) {
+        debug!(closed, "Lid switch toggled");
+        self.common.lid_closed = closed;
+        self.update_lid_state();
+    }
+
+    /// Picks up the current lid state, which libinput only reports on changes.
+    ///
+    /// Used on startup and after resuming the session, as the lid may have been
+    /// closed or opened while we weren't listening.
+    pub fn refresh_lid_state(&mut self) {
+        if let Some(closed) = read_lid_closed() {
+            if closed != self.common.lid_closed {
+                debug!(closed, "Lid state changed while inactive");
+            }
+            self.common.lid_closed = closed;
+        }
+        self.update_lid_state();
+    }
+
+    pub fn handle_tablet_mode_switch(&mut self, enabled: bool) {
+        debug!(enabled, "Tablet mode switch toggled");
+        self.common.shell.write().unwrap().set_tablet_mode(enabled);
+    }
+
+    /// Disables the built-in panel while the lid is closed and an external output is in use,
+    /// and enables it again once the lid opens or the last external output goes away.
+    ///
+    /// Needs to be re-run after hotplug, as restoring output configs may enable the panel again.
+    pub fn update_lid_state(&mut self) {
+        let shell = self.common.shell.read().unwrap();
+        let builtin = shell.builtin_output().cloned();
+        let has_external = shell
+            .outputs()
+            .any(|output| Some(output) != builtin.as_ref());
+        std::mem::drop(shell);
+
+        if self.common.lid_closed && has_external {
+            if let Some(output) = builtin {
+                if self.set_output_state(&output, OutputState::Disabled) {
+                    self.common.lid_disabled_output = Some(output.name());
+                }
+            }
+        } else if let Some(name) = self.common.lid_disabled_output.take() {
+            let output = self
+                .common
+                .output_configuration_state
+                .outputs()
+                .find(|output| output.name() == name);
+            if let Some(output) = output {
+                self.set_output_state(&output, OutputState::Enabled);
+            }
+        }
+    }
+
+    // Unlike changes through the output configuration protocol, this isn't persisted,
+    // so the panel comes back with its old config.
+    fn set_output_state(&mut self, output: &Output, state: OutputState) -> bool {
+        let backup = std::mem::replace(
+            &mut output
+                .user_data()
+                .get::<RefCell<OutputConfig>>()
+                .unwrap()
+                .borrow_mut()
+                .enabled,
+            state.clone(),
+        );
+
+        if let Err(err) = self.backend.apply_config_for_outputs(
+            false,
+            &self.common.event_loop_handle,
+            self.common.shell.clone(),
+            &mut self.common.workspace_state.update(),
+            &self.common.xdg_activation_state,
+            self.common.startup_done.clone(),
+        ) {
+            warn!(
+                ?err,
+                output = output.name(),
+                "Failed to apply lid state. Resetting"
+            );
+            output
+                .user_data()
+                .get::<RefCell<OutputConfig>>()
+                .unwrap()
+                .borrow_mut()
+                .enabled = backup;
+            if let Err(err) = self.backend.apply_config_for_outputs(
+                false,
+                &self.common.event_loop_handle,
+                self.common.shell.clone(),
+                &mut self.common.workspace_state.update(),
+                &self.common.xdg_activation_state,
+                self.common.startup_done.clone(),
+            ) {
+                error!(?err, "Failed to reset output config.");
+            }
+            return false;
+        }
+        self.common.refresh();
+
+        if state == OutputState::Disabled {
+            self.common.output_configuration_state.disable_head(output);
+        } else {
+            self.common.output_configuration_state.enable_head(output);
+        }
+        self.common.event_loop_handle.insert_idle(move |state| {
+            state.common.output_configuration_state.update();
+        });
+
+        true
+    }
+}
+
+fn read_lid_closed() -> Option<bool> {
+    let lids = std::fs::read_dir("/proc/acpi/button/lid").ok()?;
+    let mut found = None;
+    for lid in lids.flatten() {
+        let Ok(state) = std::fs::read_to_string(lid.path().join("state")) else {
+            continue;
+        };
+        // e.g. "state:      closed"
+        let closed = state.split_whitespace().last() == Some("closed");
+        found = Some(found.unwrap_or(false) || closed);
+    }
+    found
+}
@@ -0,0 +1,108 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use super::{OutputInfo, WindowInfo};
+
+use serde::{Deserialize, Serialize};
+use smithay::reexports::calloop::channel::Sender;
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "event")]
+pub enum Event {
+    WorkspaceActivated {
+        output: String,
+        index: usize,
+    },
+    WindowMapped {
+        window: WindowInfo,
+    },
+    WindowUnmapped {
+        id: Option<String>,
+        app_id: String,
+        title: String,
+    },
+    WindowFocused {
+        window: Option<WindowInfo>,
+    },
+    OutputAdded {
+        output: OutputInfo,
+    },
+    OutputRemoved {
+        name: String,
+    },
+    OverviewMode {
+        active: bool,
+    },
+    ResizeMode {
+        active: bool,
+    },
+    TabletMode {
+        active: bool,
+    },
+}
+
+/// Publishes [`Event`]s from the shell to subscribed ipc clients.
+///
+/// Publishing is a no-op until the ipc socket is set up and has subscribers,
+/// so callers should check [`EventQueue::is_active`] before building expensive events.
+#[derive(Default)]
+pub struct EventQueue {
+    sender: Option<Sender<Event>>,
+    active: Arc<AtomicBool>,
+    last_focus: Mutex<Option<String>>,
+}
+
+impl fmt::Debug for EventQueue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventQueue")
+            .field("sender", &self.sender.is_some())
+            .field("active", &self.active)
+            .finish()
+    }
+}
+
+impl EventQueue {
+    pub(super) fn new(sender: Sender<Event>, active: Arc<AtomicBool>) -> Self {
+        EventQueue {
+            sender: Some(sender),
+            active,
+            last_focus: Mutex::new(None),
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.sender.is_some() && self.active.load(Ordering::SeqCst)
+    }
+
+    pub fn publish(&self, event: Event) {
+        if !self.is_active() {
+            return;
+        }
+        if let Some(sender) = self.sender.as_ref() {
+            let _ = sender.send(event);
+        }
+    }
+
+    /// Publishes a focus change, if `window` differs from the last published focus
+    pub fn publish_focus(&self, window: Option<WindowInfo>) {
+        if !self.is_active() {
+            return;
+        }
+        let id = window.as_ref().map(|w| {
+            w.id.clone()
+                .unwrap_or_else(|| format!("{}:{}", w.app_id, w.title))
+        });
+        let mut last_focus = self.last_focus.lock().unwrap();
+        if *last_focus != id {
+            *last_focus = id;
+            std::mem::drop(last_focus);
+            self.publish(Event::WindowFocused { window });
+        }
+    }
+}
@@ -0,0 +1,596 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! JSON-lines control socket for scripts and status bars.
+//!
+//! Every line sent by a client is parsed as a [`Request`] and answered with exactly
+//! one line containing a [`Response`]. After [`Request::Subscribe`] the client
+//! additionally receives every [`Event`] as its own line.
+
+use crate::{
+    config::{Action, CompAction},
+    shell::{CosmicMapped, CosmicSurface},
+    state::{Common, State},
+    utils::prelude::*,
+};
+
+use anyhow::{Context, Result};
+use cosmic_settings_config::shortcuts;
+use serde::{Deserialize, Serialize};
+use smithay::{
+    output::Output,
+    reexports::calloop::{generic::Generic, Interest, LoopHandle, Mode, PostAction},
+    utils::Rectangle,
+    wayland::foreign_toplevel_list::ForeignToplevelHandle,
+};
+use std::{
+    cell::RefCell,
+    io::{ErrorKind, Read, Write},
+    net::Shutdown,
+    os::unix::{
+        io::{AsFd, BorrowedFd},
+        net::{UnixListener, UnixStream},
+    },
+    path::PathBuf,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+use tracing::{debug, info, warn};
+
+mod events;
+pub use self::events::*;
+
+/// Environment variable advertising the socket to child processes
+pub const SOCKET_ENV: &str = "COSMIC_COMP_SOCKET";
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "request")]
+pub enum Request {
+    Outputs,
+    Workspaces,
+    Windows,
+    Action { action: shortcuts::Action },
+    CompAction { action: CompAction },
+    Subscribe,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "response")]
+pub enum Response {
+    Outputs { outputs: Vec<OutputInfo> },
+    Workspaces { workspaces: Vec<WorkspaceInfo> },
+    Windows { windows: Vec<WindowInfo> },
+    Ok,
+    Error { message: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Geometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl<Kind> From<Rectangle<i32, Kind>> for Geometry {
+    fn from(rect: Rectangle<i32, Kind>) -> Self {
+        Geometry {
+            x: rect.loc.x,
+            y: rect.loc.y,
+            width: rect.size.w,
+            height: rect.size.h,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputInfo {
+    pub name: String,
+    pub make: String,
+    pub model: String,
+    pub geometry: Geometry,
+    pub scale: f64,
+    pub refresh: Option<i32>,
+    pub active_workspace: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceInfo {
+    pub output: String,
+    pub index: usize,
+    pub active: bool,
+    pub tiling: bool,
+    pub windows: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WindowInfo {
+    /// Identifier as advertised by `ext-foreign-toplevel-list`
+    pub id: Option<String>,
+    pub app_id: String,
+    pub title: String,
+    pub output: String,
+    /// Index of the workspace on `output`, `None` for sticky windows
+    pub workspace: Option<usize>,
+    pub geometry: Option<Geometry>,
+    pub floating: bool,
+    pub sticky: bool,
+    pub maximized: bool,
+    pub fullscreen: bool,
+    pub minimized: bool,
+    pub activated: bool,
+}
+
+/// Clients are dropped, if they let more output than this pile up
+const MAX_QUEUED_BYTES: usize = 1024 * 1024;
+/// Clients are dropped, if they send a longer line than this
+const MAX_REQUEST_BYTES: usize = 64 * 1024;
+
+type Subscribers = Rc<RefCell<Vec<Rc<RefCell<Client>>>>>;
+
+/// Write half of a client connection
+struct Client {
+    stream: UnixStream,
+    queue: Vec<u8>,
+}
+
+impl Client {
+    fn send<T: Serialize>(&mut self, message: &T) -> Result<()> {
+        let mut bytes = serde_json::to_vec(message).context("Failed to encode ipc message")?;
+        bytes.push(b'\n');
+        if self.queue.len() + bytes.len() > MAX_QUEUED_BYTES {
+            anyhow::bail!("Client isn't reading its messages");
+        }
+        self.queue.extend_from_slice(&bytes);
+        self.flush()
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        while !self.queue.is_empty() {
+            match self.stream.write(&self.queue) {
+                Ok(0) => anyhow::bail!("Client closed the connection"),
+                Ok(len) => {
+                    self.queue.drain(..len);
+                }
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err).context("Failed to write ipc message"),
+            }
+        }
+        Ok(())
+    }
+
+    /// Wakes up the read half, which then removes the client
+    fn disconnect(&self) {
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+/// Read half of a client connection
+struct ClientStream {
+    stream: UnixStream,
+    buffer: Vec<u8>,
+}
+impl AsFd for ClientStream {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.stream.as_fd()
+    }
+}
+
+pub fn socket_path(common: &Common) -> Option<PathBuf> {
+    let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR")?;
+    let mut path = PathBuf::from(runtime_dir);
+    path.push(format!(
+        "cosmic-comp-{}.sock",
+        common.socket.to_string_lossy()
+    ));
+    Some(path)
+}
+
+pub fn init_socket(handle: LoopHandle<'static, State>, common: &Common) -> Result<()> {
+    let path = socket_path(common).context("XDG_RUNTIME_DIR is not set")?;
+    // clean up after a previous instance, that didn't shut down cleanly
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path)
+        .with_context(|| format!("Failed to bind ipc socket at {}", path.display()))?;
+    listener
+        .set_nonblocking(true)
+        .context("Failed to set ipc socket non-blocking")?;
+
+    let subscribers = Subscribers::default();
+    let active = Arc::new(AtomicBool::new(false));
+    let (sender, receiver) = calloop::channel::channel();
+    let event_subscribers = subscribers.clone();
+    let event_active = active.clone();
+    handle
+        .insert_source(receiver, move |event, _, _state| {
+            if let calloop::channel::Event::Msg(event) = event {
+                let mut subscribers = event_subscribers.borrow_mut();
+                subscribers.retain(|client| {
+                    let mut client = client.borrow_mut();
+                    if let Err(err) = client.send(&event) {
+                        debug!(?err, "Dropping ipc subscriber");
+                        client.disconnect();
+                        false
+                    } else {
+                        true
+                    }
+                });
+                event_active.store(!subscribers.is_empty(), Ordering::SeqCst);
+            }
+        })
+        .map_err(|err| err.error)
+        .context("Failed to init the ipc event source")?;
+    common.shell.write().unwrap().ipc_events = EventQueue::new(sender, active.clone());
+
+    let client_handle = handle.clone();
+    handle
+        .insert_source(
+            Generic::new(listener, Interest::READ, Mode::Level),
+            move |_, listener, _state| {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        if let Err(err) =
+                            add_client(&client_handle, stream, subscribers.clone(), active.clone())
+                        {
+                            warn!(?err, "Failed to accept ipc client");
+                        }
+                    }
+                    Err(err) if err.kind() == ErrorKind::WouldBlock => {}
+                    Err(err) => warn!(?err, "Error accepting ipc client"),
+                }
+                Ok(PostAction::Continue)
+            },
+        )
+        .map_err(|err| err.error)
+        .context("Failed to init the ipc socket source")?;
+
+    info!("Listening for ipc clients on {}", path.display());
+    Ok(())
+}
+
+fn add_client(
+    handle: &LoopHandle<'static, State>,
+    stream: UnixStream,
+    subscribers: Subscribers,
+    active: Arc<AtomicBool>,
+) -> Result<()> {
+    stream
+        .set_nonblocking(true)
+        .context("Failed to set ipc client non-blocking")?;
+    let client = Rc::new(RefCell::new(Client {
+        stream: stream
+            .try_clone()
+            .context("Failed to duplicate ipc client stream")?,
+        queue: Vec::new(),
+    }));
+
+    let subscribe = {
+        let subscribers = subscribers.clone();
+        let active = active.clone();
+        move |client: &Rc<RefCell<Client>>| {
+            let mut subscribers = subscribers.borrow_mut();
+            if !subscribers
+                .iter()
+                .any(|subscriber| Rc::ptr_eq(subscriber, client))
+            {
+                subscribers.push(client.clone());
+            }
+            active.store(true, Ordering::SeqCst);
+        }
+    };
+
+    let remove = move |client: &Rc<RefCell<Client>>| -> std::io::Result<PostAction> {
+        let mut subscribers = subscribers.borrow_mut();
+        subscribers.retain(|subscriber| !Rc::ptr_eq(subscriber, client));
+        active.store(!subscribers.is_empty(), Ordering::SeqCst);
+        Ok(PostAction::Remove)
+    };
+    handle
+        .insert_source(
+            Generic::new(
+                ClientStream {
+                    stream,
+                    buffer: Vec::new(),
+                },
+                Interest::BOTH,
+                Mode::Edge,
+            ),
+            move |readiness, stream, state| {
+                // SAFETY: We don't drop the stream!
+                let stream = unsafe { stream.get_mut() };
+
+                if readiness.writable {
+                    if let Err(err) = client.borrow_mut().flush() {
+                        debug!(?err, "Error writing to ipc client");
+                        return remove(&client);
+                    }
+                }
+
+                let mut buf = [0u8; 4096];
+                loop {
+                    match stream.stream.read(&mut buf) {
+                        Ok(0) => return remove(&client),
+                        Ok(len) => stream.buffer.extend_from_slice(&buf[..len]),
+                        Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                        Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                        Err(err) => {
+                            debug!(?err, "Error reading from ipc client");
+                            return remove(&client);
+                        }
+                    }
+
+                    while let Some(pos) = stream.buffer.iter().position(|b| *b == b'\n') {
+                        let line = stream.buffer.drain(..=pos).collect::<Vec<u8>>();
+                        let line = String::from_utf8_lossy(&line[..pos]);
+                        if line.trim().is_empty() {
+                            continue;
+                        }
+
+                        let response = match serde_json::from_str::<Request>(&line) {
+                            Ok(Request::Subscribe) => {
+                                subscribe(&client);
+                                Response::Ok
+                            }
+                            Ok(request) => state.handle_ipc_request(request),
+                            Err(err) => Response::Error {
+                                message: format!("Invalid request: {}", err),
+                            },
+                        };
+
+                        if let Err(err) = client.borrow_mut().send(&response) {
+                            debug!(?err, "Error writing to ipc client");
+                            return remove(&client);
+                        }
+                    }
+
+                    if stream.buffer.len() > MAX_REQUEST_BYTES {
+                        debug!("Dropping ipc client sending oversized requests");
+                        return remove(&client);
+                    }
+                }
+
+                Ok(PostAction::Continue)
+            },
+        )
+        .map_err(|err| err.error)
+        .context("Failed to init ipc client source")?;
+
+    Ok(())
+}
+
+impl State {
+    pub fn handle_ipc_request(&mut self, request: Request) -> Response {
+        match request {
+            Request::Outputs => Response::Outputs {
+                outputs: output_infos(&self.common.shell.read().unwrap()),
+            },
+            Request::Workspaces => Response::Workspaces {
+                workspaces: workspace_infos(&self.common.shell.read().unwrap()),
+            },
+            Request::Windows => Response::Windows {
+                windows: window_infos(&self.common.shell.read().unwrap()),
+            },
+            Request::Action { action } => self.handle_ipc_action(Action::Shortcut(action)),
+            Request::CompAction { action } => self.handle_ipc_action(Action::Comp(action)),
+            Request::Subscribe => Response::Error {
+                message: String::from("Subscriptions are only supported on the ipc socket"),
+            },
+        }
+    }
+
+    fn handle_ipc_action(&mut self, action: Action) -> Response {
+        let seat = self
+            .common
+            .shell
+            .read()
+            .unwrap()
+            .seats
+            .last_active()
+            .clone();
+        self.handle_unbound_action(action, &seat);
+        Response::Ok
+    }
+}
+
+pub fn output_info(shell: &Shell, output: &Output) -> OutputInfo {
+    let props = output.physical_properties();
+    OutputInfo {
+        name: output.name(),
+        make: props.make,
+        model: props.model,
+        geometry: output.geometry().into(),
+        scale: output.current_scale().fractional_scale(),
+        refresh: output.current_mode().map(|mode| mode.refresh),
+        active_workspace: shell.workspaces.active_num(output).1,
+    }
+}
+
+pub fn output_infos(shell: &Shell) -> Vec<OutputInfo> {
+    shell
+        .outputs()
+        .map(|output| output_info(shell, output))
+        .collect()
+}
+
+pub fn workspace_infos(shell: &Shell) -> Vec<WorkspaceInfo> {
+    shell
+        .workspaces
+        .iter()
+        .flat_map(|(output, set)| {
+            set.workspaces
+                .iter()
+                .enumerate()
+                .map(move |(index, workspace)| WorkspaceInfo {
+                    output: output.name(),
+                    index,
+                    active: set.active == index,
+                    tiling: workspace.tiling_enabled,
+                    windows: workspace.mapped().count() + workspace.minimized_windows.len(),
+                })
+        })
+        .collect()
+}
+
+pub fn window_info(
+    shell: &Shell,
+    mapped: &CosmicMapped,
+    window: &CosmicSurface,
+    output: &Output,
+    workspace: Option<usize>,
+) -> WindowInfo {
+    let floating = match workspace {
+        Some(_) => shell
+            .space_for(mapped)
+            .map(|space| space.is_floating(mapped))
+            .unwrap_or(false),
+        None => true,
+    };
+
+    WindowInfo {
+        id: window_id(window),
+        app_id: window.app_id(),
+        title: window.title(),
+        output: output.name(),
+        workspace,
+        geometry: shell.element_geometry(mapped).map(Into::into),
+        floating,
+        sticky: workspace.is_none(),
+        maximized: window.is_maximized(false),
+        fullscreen: window.is_fullscreen(false),
+        minimized: window.is_minimized(),
+        activated: window.is_activated(false),
+    }
+}
+
+fn for_each_window<F>(shell: &Shell, mut f: F)
+where
+    F: FnMut(&CosmicMapped, CosmicSurface, &Output, Option<usize>),
+{
+    for (output, set) in shell.workspaces.iter() {
+        let sticky = set
+            .sticky_layer
+            .mapped()
+            .chain(set.minimized_windows.iter().map(|m| &m.window));
+        for mapped in sticky {
+            for (window, _) in mapped.windows() {
+                f(mapped, window, output, None);
+            }
+        }
+
+        for (index, workspace) in set.workspaces.iter().enumerate() {
+            let mapped = workspace
+                .mapped()
+                .chain(workspace.minimized_windows.iter().map(|m| &m.window));
+            for mapped in mapped {
+                for (window, _) in mapped.windows() {
+                    f(mapped, window, output, Some(index));
+                }
+            }
+        }
+    }
+}
+
+pub fn window_infos(shell: &Shell) -> Vec<WindowInfo> {
+    let mut windows = Vec::new();
+    for_each_window(shell, |mapped, window, output, workspace| {
+        windows.push(window_info(shell, mapped, &window, output, workspace));
+    });
+    windows
+}
+
+pub fn find_window_info(shell: &Shell, surface: &CosmicSurface) -> Option<WindowInfo> {
+    let mut info = None;
+    for_each_window(shell, |mapped, window, output, workspace| {
+        if info.is_none() && &window == surface {
+            info = Some(window_info(shell, mapped, &window, output, workspace));
+        }
+    });
+    info
+}
+
+pub fn window_id(window: &CosmicSurface) -> Option<String> {
+    window
+        .user_data()
+        .get::<Mutex<Option<ForeignToplevelHandle>>>()
+        .and_then(|handle| {
+            handle
+                .lock()
+                .unwrap()
+                .as_ref()
+                .map(|handle| handle.identifier().to_string())
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_requests() {
+        assert!(matches!(
+            serde_json::from_str::<Request>(r#"{"request":"windows"}"#),
+            Ok(Request::Windows)
+        ));
+        assert!(matches!(
+            serde_json::from_str::<Request>(r#"{"request":"subscribe"}"#),
+            Ok(Request::Subscribe)
+        ));
+        assert!(matches!(
+            serde_json::from_str::<Request>(
+                r#"{"request":"comp_action","action":"toggle_scratchpad"}"#
+            ),
+            Ok(Request::CompAction {
+                action: CompAction::ToggleScratchpad
+            })
+        ));
+        assert!(serde_json::from_str::<Request>(r#"{"request":"unknown"}"#).is_err());
+        assert!(serde_json::from_str::<Request>(r#"{"action":"toggle_scratchpad"}"#).is_err());
+        assert!(serde_json::from_str::<Request>(r#"{"request":"comp_action"}"#).is_err());
+    }
+
+    #[test]
+    fn test_serialize_responses() {
+        assert_eq!(
+            serde_json::to_string(&Response::Ok).unwrap(),
+            r#"{"response":"ok"}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&Response::Error {
+                message: String::from("Invalid request")
+            })
+            .unwrap(),
+            r#"{"response":"error","message":"Invalid request"}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&Response::Workspaces {
+                workspaces: vec![WorkspaceInfo {
+                    output: String::from("HDMI-A-1"),
+                    index: 0,
+                    active: true,
+                    tiling: false,
+                    windows: 2,
+                }]
+            })
+            .unwrap(),
+            r#"{"response":"workspaces","workspaces":[{"output":"HDMI-A-1","index":0,"active":true,"tiling":false,"windows":2}]}"#
+        );
+    }
+
+    #[test]
+    fn test_geometry_from_rectangle() {
+        let rect =
+            Rectangle::<i32, smithay::utils::Logical>::from_loc_and_size((10, 20), (300, 400));
+        assert_eq!(
+            Geometry::from(rect),
+            Geometry {
+                x: 10,
+                y: 20,
+                width: 300,
+                height: 400,
+            }
+        );
+    }
+}
@@ -21,6 +21,7 @@ pub mod dbus;
 #[cfg(feature = "debug")]
 pub mod debug;
 pub mod input;
+pub mod ipc;
 mod logger;
 pub mod session;
 pub mod shell;
@@ -107,6 +108,12 @@ fn main() -> Result<()> {
     // init backend
     backend::init_backend_auto(&display, &mut event_loop, &mut state)?;
 
+    if let Err(err) = ipc::init_socket(event_loop.handle(), &state.common) {
+        warn!(?err, "Failed to setup ipc socket");
+    }
+
+    shell::restore::init(&event_loop.handle());
+
     if let Err(err) = theme::watch_theme(event_loop.handle()) {
         warn!(?err, "Failed to watch theme");
     }
@@ -169,6 +176,9 @@ fn main() -> Result<()> {
         }
     })?;
 
+    // finish writing a running recording, instead of leaving a partial file behind
+    state.finish_recording();
+
     // kill kiosk child if loop exited
     if let Some(mut child) = state.common.kiosk_child.take() {
         let _ = child.kill();
@@ -194,7 +204,7 @@ fn init_wayland_display(
     event_loop
         .handle()
         .insert_source(source, |client_stream, _, state| {
-            let client_state = state.new_client_state();
+            let client_state = state.new_client_state(&client_stream);
             if let Err(err) = state
                 .common
                 .display_handle
@@ -6,6 +6,7 @@ use smithay::reexports::{
 };
 
 use anyhow::{anyhow, Context, Result};
+use cosmic_comp_config::security::Privilege;
 use sendfd::RecvWithFd;
 use serde::{Deserialize, Serialize};
 use std::{
@@ -72,6 +73,12 @@ pub fn get_env(common: &Common) -> Result<HashMap<String, String>> {
     if let Some(display) = common.xwayland_state.as_ref().map(|s| s.display) {
         env.insert(String::from("DISPLAY"), format!(":{}", display));
     }
+    if let Some(path) = crate::ipc::socket_path(common).filter(|path| path.exists()) {
+        env.insert(
+            String::from(crate::ipc::SOCKET_ENV),
+            path.to_string_lossy().into_owned(),
+        );
+    }
     Ok(env)
 }
 
@@ -146,8 +153,8 @@ pub fn setup_socket(handle: LoopHandle<State>, common: &Common) -> Result<()> {
                                                     }
                                                     let stream = unsafe { UnixStream::from_raw_fd(fd) };
                                                     let client_state = Arc::new(ClientState {
-                                                        privileged: true,
-                                                        ..state.new_client_state()
+                                                        privileges: Privilege::ALL.into_iter().collect(),
+                                                        ..state.new_client_state(&stream)
                                                     });
                                                     if let Err(err) = state.common.display_handle.insert_client(stream, client_state) {
                                                         warn!(?err, "Failed to add privileged client to display");
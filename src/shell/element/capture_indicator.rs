@@ -0,0 +1,65 @@
+use crate::{
+    fl,
+    utils::iced::{IcedElement, Program},
+};
+
+use calloop::LoopHandle;
+use cosmic::{
+    iced::widget::{container, horizontal_space, row},
+    iced_core::{Alignment, Background, Border, Color, Length},
+    theme,
+    widget::{icon::from_name, text},
+    Apply,
+};
+use smithay::utils::Size;
+
+pub type CaptureIndicator = IcedElement<CaptureIndicatorInternal>;
+
+pub fn capture_indicator(
+    evlh: LoopHandle<'static, crate::state::State>,
+    theme: cosmic::Theme,
+) -> CaptureIndicator {
+    let indicator =
+        CaptureIndicator::new(CaptureIndicatorInternal, Size::from((1, 1)), evlh, theme);
+    let size = indicator.minimum_size();
+    indicator.resize(size);
+    indicator
+}
+
+pub struct CaptureIndicatorInternal;
+
+impl Program for CaptureIndicatorInternal {
+    type Message = ();
+
+    fn view(&self) -> cosmic::Element<'_, Self::Message> {
+        row(vec![
+            from_name("media-record-symbolic")
+                .size(16)
+                .prefer_svg(true)
+                .icon()
+                .into(),
+            horizontal_space(8).into(),
+            text(fl!("screencopy-indicator"))
+                .font(cosmic::font::FONT)
+                .size(14)
+                .into(),
+        ])
+        .align_items(Alignment::Center)
+        .padding([4, 12])
+        .apply(container)
+        .style(theme::Container::custom(|theme| container::Appearance {
+            icon_color: Some(Color::from(theme.cosmic().destructive.on)),
+            text_color: Some(Color::from(theme.cosmic().destructive.on)),
+            background: Some(Background::Color(theme.cosmic().destructive_color().into())),
+            border: Border {
+                radius: 12.0.into(),
+                width: 0.0,
+                color: Color::TRANSPARENT,
+            },
+            shadow: Default::default(),
+        }))
+        .width(Length::Shrink)
+        .height(Length::Shrink)
+        .into()
+    }
+}
@@ -7,6 +7,7 @@ use crate::{
     utils::{iced::IcedElementInternal, prelude::*},
 };
 use calloop::LoopHandle;
+use cosmic_comp_config::DecorationsConfig;
 use id_tree::NodeId;
 use smithay::{
     backend::{
@@ -58,6 +59,7 @@ pub mod stack;
 pub use self::stack::CosmicStack;
 pub mod window;
 pub use self::window::CosmicWindow;
+pub mod capture_indicator;
 pub mod resize_indicator;
 pub mod stack_hover;
 pub mod swap_indicator;
@@ -109,6 +111,7 @@ pub struct CosmicMapped {
     pub last_geometry: Arc<Mutex<Option<Rectangle<i32, Local>>>>,
     pub moved_since_mapped: Arc<AtomicBool>,
     pub floating_tiled: Arc<Mutex<Option<TiledCorners>>>,
+    pub always_on_top: Arc<AtomicBool>,
     //sticky
     pub previous_layer: Arc<Mutex<Option<ManagedLayer>>>,
 
@@ -126,6 +129,7 @@ impl fmt::Debug for CosmicMapped {
             .field("resize_state", &self.resize_state)
             .field("last_geometry", &self.last_geometry)
             .field("moved_since_mapped", &self.moved_since_mapped)
+            .field("always_on_top", &self.always_on_top)
             .field("floating_tiled", &self.floating_tiled)
             .finish()
     }
@@ -469,6 +473,7 @@ impl CosmicMapped {
     }
 
     pub fn min_size(&self) -> Option<Size<i32, Logical>> {
+        let header = Size::from((0, self.header_height().unwrap_or(0)));
         match &self.element {
             CosmicMappedInternal::Stack(stack) => {
                 stack.surfaces().fold(None, |min_size, window| {
@@ -485,11 +490,13 @@ impl CosmicMapped {
             CosmicMappedInternal::Window(window) => window.surface().min_size(),
             _ => unreachable!(),
         }
+        .map(|size| size + header)
     }
 
     pub fn max_size(&self) -> Option<Size<i32, Logical>> {
         match &self.element {
             CosmicMappedInternal::Stack(stack) => {
+                let header = Size::from((0, stack.tab_height()));
                 let theoretical_max = stack.surfaces().fold(None, |max_size, window| {
                     let win_max_size = window.max_size();
                     match (max_size, win_max_size) {
@@ -516,6 +523,7 @@ impl CosmicMapped {
                         ),
                     }
                 });
+                let theoretical_max = theoretical_max.map(|size| size + header);
                 // The problem is, with accumulated sizes, the minimum size could be larger than our maximum...
                 let min_size = self.min_size();
                 match (theoretical_max, min_size) {
@@ -524,7 +532,10 @@ impl CosmicMapped {
                     (Some(max), Some(min)) => Some((max.w.max(min.w), max.h.max(min.h)).into()),
                 }
             }
-            CosmicMappedInternal::Window(window) => window.surface().max_size(),
+            CosmicMappedInternal::Window(window) => window
+                .surface()
+                .max_size()
+                .map(|size| size + (0, window.ssd_height(false)).into()),
             _ => unreachable!(),
         }
     }
@@ -574,6 +585,18 @@ impl CosmicMapped {
         }
     }
 
+    /// Height of the server-side header or tab bar, windows can be dropped onto to stack them
+    pub fn header_height(&self) -> Option<i32> {
+        match &self.element {
+            CosmicMappedInternal::Stack(stack) => Some(stack.tab_height()),
+            CosmicMappedInternal::Window(window) => {
+                let height = window.ssd_height(false);
+                (height > 0).then_some(height)
+            }
+            _ => None,
+        }
+    }
+
     pub fn stack_ref(&self) -> Option<&CosmicStack> {
         match &self.element {
             CosmicMappedInternal::Stack(stack) => Some(stack),
@@ -592,6 +615,7 @@ impl CosmicMapped {
         &mut self,
         (output, overlap): (&Output, Rectangle<i32, Logical>),
         theme: cosmic::Theme,
+        decorations: DecorationsConfig,
     ) {
         match &self.element {
             CosmicMappedInternal::Window(window) => {
@@ -599,7 +623,7 @@ impl CosmicMapped {
                 let activated = surface.is_activated(true);
                 let handle = window.loop_handle();
 
-                let stack = CosmicStack::new(std::iter::once(surface), handle, theme);
+                let stack = CosmicStack::new(std::iter::once(surface), handle, theme, decorations);
                 if let Some(geo) = self.last_geometry.lock().unwrap().clone() {
                     stack.set_geometry(geo.to_global(&output));
                 }
@@ -619,11 +643,13 @@ impl CosmicMapped {
         surface: CosmicSurface,
         (output, overlap): (&Output, Rectangle<i32, Logical>),
         theme: cosmic::Theme,
+        decorations: DecorationsConfig,
     ) {
         let handle = self.loop_handle();
         surface.try_force_undecorated(false);
         surface.set_tiled(false);
-        let window = CosmicWindow::new(surface, handle, theme);
+        let window = CosmicWindow::new(surface, handle, theme, decorations);
+        window.set_tiling_layer(self.tiling_node_id.lock().unwrap().is_some());
 
         if let Some(geo) = self.last_geometry.lock().unwrap().clone() {
             window.set_geometry(geo.to_global(&output));
@@ -847,6 +873,7 @@ impl CosmicMapped {
             p_elements: Vec::new(),
         };
 
+        let alpha = alpha * self.active_window().opacity();
         #[cfg_attr(not(feature = "debug"), allow(unused_mut))]
         elements.extend_map(
             match &self.element {
@@ -874,6 +901,21 @@ impl CosmicMapped {
         }
     }
 
+    pub(crate) fn update_decorations(&self, decorations: DecorationsConfig) {
+        match &self.element {
+            CosmicMappedInternal::Window(w) => w.set_decorations(decorations),
+            CosmicMappedInternal::Stack(s) => s.set_decorations(decorations),
+            CosmicMappedInternal::_GenericCatcher(_) => {}
+        }
+    }
+
+    /// Moves the element into or out of a tiling layer, which might hide the header of a window
+    pub fn set_tiling_layer(&self, tiling: bool) {
+        if let CosmicMappedInternal::Window(window) = &self.element {
+            window.set_tiling_layer(tiling);
+        }
+    }
+
     pub(crate) fn force_redraw(&self) {
         match &self.element {
             CosmicMappedInternal::Window(w) => w.force_redraw(),
@@ -1027,6 +1069,7 @@ impl From<CosmicWindow> for CosmicMapped {
             last_geometry: Arc::new(Mutex::new(None)),
             moved_since_mapped: Arc::new(AtomicBool::new(false)),
             floating_tiled: Arc::new(Mutex::new(None)),
+            always_on_top: Arc::new(AtomicBool::new(false)),
             previous_layer: Arc::new(Mutex::new(None)),
             #[cfg(feature = "debug")]
             debug: Arc::new(Mutex::new(None)),
@@ -1045,6 +1088,7 @@ impl From<CosmicStack> for CosmicMapped {
             last_geometry: Arc::new(Mutex::new(None)),
             moved_since_mapped: Arc::new(AtomicBool::new(false)),
             floating_tiled: Arc::new(Mutex::new(None)),
+            always_on_top: Arc::new(AtomicBool::new(false)),
             previous_layer: Arc::new(Mutex::new(None)),
             #[cfg(feature = "debug")]
             debug: Arc::new(Mutex::new(None)),
@@ -1,4 +1,8 @@
-use super::{surface::RESIZE_BORDER, window::Focus, CosmicSurface};
+use super::{
+    surface::RESIZE_BORDER,
+    window::{titlebar_button, Focus},
+    CosmicSurface,
+};
 use crate::{
     backend::render::{cursor::CursorState, SplitRenderElements},
     shell::{
@@ -20,6 +24,7 @@ use cosmic::{
     iced_widget::scrollable::AbsoluteOffset,
     theme, widget as cosmic_widget, Apply, Element as CosmicElement, Theme,
 };
+use cosmic_comp_config::{DecorationsConfig, TitlebarButton};
 use cosmic_settings_config::shortcuts;
 use once_cell::sync::Lazy;
 use shortcuts::action::{Direction, FocusDirection};
@@ -102,6 +107,7 @@ pub struct CosmicStackInternal {
     last_seat: Arc<Mutex<Option<(Seat<State>, Serial)>>>,
     geometry: Arc<Mutex<Option<Rectangle<i32, Global>>>>,
     mask: Arc<Mutex<Option<tiny_skia::Mask>>>,
+    decorations: Arc<Mutex<DecorationsConfig>>,
 }
 
 impl CosmicStackInternal {
@@ -113,9 +119,23 @@ impl CosmicStackInternal {
     pub fn current_focus(&self) -> Option<Focus> {
         unsafe { Focus::from_u8(self.pointer_entered.load(Ordering::SeqCst)) }
     }
+
+    fn tab_height(&self) -> i32 {
+        tab_height(&self.decorations.lock().unwrap())
+    }
 }
 
 pub const TAB_HEIGHT: i32 = 24;
+pub const COMPACT_TAB_HEIGHT: i32 = 20;
+
+/// Height of the tab bar, as configured
+pub fn tab_height(decorations: &DecorationsConfig) -> i32 {
+    if decorations.compact {
+        COMPACT_TAB_HEIGHT
+    } else {
+        TAB_HEIGHT
+    }
+}
 
 #[derive(Debug, Clone)]
 pub enum MoveResult {
@@ -129,6 +149,7 @@ impl CosmicStack {
         windows: impl Iterator<Item = I>,
         handle: LoopHandle<'static, crate::state::State>,
         theme: cosmic::Theme,
+        decorations: DecorationsConfig,
     ) -> CosmicStack {
         let windows = windows.map(Into::into).collect::<Vec<_>>();
         assert!(!windows.is_empty());
@@ -140,6 +161,7 @@ impl CosmicStack {
         }
 
         let width = windows[0].geometry().size.w;
+        let height = tab_height(&decorations);
         CosmicStack(IcedElement::new(
             CosmicStackInternal {
                 windows: Arc::new(Mutex::new(windows)),
@@ -155,8 +177,9 @@ impl CosmicStack {
                 last_seat: Arc::new(Mutex::new(None)),
                 geometry: Arc::new(Mutex::new(None)),
                 mask: Arc::new(Mutex::new(None)),
+                decorations: Arc::new(Mutex::new(decorations)),
             },
-            (width, TAB_HEIGHT),
+            (width, height),
             handle,
             theme,
         ))
@@ -168,8 +191,8 @@ impl CosmicStack {
         window.set_tiled(true);
         self.0.with_program(|p| {
             if let Some(mut geo) = p.geometry.lock().unwrap().clone() {
-                geo.loc.y += TAB_HEIGHT;
-                geo.size.h -= TAB_HEIGHT;
+                geo.loc.y += p.tab_height();
+                geo.size.h -= p.tab_height();
                 window.set_geometry(geo);
             }
             window.send_configure();
@@ -187,8 +210,10 @@ impl CosmicStack {
             }
             p.scroll_to_focus.store(true, Ordering::SeqCst);
         });
-        self.0
-            .resize(Size::from((self.active().geometry().size.w, TAB_HEIGHT)));
+        self.0.resize(Size::from((
+            self.active().geometry().size.w,
+            self.tab_height(),
+        )));
         self.0.force_redraw()
     }
 
@@ -215,8 +240,10 @@ impl CosmicStack {
 
             p.active.fetch_min(windows.len() - 1, Ordering::SeqCst);
         });
-        self.0
-            .resize(Size::from((self.active().geometry().size.w, TAB_HEIGHT)));
+        self.0.resize(Size::from((
+            self.active().geometry().size.w,
+            self.tab_height(),
+        )));
         self.0.force_redraw()
     }
 
@@ -244,8 +271,10 @@ impl CosmicStack {
 
             Some(window)
         });
-        self.0
-            .resize(Size::from((self.active().geometry().size.w, TAB_HEIGHT)));
+        self.0.resize(Size::from((
+            self.active().geometry().size.w,
+            self.tab_height(),
+        )));
         self.0.force_redraw();
         window
     }
@@ -323,8 +352,10 @@ impl CosmicStack {
         });
 
         if result {
-            self.0
-                .resize(Size::from((self.active().geometry().size.w, TAB_HEIGHT)));
+            self.0.resize(Size::from((
+                self.active().geometry().size.w,
+                self.tab_height(),
+            )));
             self.0.force_update();
         }
 
@@ -370,8 +401,10 @@ impl CosmicStack {
         });
 
         if !matches!(result, MoveResult::Default) {
-            self.0
-                .resize(Size::from((self.active().geometry().size.w, TAB_HEIGHT)));
+            self.0.resize(Size::from((
+                self.active().geometry().size.w,
+                self.tab_height(),
+            )));
             self.0.force_update();
         }
 
@@ -400,8 +433,10 @@ impl CosmicStack {
                 p.previous_keyboard.store(old, Ordering::SeqCst);
             }
         });
-        self.0
-            .resize(Size::from((self.active().geometry().size.w, TAB_HEIGHT)));
+        self.0.resize(Size::from((
+            self.active().geometry().size.w,
+            self.tab_height(),
+        )));
         self.0.force_redraw()
     }
 
@@ -424,6 +459,7 @@ impl CosmicStack {
         self.0.with_program(|p| {
             let mut stack_ui = None;
             let geo = p.windows.lock().unwrap()[p.active.load(Ordering::SeqCst)].geometry();
+            let tab_height = p.tab_height();
 
             let point_i32 = relative_pos.to_i32_round::<i32>();
             if (point_i32.x - geo.loc.x >= -RESIZE_BORDER && point_i32.x - geo.loc.x < 0)
@@ -431,7 +467,7 @@ impl CosmicStack {
                 || (point_i32.x - geo.loc.x >= geo.size.w
                     && point_i32.x - geo.loc.x < geo.size.w + RESIZE_BORDER)
                 || (point_i32.y - geo.loc.y >= geo.size.h
-                    && point_i32.y - geo.loc.y < geo.size.h + TAB_HEIGHT + RESIZE_BORDER)
+                    && point_i32.y - geo.loc.y < geo.size.h + tab_height + RESIZE_BORDER)
             {
                 stack_ui = Some((
                     PointerFocusTarget::StackUI(self.clone()),
@@ -439,14 +475,14 @@ impl CosmicStack {
                 ));
             }
 
-            if point_i32.y - geo.loc.y < TAB_HEIGHT {
+            if point_i32.y - geo.loc.y < tab_height {
                 stack_ui = Some((
                     PointerFocusTarget::StackUI(self.clone()),
                     Point::from((0., 0.)),
                 ));
             }
 
-            relative_pos.y -= TAB_HEIGHT as f64;
+            relative_pos.y -= tab_height as f64;
 
             let active_window = &p.windows.lock().unwrap()[p.active.load(Ordering::SeqCst)];
             active_window
@@ -458,7 +494,7 @@ impl CosmicStack {
                             surface,
                             toplevel: Some(active_window.clone().into()),
                         },
-                        surface_offset.to_f64() + Point::from((0., TAB_HEIGHT as f64)),
+                        surface_offset.to_f64() + Point::from((0., tab_height as f64)),
                     )
                 })
                 .or(stack_ui)
@@ -466,7 +502,11 @@ impl CosmicStack {
     }
 
     pub fn offset(&self) -> Point<i32, Logical> {
-        Point::from((0, TAB_HEIGHT))
+        Point::from((0, self.tab_height()))
+    }
+
+    pub fn tab_height(&self) -> i32 {
+        self.0.with_program(|p| p.tab_height())
     }
 
     pub fn pending_size(&self) -> Option<Size<i32, Logical>> {
@@ -481,8 +521,8 @@ impl CosmicStack {
 
     pub fn set_geometry(&self, geo: Rectangle<i32, Global>) {
         self.0.with_program(|p| {
-            let loc = (geo.loc.x, geo.loc.y + TAB_HEIGHT);
-            let size = (geo.size.w, geo.size.h - TAB_HEIGHT);
+            let loc = (geo.loc.x, geo.loc.y + p.tab_height());
+            let size = (geo.size.w, geo.size.h - p.tab_height());
 
             let win_geo = Rectangle::from_loc_and_size(loc, size);
             for window in p.windows.lock().unwrap().iter() {
@@ -499,7 +539,7 @@ impl CosmicStack {
             surface.0.on_commit();
             if self.active() == surface {
                 self.0
-                    .resize(Size::from((surface.geometry().size.w, TAB_HEIGHT)));
+                    .resize(Size::from((surface.geometry().size.w, self.tab_height())));
             }
         }
     }
@@ -562,7 +602,7 @@ impl CosmicStack {
             })
             .to_physical_precise_round(scale);
         let stack_loc = location + offset;
-        let window_loc = location + Point::from((0, (TAB_HEIGHT as f64 * scale.y) as i32));
+        let window_loc = location + Point::from((0, (self.tab_height() as f64 * scale.y) as i32));
 
         let w_elements = AsRenderElements::<R>::render_elements::<CosmicStackRenderElement<R>>(
             &self.0, renderer, stack_loc, scale, alpha,
@@ -592,6 +632,14 @@ impl CosmicStack {
         self.0.set_theme(theme);
     }
 
+    pub(crate) fn set_decorations(&self, decorations: DecorationsConfig) {
+        let height = tab_height(&decorations);
+        self.0
+            .with_program(|p| *p.decorations.lock().unwrap() = decorations);
+        self.0
+            .resize(Size::from((self.active().geometry().size.w, height)));
+    }
+
     pub(crate) fn force_redraw(&self) {
         self.0.force_redraw();
     }
@@ -645,6 +693,10 @@ pub enum Message {
     PotentialTabDragStart(usize),
     Activate(usize),
     Close(usize),
+    Minimize,
+    Maximize,
+    Sticky,
+    AlwaysOnTop,
     ScrollForward,
     ScrollBack,
     Scrolled,
@@ -748,6 +800,26 @@ impl Program for CosmicStackInternal {
                     val.close()
                 }
             }
+            Message::Minimize | Message::Maximize | Message::Sticky | Message::AlwaysOnTop => {
+                let active = self.active.load(Ordering::SeqCst);
+                if let Some(surface) = self.windows.lock().unwrap()[active]
+                    .wl_surface()
+                    .map(Cow::into_owned)
+                {
+                    loop_handle.insert_idle(move |state| {
+                        let mut shell = state.common.shell.write().unwrap();
+                        if let Some(mapped) = shell.element_for_surface(&surface).cloned() {
+                            let seat = shell.seats.last_active().clone();
+                            match message {
+                                Message::Minimize => shell.minimize_request(&mapped),
+                                Message::Maximize => shell.maximize_toggle(&mapped, &seat),
+                                Message::Sticky => shell.toggle_sticky(&seat, &mapped),
+                                _ => shell.toggle_always_on_top(&seat, &mapped),
+                            }
+                        }
+                    });
+                }
+            }
             Message::Scrolled => {
                 self.scroll_to_focus.store(false, Ordering::SeqCst);
             }
@@ -758,6 +830,7 @@ impl Program for CosmicStackInternal {
                         .wl_surface()
                         .map(Cow::into_owned)
                     {
+                        let tab_height = self.tab_height();
                         loop_handle.insert_idle(move |state| {
                             let shell = state.common.shell.read().unwrap();
                             if let Some(mapped) = shell.element_for_surface(&surface).cloned() {
@@ -784,7 +857,7 @@ impl Program for CosmicStackInternal {
                                     .unwrap()
                                     .current_location()
                                     .to_i32_round();
-                                cursor.y -= TAB_HEIGHT;
+                                cursor.y -= tab_height;
                                 let res = shell.menu_request(
                                     &surface,
                                     &seat,
@@ -812,6 +885,7 @@ impl Program for CosmicStackInternal {
                         .wl_surface()
                         .map(Cow::into_owned)
                     {
+                        let tab_height = self.tab_height();
                         loop_handle.insert_idle(move |state| {
                             let shell = state.common.shell.read().unwrap();
                             if let Some(mapped) = shell.element_for_surface(&surface).cloned() {
@@ -826,7 +900,7 @@ impl Program for CosmicStackInternal {
                                         .unwrap()
                                         .current_location()
                                         .to_i32_round();
-                                    cursor.y -= TAB_HEIGHT;
+                                    cursor.y -= tab_height;
                                     let res = shell.menu_request(
                                         &surface,
                                         &seat,
@@ -862,7 +936,24 @@ impl Program for CosmicStackInternal {
         let active = self.active.load(Ordering::SeqCst);
         let group_focused = self.group_focused.load(Ordering::SeqCst);
 
-        let elements = vec![
+        let decorations = self.decorations.lock().unwrap();
+        let height = tab_height(&decorations);
+        let maximized = windows[active].is_maximized(false);
+        let button = |button| {
+            let message = match button {
+                TitlebarButton::Menu => Message::Menu,
+                TitlebarButton::Minimize => Message::Minimize,
+                TitlebarButton::Maximize => Message::Maximize,
+                TitlebarButton::Close => Message::Close(active),
+                TitlebarButton::Sticky => Message::Sticky,
+                TitlebarButton::AlwaysOnTop => Message::AlwaysOnTop,
+            };
+            titlebar_button(button, maximized, message)
+        };
+
+        let mut elements = Vec::new();
+        elements.extend(decorations.left_buttons.iter().filter_map(|b| button(*b)));
+        elements.extend([
             cosmic_widget::icon::from_name("window-stack-symbolic")
                 .size(16)
                 .prefer_svg(true)
@@ -879,7 +970,7 @@ impl Program for CosmicStackInternal {
                     theme::Svg::Default
                 })
                 .apply(iced_widget::container)
-                .padding([4, 24])
+                .padding([(height - 16) as u16 / 2, 24])
                 .center_y()
                 .apply(iced_widget::mouse_area)
                 .on_press(Message::DragStart)
@@ -898,6 +989,7 @@ impl Program for CosmicStackInternal {
                         .on_press(Message::PotentialTabDragStart(i))
                         .on_right_click(Message::TabMenu(i))
                         .on_close(Message::Close(i))
+                        .height(height)
                     }),
                     active,
                     windows[active].is_activated(false),
@@ -919,7 +1011,8 @@ impl Program for CosmicStackInternal {
                 .on_press(Message::DragStart)
                 .on_right_press(Message::Menu)
                 .into(),
-        ];
+        ]);
+        elements.extend(decorations.right_buttons.iter().filter_map(|b| button(*b)));
 
         let radius = if windows[active].is_maximized(false) {
             Radius::from(0.0)
@@ -929,7 +1022,7 @@ impl Program for CosmicStackInternal {
         let group_focused = self.group_focused.load(Ordering::SeqCst);
 
         iced_widget::row(elements)
-            .height(TAB_HEIGHT as u16)
+            .height(height as u16)
             .width(Length::Fill) //width as u16)
             .apply(iced_widget::container)
             .center_y()
@@ -966,7 +1059,10 @@ impl Program for CosmicStackInternal {
     ) {
         if self.group_focused.load(Ordering::SeqCst) {
             let border = Rectangle::from_loc_and_size(
-                (0, ((TAB_HEIGHT as f32 * scale) - scale).floor() as i32),
+                (
+                    0,
+                    ((self.tab_height() as f32 * scale) - scale).floor() as i32,
+                ),
                 (pixels.width() as i32, scale.ceil() as i32),
             );
 
@@ -1010,7 +1106,7 @@ impl SpaceElement for CosmicStack {
                 SpaceElement::bbox(&p.windows.lock().unwrap()[p.active.load(Ordering::SeqCst)]);
             bbox.loc -= Point::from((RESIZE_BORDER, RESIZE_BORDER));
             bbox.size += Size::from((RESIZE_BORDER * 2, RESIZE_BORDER * 2));
-            bbox.size.h += TAB_HEIGHT;
+            bbox.size.h += p.tab_height();
             bbox
         })
     }
@@ -1058,7 +1154,7 @@ impl SpaceElement for CosmicStack {
         self.0.with_program(|p| {
             let mut geo =
                 SpaceElement::geometry(&p.windows.lock().unwrap()[p.active.load(Ordering::SeqCst)]);
-            geo.size.h += TAB_HEIGHT;
+            geo.size.h += p.tab_height();
             geo
         })
     }
@@ -1089,8 +1185,10 @@ impl SpaceElement for CosmicStack {
             let active = p.active.load(Ordering::SeqCst);
 
             if old_active != active {
-                self.0
-                    .resize(Size::from((self.active().geometry().size.w, TAB_HEIGHT)));
+                self.0.resize(Size::from((
+                    self.active().geometry().size.w,
+                    p.tab_height(),
+                )));
                 self.0.force_redraw();
             }
 
@@ -1187,7 +1285,7 @@ impl PointerTarget<State> for CosmicStack {
         let mut event = event.clone();
         self.0.with_program(|p| {
             let active_window = &p.windows.lock().unwrap()[p.active.load(Ordering::SeqCst)];
-            let Some(next) = Focus::under(active_window, TAB_HEIGHT, event.location) else {
+            let Some(next) = Focus::under(active_window, p.tab_height(), event.location) else {
                 return;
             };
             let _old_focus = p.swap_focus(Some(next));
@@ -1217,7 +1315,7 @@ impl PointerTarget<State> for CosmicStack {
         self.0.with_program(|p| {
             let active = p.active.load(Ordering::SeqCst);
             let active_window = &p.windows.lock().unwrap()[active];
-            let Some(next) = Focus::under(active_window, TAB_HEIGHT, event.location) else {
+            let Some(next) = Focus::under(active_window, p.tab_height(), event.location) else {
                 return;
             };
             let _previous = p.swap_focus(Some(next));
@@ -1240,7 +1338,7 @@ impl PointerTarget<State> for CosmicStack {
 
         PointerTarget::motion(&self.0, seat, data, &event);
         if event.location.y < 0.0
-            || event.location.y > TAB_HEIGHT as f64
+            || event.location.y > self.tab_height() as f64
             || event.location.x < 64.0
             || event.location.x > (active_window_geo.size.w as f64 - 64.0)
         {
@@ -1462,7 +1560,7 @@ impl TouchTarget<State> for CosmicStack {
         TouchTarget::motion(&self.0, seat, data, &event, seq);
 
         if event.location.y < 0.0
-            || event.location.y > TAB_HEIGHT as f64
+            || event.location.y > self.tab_height() as f64
             || event.location.x < 64.0
             || event.location.x > (active_window_geo.size.w as f64 - 64.0)
         {
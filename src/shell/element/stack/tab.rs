@@ -150,6 +150,7 @@ pub struct Tab<Message: TabMessage> {
     rule_theme: TabRuleTheme,
     background_theme: TabBackgroundTheme,
     active: bool,
+    height: i32,
 }
 
 impl<Message: TabMessage + 'static> Tab<Message> {
@@ -165,6 +166,7 @@ impl<Message: TabMessage + 'static> Tab<Message> {
             rule_theme: TabRuleTheme::Default,
             background_theme: TabBackgroundTheme::Default,
             active: false,
+            height: TAB_HEIGHT,
         }
     }
 
@@ -183,6 +185,11 @@ impl<Message: TabMessage + 'static> Tab<Message> {
         self
     }
 
+    pub(super) fn height(mut self, height: i32) -> Self {
+        self.height = height;
+        self
+    }
+
     pub(super) fn font(mut self, font: Font) -> Self {
         self.font = font;
         self
@@ -254,6 +261,7 @@ impl<Message: TabMessage + 'static> Tab<Message> {
             elements: items,
             press_message: self.press_message,
             right_click_message: self.right_click_message,
+            height: self.height,
         }
     }
 }
@@ -273,6 +281,7 @@ pub(super) struct TabInternal<'a, Message: TabMessage> {
     elements: Vec<cosmic::Element<'a, Message>>,
     press_message: Option<Message>,
     right_click_message: Option<Message>,
+    height: i32,
 }
 
 impl<'a, Message> Widget<Message, cosmic::Theme, cosmic::Renderer> for TabInternal<'a, Message>
@@ -301,7 +310,7 @@ where
 
     fn layout(&self, tree: &mut Tree, renderer: &cosmic::Renderer, limits: &Limits) -> Node {
         let min_size = Size {
-            height: TAB_HEIGHT as f32,
+            height: self.height as f32,
             width: if self.active {
                 MIN_ACTIVE_TAB_WIDTH as f32
             } else {
@@ -1,9 +1,14 @@
 use std::{
     borrow::Cow,
-    sync::atomic::{AtomicBool, Ordering},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
     time::Duration,
 };
 
+use cosmic_comp_config::DecorationsConfig;
+
 use smithay::{
     backend::renderer::{
         element::{
@@ -30,7 +35,7 @@ use smithay::{
                 shell::server::xdg_toplevel::State as ToplevelState,
             },
         },
-        wayland_server::protocol::wl_surface::WlSurface,
+        wayland_server::{protocol::wl_surface::WlSurface, Resource},
     },
     utils::{user_data::UserDataMap, IsAlive, Logical, Rectangle, Serial, Size},
     wayland::{
@@ -43,7 +48,7 @@ use smithay::{
 
 use crate::{
     backend::render::SplitRenderElements,
-    state::{State, SurfaceDmabufFeedback},
+    state::{ClientState, State, SurfaceDmabufFeedback},
     utils::prelude::*,
     wayland::handlers::decoration::PreferredDecorationMode,
 };
@@ -84,9 +89,27 @@ impl PartialEq<X11Surface> for CosmicSurface {
 #[derive(Default)]
 struct Minimized(AtomicBool);
 
+struct Opacity(Mutex<f32>);
+
+impl Default for Opacity {
+    fn default() -> Self {
+        Opacity(Mutex::new(1.0))
+    }
+}
+
 pub const SSD_HEIGHT: i32 = 36;
+pub const COMPACT_SSD_HEIGHT: i32 = 28;
 pub const RESIZE_BORDER: i32 = 10;
 
+/// Height of server-side headers, as configured
+pub fn header_height(decorations: &DecorationsConfig) -> i32 {
+    if decorations.compact {
+        COMPACT_SSD_HEIGHT
+    } else {
+        SSD_HEIGHT
+    }
+}
+
 impl CosmicSurface {
     pub fn title(&self) -> String {
         match self.0.underlying_surface() {
@@ -122,6 +145,20 @@ impl CosmicSurface {
         }
     }
 
+    /// Process id of the client owning the surface, if known
+    pub fn pid(&self) -> Option<i32> {
+        match self.0.underlying_surface() {
+            WindowSurface::Wayland(toplevel) => {
+                toplevel
+                    .wl_surface()
+                    .client()?
+                    .get_data::<ClientState>()?
+                    .pid
+            }
+            WindowSurface::X11(surface) => surface.pid().map(|pid| pid as i32),
+        }
+    }
+
     pub fn pending_size(&self) -> Option<Size<i32, Logical>> {
         match self.0.underlying_surface() {
             WindowSurface::Wayland(toplevel) => toplevel.with_pending_state(|state| state.size),
@@ -387,6 +424,26 @@ impl CosmicSurface {
         }
     }
 
+    pub fn opacity(&self) -> f32 {
+        *self
+            .0
+            .user_data()
+            .get_or_insert_threadsafe(Opacity::default)
+            .0
+            .lock()
+            .unwrap()
+    }
+
+    pub fn set_opacity(&self, opacity: f32) {
+        *self
+            .0
+            .user_data()
+            .get_or_insert_threadsafe(Opacity::default)
+            .0
+            .lock()
+            .unwrap() = opacity.clamp(0.0, 1.0);
+    }
+
     pub fn set_suspended(&self, suspended: bool) {
         match self.0.underlying_surface() {
             WindowSurface::Wayland(window) => window.with_pending_state(|state| {
@@ -402,6 +459,7 @@ impl CosmicSurface {
         }
     }
 
+    /// Minimum size of the surface, without server-side decorations
     pub fn min_size(&self) -> Option<Size<i32, Logical>> {
         match self.0.underlying_surface() {
             WindowSurface::Wayland(toplevel) => {
@@ -416,15 +474,9 @@ impl CosmicSurface {
             }
             WindowSurface::X11(surface) => surface.min_size(),
         }
-        .map(|size| {
-            if self.is_decorated(false) {
-                size
-            } else {
-                size + (0, SSD_HEIGHT).into()
-            }
-        })
     }
 
+    /// Maximum size of the surface, without server-side decorations
     pub fn max_size(&self) -> Option<Size<i32, Logical>> {
         match self.0.underlying_surface() {
             WindowSurface::Wayland(toplevel) => {
@@ -439,13 +491,6 @@ impl CosmicSurface {
             }
             WindowSurface::X11(surface) => surface.max_size(),
         }
-        .map(|size| {
-            if self.is_decorated(false) {
-                size
-            } else {
-                size + (0, SSD_HEIGHT).into()
-            }
-        })
     }
 
     pub fn serial_acked(&self, serial: &Serial) -> bool {
@@ -13,10 +13,12 @@ use crate::{
 use calloop::LoopHandle;
 use cosmic::{
     config::Density,
-    iced::{Color, Command},
-    widget::mouse_area,
+    iced::{widget, Color, Command},
+    theme,
+    widget::{icon::from_name, mouse_area},
     Apply,
 };
+use cosmic_comp_config::{DecorationsConfig, TitlebarButton};
 use smithay::{
     backend::{
         input::KeyState,
@@ -61,7 +63,7 @@ use std::{
 use wayland_backend::server::ObjectId;
 
 use super::{
-    surface::{RESIZE_BORDER, SSD_HEIGHT},
+    surface::{header_height, RESIZE_BORDER},
     CosmicSurface,
 };
 
@@ -84,6 +86,9 @@ pub struct CosmicWindowInternal {
     pointer_entered: Arc<AtomicU8>,
     last_seat: Arc<Mutex<Option<(Seat<State>, Serial)>>>,
     last_title: Arc<Mutex<String>>,
+    decorations: Arc<Mutex<DecorationsConfig>>,
+    /// Whether the window is part of a tiling layer, which might hide its header
+    tiling_layer: Arc<AtomicBool>,
 }
 
 impl fmt::Debug for CosmicWindowInternal {
@@ -176,7 +181,19 @@ impl CosmicWindowInternal {
     }
     /// returns if the window has any current or pending server-side decorations
     pub fn has_ssd(&self, pending: bool) -> bool {
-        !self.window.is_decorated(pending)
+        self.ssd_height(pending) > 0
+    }
+
+    /// Height of the server-side header drawn above the window, zero if there is none
+    pub fn ssd_height(&self, pending: bool) -> i32 {
+        let decorations = self.decorations.lock().unwrap();
+        if self.window.is_decorated(pending)
+            || (decorations.hide_tiled_titlebars && self.tiling_layer.load(Ordering::SeqCst))
+        {
+            0
+        } else {
+            header_height(&decorations)
+        }
     }
 }
 
@@ -185,10 +202,12 @@ impl CosmicWindow {
         window: impl Into<CosmicSurface>,
         handle: LoopHandle<'static, crate::state::State>,
         theme: cosmic::Theme,
+        decorations: DecorationsConfig,
     ) -> CosmicWindow {
         let window = window.into();
         let width = window.geometry().size.w;
         let last_title = window.title();
+        let height = header_height(&decorations);
         CosmicWindow(IcedElement::new(
             CosmicWindowInternal {
                 window,
@@ -196,8 +215,10 @@ impl CosmicWindow {
                 pointer_entered: Arc::new(AtomicU8::new(0)),
                 last_seat: Arc::new(Mutex::new(None)),
                 last_title: Arc::new(Mutex::new(last_title)),
+                decorations: Arc::new(Mutex::new(decorations)),
+                tiling_layer: Arc::new(AtomicBool::new(false)),
             },
-            (width, SSD_HEIGHT),
+            (width, height),
             handle,
             theme,
         ))
@@ -206,38 +227,34 @@ impl CosmicWindow {
     pub fn pending_size(&self) -> Option<Size<i32, Logical>> {
         self.0.with_program(|p| {
             let mut size = p.window.pending_size()?;
-            if p.has_ssd(true) {
-                size.h += SSD_HEIGHT;
-            }
+            size.h += p.ssd_height(true);
             Some(size)
         })
     }
 
     pub fn set_geometry(&self, geo: Rectangle<i32, Global>) {
         self.0.with_program(|p| {
-            let loc = (
-                geo.loc.x,
-                geo.loc.y + if p.has_ssd(true) { SSD_HEIGHT } else { 0 },
-            );
-            let size = (
-                geo.size.w,
-                std::cmp::max(geo.size.h - if p.has_ssd(true) { SSD_HEIGHT } else { 0 }, 0),
-            );
+            let ssd_height = p.ssd_height(true);
+            let loc = (geo.loc.x, geo.loc.y + ssd_height);
+            let size = (geo.size.w, std::cmp::max(geo.size.h - ssd_height, 0));
             p.window
                 .set_geometry(Rectangle::from_loc_and_size(loc, size));
         });
     }
 
     pub fn on_commit(&self, surface: &WlSurface) {
-        let mut geo = None;
+        let mut size = None;
         self.0.with_program(|p| {
             if &p.window == surface {
                 p.window.0.on_commit();
-                geo = Some(p.window.geometry());
+                size = Some(Size::from((
+                    p.window.geometry().size.w,
+                    header_height(&p.decorations.lock().unwrap()),
+                )));
             }
         });
-        if let Some(geo) = geo {
-            self.0.resize(Size::from((geo.size.w, SSD_HEIGHT)));
+        if let Some(size) = size {
+            self.0.resize(size);
         }
     }
 
@@ -254,6 +271,7 @@ impl CosmicWindow {
             let mut window_ui = None;
             if p.has_ssd(false) {
                 let geo = p.window.geometry();
+                let ssd_height = p.ssd_height(false);
 
                 let point_i32 = relative_pos.to_i32_round::<i32>();
                 if (point_i32.x - geo.loc.x >= -RESIZE_BORDER && point_i32.x - geo.loc.x < 0)
@@ -261,7 +279,7 @@ impl CosmicWindow {
                     || (point_i32.x - geo.loc.x >= geo.size.w
                         && point_i32.x - geo.loc.x < geo.size.w + RESIZE_BORDER)
                     || (point_i32.y - geo.loc.y >= geo.size.h
-                        && point_i32.y - geo.loc.y < geo.size.h + SSD_HEIGHT + RESIZE_BORDER)
+                        && point_i32.y - geo.loc.y < geo.size.h + ssd_height + RESIZE_BORDER)
                 {
                     window_ui = Some((
                         PointerFocusTarget::WindowUI(self.clone()),
@@ -269,15 +287,15 @@ impl CosmicWindow {
                     ));
                 }
 
-                if point_i32.y - geo.loc.y < SSD_HEIGHT {
+                if point_i32.y - geo.loc.y < ssd_height {
                     window_ui = Some((
                         PointerFocusTarget::WindowUI(self.clone()),
                         Point::from((0., 0.)),
                     ));
                 }
 
-                relative_pos.y -= SSD_HEIGHT as f64;
-                offset.y += SSD_HEIGHT as f64;
+                relative_pos.y -= ssd_height as f64;
+                offset.y += ssd_height as f64;
             }
 
             p.window
@@ -301,12 +319,11 @@ impl CosmicWindow {
     }
 
     pub fn offset(&self) -> Point<i32, Logical> {
-        let has_ssd = self.0.with_program(|p| p.has_ssd(false));
-        if has_ssd {
-            Point::from((0, SSD_HEIGHT))
-        } else {
-            Point::from((0, 0))
-        }
+        Point::from((0, self.ssd_height(false)))
+    }
+
+    pub fn ssd_height(&self, pending: bool) -> i32 {
+        self.0.with_program(|p| p.ssd_height(pending))
     }
 
     pub(super) fn loop_handle(&self) -> LoopHandle<'static, crate::state::State> {
@@ -325,10 +342,11 @@ impl CosmicWindow {
         <R as Renderer>::TextureId: Send + Clone + 'static,
         C: From<CosmicWindowRenderElement<R>>,
     {
-        let has_ssd = self.0.with_program(|p| p.has_ssd(false));
+        let ssd_height = self.0.with_program(|p| p.ssd_height(false));
+        let has_ssd = ssd_height > 0;
 
         let window_loc = if has_ssd {
-            location + Point::from((0, (SSD_HEIGHT as f64 * scale.y) as i32))
+            location + Point::from((0, (ssd_height as f64 * scale.y) as i32))
         } else {
             location
         };
@@ -367,6 +385,21 @@ impl CosmicWindow {
         self.0.set_theme(theme);
     }
 
+    pub(crate) fn set_decorations(&self, decorations: DecorationsConfig) {
+        let size = self.0.with_program(|p| {
+            let size = (p.window.geometry().size.w, header_height(&decorations));
+            *p.decorations.lock().unwrap() = decorations;
+            size
+        });
+        self.0.resize(Size::from(size));
+    }
+
+    /// Moves the window into or out of a tiling layer, the header height might change
+    pub fn set_tiling_layer(&self, tiling: bool) {
+        self.0
+            .with_program(|p| p.tiling_layer.store(tiling, Ordering::SeqCst));
+    }
+
     pub(crate) fn force_redraw(&self) {
         self.0.force_redraw();
     }
@@ -379,6 +412,43 @@ pub enum Message {
     Maximize,
     Close,
     Menu,
+    Sticky,
+    AlwaysOnTop,
+}
+
+/// Header button, `None` if it is disabled in the desktop settings
+pub(super) fn titlebar_button<'a, Message: Clone + 'static>(
+    button: TitlebarButton,
+    maximized: bool,
+    message: Message,
+) -> Option<cosmic::Element<'a, Message>> {
+    let icon = match button {
+        TitlebarButton::Menu => "open-menu-symbolic",
+        TitlebarButton::Minimize if cosmic::config::show_minimize() => "window-minimize-symbolic",
+        TitlebarButton::Maximize if cosmic::config::show_maximize() => {
+            if maximized {
+                "window-restore-symbolic"
+            } else {
+                "window-maximize-symbolic"
+            }
+        }
+        TitlebarButton::Close => "window-close-symbolic",
+        TitlebarButton::Sticky => "view-pin-symbolic",
+        TitlebarButton::AlwaysOnTop => "go-top-symbolic",
+        _ => return None,
+    };
+
+    Some(
+        from_name(icon)
+            .size(16)
+            .prefer_svg(true)
+            .icon()
+            .apply(widget::button)
+            .padding(4)
+            .style(theme::iced::Button::Text)
+            .on_press(message)
+            .into(),
+    )
 }
 
 impl Program for CosmicWindowInternal {
@@ -439,10 +509,25 @@ impl Program for CosmicWindowInternal {
                     });
                 }
             }
+            Message::Sticky | Message::AlwaysOnTop => {
+                if let Some(surface) = self.window.wl_surface().map(Cow::into_owned) {
+                    loop_handle.insert_idle(move |state| {
+                        let mut shell = state.common.shell.write().unwrap();
+                        if let Some(mapped) = shell.element_for_surface(&surface).cloned() {
+                            let seat = shell.seats.last_active().clone();
+                            match message {
+                                Message::Sticky => shell.toggle_sticky(&seat, &mapped),
+                                _ => shell.toggle_always_on_top(&seat, &mapped),
+                            }
+                        }
+                    });
+                }
+            }
             Message::Close => self.window.close(),
             Message::Menu => {
                 if let Some((seat, serial)) = self.last_seat.lock().unwrap().clone() {
                     if let Some(surface) = self.window.wl_surface().map(Cow::into_owned) {
+                        let ssd_height = self.ssd_height(false);
                         loop_handle.insert_idle(move |state| {
                             let shell = state.common.shell.read().unwrap();
                             if let Some(mapped) = shell.element_for_surface(&surface).cloned() {
@@ -466,7 +551,7 @@ impl Program for CosmicWindowInternal {
 
                                 let pointer = seat.get_pointer().unwrap();
                                 let mut cursor = pointer.current_location().to_i32_round();
-                                cursor.y -= SSD_HEIGHT;
+                                cursor.y -= ssd_height;
 
                                 let res = shell.menu_request(
                                     &surface,
@@ -503,16 +588,28 @@ impl Program for CosmicWindowInternal {
         let mut header = cosmic::widget::header_bar()
             .title(self.last_title.lock().unwrap().clone())
             .on_drag(Message::DragStart)
-            .on_close(Message::Close)
             .focused(self.window.is_activated(false))
             .density(Density::Compact)
             .on_double_click(Message::Maximize);
 
-        if cosmic::config::show_minimize() {
-            header = header.on_minimize(Message::Minimize);
+        let decorations = self.decorations.lock().unwrap();
+        let maximized = self.window.is_maximized(false);
+        let button = |button| {
+            let message = match button {
+                TitlebarButton::Menu => Message::Menu,
+                TitlebarButton::Minimize => Message::Minimize,
+                TitlebarButton::Maximize => Message::Maximize,
+                TitlebarButton::Close => Message::Close,
+                TitlebarButton::Sticky => Message::Sticky,
+                TitlebarButton::AlwaysOnTop => Message::AlwaysOnTop,
+            };
+            titlebar_button(button, maximized, message)
+        };
+        for button in decorations.left_buttons.iter().filter_map(|b| button(*b)) {
+            header = header.start(button);
         }
-        if cosmic::config::show_maximize() {
-            header = header.on_maximize(Message::Maximize);
+        for button in decorations.right_buttons.iter().filter_map(|b| button(*b)) {
+            header = header.end(button);
         }
         let header = header.apply(mouse_area).on_right_press(Message::Menu);
 
@@ -533,7 +630,7 @@ impl SpaceElement for CosmicWindow {
             if p.has_ssd(false) {
                 bbox.loc -= Point::from((RESIZE_BORDER, RESIZE_BORDER));
                 bbox.size += Size::from((RESIZE_BORDER * 2, RESIZE_BORDER * 2));
-                bbox.size.h += SSD_HEIGHT;
+                bbox.size.h += p.ssd_height(false);
             }
             bbox
         })
@@ -569,9 +666,7 @@ impl SpaceElement for CosmicWindow {
     fn geometry(&self) -> Rectangle<i32, Logical> {
         self.0.with_program(|p| {
             let mut geo = SpaceElement::geometry(&p.window);
-            if p.has_ssd(false) {
-                geo.size.h += SSD_HEIGHT;
-            }
+            geo.size.h += p.ssd_height(false);
             geo
         })
     }
@@ -641,7 +736,8 @@ impl PointerTarget<State> for CosmicWindow {
         let mut event = event.clone();
         self.0.with_program(|p| {
             if p.has_ssd(false) {
-                let Some(next) = Focus::under(&p.window, SSD_HEIGHT, event.location) else {
+                let Some(next) = Focus::under(&p.window, p.ssd_height(false), event.location)
+                else {
                     return;
                 };
                 let old_focus = p.swap_focus(Some(next));
@@ -662,7 +758,8 @@ impl PointerTarget<State> for CosmicWindow {
         let mut event = event.clone();
         self.0.with_program(|p| {
             if p.has_ssd(false) {
-                let Some(next) = Focus::under(&p.window, SSD_HEIGHT, event.location) else {
+                let Some(next) = Focus::under(&p.window, p.ssd_height(false), event.location)
+                else {
                     return;
                 };
                 let _previous = p.swap_focus(Some(next));
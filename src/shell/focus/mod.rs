@@ -1,4 +1,5 @@
 use crate::{
+    ipc,
     shell::{element::CosmicMapped, Shell},
     state::Common,
     utils::prelude::*,
@@ -163,6 +164,7 @@ impl Shell {
                 }
             }
         }
+        workspace.scrolling_layer.scroll_to(mapped);
     }
 
     fn update_active(&mut self) {
@@ -192,6 +194,7 @@ impl Shell {
             for focused in focused_windows.iter() {
                 raise_with_children(&mut set.sticky_layer, focused);
             }
+            set.sticky_layer.raise_always_on_top();
             for window in set.sticky_layer.mapped() {
                 window.set_activated(focused_windows.contains(&window));
                 window.configure();
@@ -205,6 +208,7 @@ impl Shell {
             for focused in focused_windows.iter() {
                 raise_with_children(&mut workspace.floating_layer, focused);
             }
+            workspace.floating_layer.raise_always_on_top();
             for window in workspace.mapped() {
                 window.set_activated(focused_windows.contains(&window));
                 window.configure();
@@ -434,7 +438,19 @@ impl Common {
             }
         }
 
-        state.common.shell.write().unwrap().update_active()
+        let mut shell = state.common.shell.write().unwrap();
+        shell.update_active();
+
+        if shell.ipc_events.is_active() {
+            let focused = shell
+                .seats
+                .last_active()
+                .get_keyboard()
+                .and_then(|keyboard| keyboard.current_focus())
+                .and_then(|target| shell.focused_element(&target))
+                .and_then(|mapped| ipc::find_window_info(&shell, &mapped.active_window()));
+            shell.ipc_events.publish_focus(focused);
+        }
     }
 }
 
@@ -111,6 +111,7 @@ pub fn tab_items(
                     surface,
                     state.common.event_loop_handle.clone(),
                     state.common.theme.clone(),
+                    state.common.config.cosmic_conf.decorations.clone(),
                 )
                 .into();
 
@@ -128,10 +129,7 @@ pub fn tab_items(
                     {
                         workspace.unmaximize_request(&mapped);
                     }
-                    let focus_stack = workspace.focus_stack.get(&seat);
-                    workspace
-                        .tiling_layer
-                        .map(mapped, Some(focus_stack.iter()), None);
+                    workspace.map_tiled(mapped, Some(&seat), None);
                 } else {
                     workspace.floating_layer.map(mapped, None)
                 }
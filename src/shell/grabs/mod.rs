@@ -71,6 +71,8 @@ mod menu;
 pub use self::menu::*;
 mod moving;
 pub use self::moving::*;
+mod region;
+pub use self::region::*;
 
 bitflags::bitflags! {
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -11,7 +11,7 @@ use crate::{
             CosmicMappedRenderElement,
         },
         focus::target::{KeyboardFocusTarget, PointerFocusTarget},
-        layout::floating::TiledCorners,
+        layout::floating::{TiledCorners, ZoneGeometry},
         CosmicMapped, CosmicSurface, Direction, ManagedLayer,
     },
     utils::prelude::*,
@@ -20,11 +20,12 @@ use crate::{
 
 use calloop::LoopHandle;
 use cosmic::theme::CosmicTheme;
+use cosmic_comp_config::snapping::ZoneModifier;
 use smithay::{
     backend::{
         input::ButtonState,
         renderer::{
-            element::{utils::RescaleRenderElement, AsRenderElements, RenderElement},
+            element::{utils::RescaleRenderElement, AsRenderElements, Id, RenderElement},
             ImportAll, ImportMem, Renderer,
         },
     },
@@ -62,6 +63,8 @@ pub struct MoveGrabState {
     start: Instant,
     previous: ManagedLayer,
     snapping_zone: Option<SnappingZone>,
+    /// User-defined zones revealed on the cursor output
+    zones: Vec<(ZoneGeometry, Id)>,
     stacking_indicator: Option<(StackHover, Point<i32, Logical>)>,
     location: Point<f64, Logical>,
     cursor_output: Output,
@@ -147,6 +150,29 @@ impl MoveGrabState {
 
         let gaps = (theme.gaps.0 as i32, theme.gaps.1 as i32);
 
+        let zone_indicators = if &self.cursor_output == output {
+            let base_color = theme.palette.neutral_9;
+            self.zones
+                .iter()
+                .filter(|(zone, _)| self.snapping_zone != Some(SnappingZone::Custom(*zone)))
+                .map(|(zone, id)| {
+                    CosmicMappedRenderElement::from(IndicatorShader::element(
+                        renderer,
+                        Key::Static(id.clone()),
+                        TiledCorners::Zone(*zone).relative_geometry(non_exclusive_geometry, gaps),
+                        2,
+                        theme.radius_s()[0] as u8,
+                        0.6,
+                        output_scale.x,
+                        [base_color.red, base_color.green, base_color.blue],
+                    ))
+                    .into()
+                })
+                .collect::<Vec<_>>()
+        } else {
+            Vec::new()
+        };
+
         let snapping_indicator = match &self.snapping_zone {
             Some(t) if &self.cursor_output == output => {
                 let base_color = theme.palette.neutral_9;
@@ -231,6 +257,7 @@ impl MoveGrabState {
                 }
                 x => x,
             }))
+            .chain(zone_indicators)
             .chain(snapping_indicator)
             .map(I::from)
             .collect()
@@ -259,6 +286,7 @@ pub enum SnappingZone {
     BottomRight,
     Right,
     TopRight,
+    Custom(ZoneGeometry),
 }
 
 const SNAP_RANGE: i32 = 32;
@@ -293,6 +321,9 @@ impl SnappingZone {
             SnappingZone::BottomRight => (bottom_zone && right_6th) || (right_zone && bottom_4th),
             SnappingZone::Right => right_zone && !top_4th && !bottom_4th,
             SnappingZone::TopRight => (top_zone_56 && right_6th) || (right_zone && top_4th),
+            SnappingZone::Custom(zone) => zone
+                .rectangle(output_geometry.as_logical())
+                .contains(point.as_logical()),
         }
     }
     pub fn overlay_geometry(
@@ -324,6 +355,9 @@ impl SnappingZone {
             SnappingZone::TopRight => {
                 TiledCorners::TopRight.relative_geometry(non_exclusive_geometry, gaps)
             }
+            SnappingZone::Custom(zone) => {
+                TiledCorners::Zone(*zone).relative_geometry(non_exclusive_geometry, gaps)
+            }
         }
     }
 }
@@ -335,6 +369,8 @@ pub struct MoveGrab {
     cursor_output: Output,
     window_outputs: HashSet<Output>,
     previous: ManagedLayer,
+    /// The window is a tab dragged out of a stack
+    detached: bool,
     release: ReleaseMode,
     // SAFETY: This is only used on drop which will always be on the main thread
     evlh: NotSend<LoopHandle<'static, State>>,
@@ -394,8 +430,11 @@ impl MoveGrab {
                 }
             }
 
-            let indicator_location = shell.stacking_indicator(&current_output, self.previous);
-            if indicator_location.is_some() != grab_state.stacking_indicator.is_some() {
+            let indicator_location =
+                shell.stacking_indicator(&current_output, self.previous, location.as_global());
+            if indicator_location.map(|geo| geo.loc.as_logical())
+                != grab_state.stacking_indicator.as_ref().map(|(_, loc)| *loc)
+            {
                 grab_state.stacking_indicator = indicator_location.map(|geo| {
                     let element = stack_hover(
                         state.common.event_loop_handle.clone(),
@@ -417,29 +456,66 @@ impl MoveGrab {
 
             // Check for overlapping with zones
             if grab_state.previous == ManagedLayer::Floating {
-                let output_geometry = current_output.geometry().to_local(&current_output);
-                grab_state.snapping_zone = [
-                    SnappingZone::Maximize,
-                    SnappingZone::Top,
-                    SnappingZone::TopLeft,
-                    SnappingZone::Left,
-                    SnappingZone::BottomLeft,
-                    SnappingZone::Bottom,
-                    SnappingZone::BottomRight,
-                    SnappingZone::Right,
-                    SnappingZone::TopRight,
-                ]
-                .iter()
-                .find(|&x| {
-                    x.contains(
-                        location
-                            .as_global()
-                            .to_local(&current_output)
-                            .to_i32_floor(),
-                        output_geometry,
-                    )
-                })
-                .cloned();
+                let zones_config = &state.common.config.cosmic_conf.snapping_zones;
+                let revealed = self.seat.get_keyboard().is_some_and(|keyboard| {
+                    let modifiers = keyboard.modifier_state();
+                    match zones_config.modifier {
+                        ZoneModifier::Shift => modifiers.shift,
+                        ZoneModifier::Ctrl => modifiers.ctrl,
+                        ZoneModifier::Alt => modifiers.alt,
+                        ZoneModifier::Super => modifiers.logo,
+                    }
+                });
+                let layout = revealed
+                    .then(|| zones_config.layout(&current_output.name()))
+                    .flatten();
+                let location = location
+                    .as_global()
+                    .to_local(&current_output)
+                    .to_i32_floor();
+
+                if let Some(layout) = layout {
+                    let zones = layout
+                        .zones()
+                        .iter()
+                        .map(ZoneGeometry::from)
+                        .collect::<Vec<_>>();
+                    if !grab_state
+                        .zones
+                        .iter()
+                        .map(|(zone, _)| zone)
+                        .eq(zones.iter())
+                    {
+                        grab_state.zones =
+                            zones.into_iter().map(|zone| (zone, Id::new())).collect();
+                    }
+                    let non_exclusive_geometry = layer_map_for_output(&current_output)
+                        .non_exclusive_zone()
+                        .as_local();
+                    grab_state.snapping_zone = grab_state
+                        .zones
+                        .iter()
+                        .map(|(zone, _)| SnappingZone::Custom(*zone))
+                        .find(|zone| zone.contains(location, non_exclusive_geometry));
+                } else {
+                    grab_state.zones.clear();
+
+                    let output_geometry = current_output.geometry().to_local(&current_output);
+                    grab_state.snapping_zone = [
+                        SnappingZone::Maximize,
+                        SnappingZone::Top,
+                        SnappingZone::TopLeft,
+                        SnappingZone::Left,
+                        SnappingZone::BottomLeft,
+                        SnappingZone::Bottom,
+                        SnappingZone::BottomRight,
+                        SnappingZone::Right,
+                        SnappingZone::TopRight,
+                    ]
+                    .iter()
+                    .find(|&x| x.contains(location, output_geometry))
+                    .cloned();
+                }
             }
         }
         drop(borrow);
@@ -678,6 +754,7 @@ impl MoveGrab {
         cursor_output: Output,
         indicator_thickness: u8,
         previous_layer: ManagedLayer,
+        detached: bool,
         release: ReleaseMode,
         evlh: LoopHandle<'static, State>,
     ) -> MoveGrab {
@@ -695,6 +772,7 @@ impl MoveGrab {
             start: Instant::now(),
             stacking_indicator: None,
             snapping_zone: None,
+            zones: Vec::new(),
             previous: previous_layer,
             location: start_data.location(),
             cursor_output: cursor_output.clone(),
@@ -719,6 +797,7 @@ impl MoveGrab {
             window_outputs: outputs,
             cursor_output,
             previous: previous_layer,
+            detached,
             release,
             evlh: NotSend(evlh),
         }
@@ -743,6 +822,7 @@ impl Drop for MoveGrab {
         let seat = self.seat.clone();
         let window_outputs = self.window_outputs.drain().collect::<HashSet<_>>();
         let previous = self.previous;
+        let detached = self.detached;
         let window = self.window.clone();
         let is_touch_grab = matches!(self.start_data, GrabStartData::Touch(_));
 
@@ -769,75 +849,108 @@ impl Drop for MoveGrab {
                         }
                     }
 
-                    match previous {
-                        ManagedLayer::Sticky => {
-                            grab_state.window.set_geometry(Rectangle::from_loc_and_size(
-                                window_location,
-                                grab_state.window.geometry().size.as_global(),
-                            ));
-                            let set = shell.workspaces.sets.get_mut(&output).unwrap();
-                            let (window, location) = set
-                                .sticky_layer
-                                .drop_window(grab_state.window, window_location.to_local(&output));
-
-                            Some((window, location.to_global(&output)))
-                        }
-                        ManagedLayer::Tiling if shell.active_space(&output).tiling_enabled => {
-                            let (window, location) = shell
-                                .active_space_mut(&output)
-                                .tiling_layer
-                                .drop_window(grab_state.window);
-                            Some((window, location.to_global(&output)))
-                        }
-                        _ => {
-                            grab_state.window.set_geometry(Rectangle::from_loc_and_size(
-                                window_location,
-                                grab_state.window.geometry().size.as_global(),
-                            ));
-                            let theme = shell.theme.clone();
-                            let workspace = shell.active_space_mut(&output);
-                            let (window, location) = workspace.floating_layer.drop_window(
-                                grab_state.window,
-                                window_location.to_local(&workspace.output),
-                            );
-
-                            if previous == ManagedLayer::Floating {
-                                if let Some(sz) = grab_state.snapping_zone {
-                                    if sz == SnappingZone::Maximize {
-                                        shell.maximize_toggle(&window, &seat);
-                                    } else {
-                                        let directions = match sz {
-                                            SnappingZone::Maximize => vec![],
-                                            SnappingZone::Top => vec![Direction::Up],
-                                            SnappingZone::TopLeft => {
-                                                vec![Direction::Up, Direction::Left]
-                                            }
-                                            SnappingZone::Left => vec![Direction::Left],
-                                            SnappingZone::BottomLeft => {
-                                                vec![Direction::Down, Direction::Left]
-                                            }
-                                            SnappingZone::Bottom => vec![Direction::Down],
-                                            SnappingZone::BottomRight => {
-                                                vec![Direction::Down, Direction::Right]
-                                            }
-                                            SnappingZone::Right => vec![Direction::Right],
-                                            SnappingZone::TopRight => {
-                                                vec![Direction::Up, Direction::Right]
+                    if let Some(res) = shell.stack_dropped_window(
+                        &grab_state.window,
+                        &output,
+                        previous,
+                        grab_state.location.as_global(),
+                    ) {
+                        Some(res)
+                    } else {
+                        match previous {
+                            ManagedLayer::Sticky => {
+                                grab_state.window.set_geometry(Rectangle::from_loc_and_size(
+                                    window_location,
+                                    grab_state.window.geometry().size.as_global(),
+                                ));
+                                let set = shell.workspaces.sets.get_mut(&output).unwrap();
+                                let (window, location) = set.sticky_layer.drop_window(
+                                    grab_state.window,
+                                    window_location.to_local(&output),
+                                );
+
+                                Some((window, location.to_global(&output)))
+                            }
+                            ManagedLayer::Tiling
+                                if shell.active_space(&output).tiling_enabled
+                                    && shell.active_space(&output).scrolling_enabled =>
+                            {
+                                let workspace = shell.active_space_mut(&output);
+                                let window = grab_state.window.clone();
+                                workspace.map_tiled(grab_state.window, Some(&seat), None);
+                                let location = workspace.element_geometry(&window).unwrap().loc;
+                                Some((window, location.to_global(&output)))
+                            }
+                            // tabs dropped outside of any drop target become floating windows
+                            ManagedLayer::Tiling
+                                if shell.active_space(&output).tiling_enabled
+                                    && (!detached
+                                        || shell
+                                            .active_space(&output)
+                                            .tiling_layer
+                                            .has_drop_target()) =>
+                            {
+                                let (window, location) = shell
+                                    .active_space_mut(&output)
+                                    .tiling_layer
+                                    .drop_window(grab_state.window);
+                                Some((window, location.to_global(&output)))
+                            }
+                            _ => {
+                                grab_state.window.set_geometry(Rectangle::from_loc_and_size(
+                                    window_location,
+                                    grab_state.window.geometry().size.as_global(),
+                                ));
+                                let theme = shell.theme.clone();
+                                let workspace = shell.active_space_mut(&output);
+                                let (window, location) = workspace.floating_layer.drop_window(
+                                    grab_state.window,
+                                    window_location.to_local(&workspace.output),
+                                );
+
+                                if previous == ManagedLayer::Floating {
+                                    if let Some(sz) = grab_state.snapping_zone {
+                                        if sz == SnappingZone::Maximize {
+                                            shell.maximize_toggle(&window, &seat);
+                                        } else if let SnappingZone::Custom(zone) = sz {
+                                            workspace.floating_layer.snap_to_zone(&window, zone);
+                                        } else {
+                                            let directions = match sz {
+                                                SnappingZone::Maximize
+                                                | SnappingZone::Custom(_) => {
+                                                    vec![]
+                                                }
+                                                SnappingZone::Top => vec![Direction::Up],
+                                                SnappingZone::TopLeft => {
+                                                    vec![Direction::Up, Direction::Left]
+                                                }
+                                                SnappingZone::Left => vec![Direction::Left],
+                                                SnappingZone::BottomLeft => {
+                                                    vec![Direction::Down, Direction::Left]
+                                                }
+                                                SnappingZone::Bottom => vec![Direction::Down],
+                                                SnappingZone::BottomRight => {
+                                                    vec![Direction::Down, Direction::Right]
+                                                }
+                                                SnappingZone::Right => vec![Direction::Right],
+                                                SnappingZone::TopRight => {
+                                                    vec![Direction::Up, Direction::Right]
+                                                }
+                                            };
+                                            for direction in directions {
+                                                workspace.floating_layer.move_element(
+                                                    direction,
+                                                    &seat,
+                                                    ManagedLayer::Floating,
+                                                    &theme,
+                                                    &window,
+                                                );
                                             }
-                                        };
-                                        for direction in directions {
-                                            workspace.floating_layer.move_element(
-                                                direction,
-                                                &seat,
-                                                ManagedLayer::Floating,
-                                                &theme,
-                                                &window,
-                                            );
                                         }
                                     }
                                 }
+                                Some((window, location.to_global(&output)))
                             }
-                            Some((window, location.to_global(&output)))
                         }
                     }
                 } else {
@@ -0,0 +1,370 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::sync::Mutex;
+
+use smithay::{
+    backend::{
+        input::ButtonState,
+        renderer::{
+            element::{Id, RenderElement},
+            ImportAll, ImportMem, Renderer,
+        },
+    },
+    input::{
+        pointer::{
+            AxisFrame, ButtonEvent, CursorIcon, GestureHoldBeginEvent, GestureHoldEndEvent,
+            GesturePinchBeginEvent, GesturePinchEndEvent, GesturePinchUpdateEvent,
+            GestureSwipeBeginEvent, GestureSwipeEndEvent, GestureSwipeUpdateEvent,
+            GrabStartData as PointerGrabStartData, MotionEvent, PointerGrab, PointerInnerHandle,
+            RelativeMotionEvent,
+        },
+        Seat,
+    },
+    output::Output,
+    utils::{Logical, Point, Rectangle},
+};
+
+use crate::{
+    backend::render::{cursor::CursorState, element::AsGlowRenderer, BackdropShader, Key},
+    shell::{element::CosmicMappedRenderElement, focus::target::PointerFocusTarget},
+    utils::{prelude::*, screenshot::screenshot_region},
+};
+
+const BTN_LEFT: u32 = 0x110;
+
+pub type SeatRegionGrabState = Mutex<Option<RegionSelection>>;
+
+/// Area of the screen currently being selected for a screenshot
+pub struct RegionSelection {
+    start: Option<Point<f64, Global>>,
+    current: Point<f64, Global>,
+    ids: [Id; 4],
+}
+
+impl RegionSelection {
+    /// Selected area, once the user started dragging
+    pub fn rectangle(&self) -> Option<Rectangle<i32, Global>> {
+        let start = self.start?;
+        let top_left =
+            Point::<f64, Global>::from((start.x.min(self.current.x), start.y.min(self.current.y)));
+        let bottom_right =
+            Point::<f64, Global>::from((start.x.max(self.current.x), start.y.max(self.current.y)));
+        let top_left = top_left.to_i32_floor();
+        let bottom_right = bottom_right.to_i32_ceil();
+        Some(Rectangle::from_loc_and_size(
+            top_left,
+            (bottom_right.x - top_left.x, bottom_right.y - top_left.y),
+        ))
+    }
+
+    /// Dims everything on the output outside of the selection
+    pub fn render<I, R>(&self, renderer: &mut R, output: &Output) -> Vec<I>
+    where
+        R: Renderer + ImportAll + ImportMem + AsGlowRenderer,
+        <R as Renderer>::TextureId: Send + Clone + 'static,
+        CosmicMappedRenderElement<R>: RenderElement<R>,
+        I: From<CosmicMappedRenderElement<R>>,
+    {
+        let geo = output.geometry();
+        let rects = match self
+            .rectangle()
+            .and_then(|selection| selection.intersection(geo))
+        {
+            Some(sel) => [
+                // above
+                Rectangle::from_loc_and_size(geo.loc, (geo.size.w, sel.loc.y - geo.loc.y)),
+                // below
+                Rectangle::from_loc_and_size(
+                    (geo.loc.x, sel.loc.y + sel.size.h),
+                    (
+                        geo.size.w,
+                        geo.loc.y + geo.size.h - (sel.loc.y + sel.size.h),
+                    ),
+                ),
+                // left
+                Rectangle::from_loc_and_size(
+                    (geo.loc.x, sel.loc.y),
+                    (sel.loc.x - geo.loc.x, sel.size.h),
+                ),
+                // right
+                Rectangle::from_loc_and_size(
+                    (sel.loc.x + sel.size.w, sel.loc.y),
+                    (
+                        geo.loc.x + geo.size.w - (sel.loc.x + sel.size.w),
+                        sel.size.h,
+                    ),
+                ),
+            ],
+            None => [
+                geo,
+                Rectangle::default(),
+                Rectangle::default(),
+                Rectangle::default(),
+            ],
+        };
+
+        rects
+            .into_iter()
+            .zip(self.ids.iter())
+            .filter(|(rect, _)| !rect.is_empty())
+            .map(|(rect, id)| {
+                CosmicMappedRenderElement::from(BackdropShader::element(
+                    renderer,
+                    Key::Static(id.clone()),
+                    rect.to_local(output),
+                    0.,
+                    0.5,
+                    [0., 0., 0.],
+                ))
+                .into()
+            })
+            .collect()
+    }
+}
+
+/// Lets the user drag out a region and takes a screenshot of it.
+///
+/// Pressing any other button cancels the selection, as does Escape through the
+/// general handling of grabs in [`PrivateAction::Escape`](crate::config::PrivateAction).
+pub struct RegionGrab {
+    start_data: PointerGrabStartData<State>,
+    seat: Seat<State>,
+    clipboard: bool,
+}
+
+impl RegionGrab {
+    pub fn new(
+        start_data: PointerGrabStartData<State>,
+        seat: &Seat<State>,
+        clipboard: bool,
+    ) -> RegionGrab {
+        *seat
+            .user_data()
+            .get::<SeatRegionGrabState>()
+            .unwrap()
+            .lock()
+            .unwrap() = Some(RegionSelection {
+            start: None,
+            current: start_data.location.as_global(),
+            ids: [Id::new(), Id::new(), Id::new(), Id::new()],
+        });
+        let cursor_state = seat.user_data().get::<CursorState>().unwrap();
+        cursor_state
+            .lock()
+            .unwrap()
+            .set_shape(CursorIcon::Crosshair);
+
+        RegionGrab {
+            start_data,
+            seat: seat.clone(),
+            clipboard,
+        }
+    }
+}
+
+impl PointerGrab<State> for RegionGrab {
+    fn motion(
+        &mut self,
+        state: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        _focus: Option<(PointerFocusTarget, Point<f64, Logical>)>,
+        event: &MotionEvent,
+    ) {
+        if let Some(selection) = self
+            .seat
+            .user_data()
+            .get::<SeatRegionGrabState>()
+            .unwrap()
+            .lock()
+            .unwrap()
+            .as_mut()
+        {
+            selection.current = event.location.as_global();
+        }
+        // While the grab is active, no client has pointer focus
+        handle.motion(state, None, event);
+
+        schedule_render(state);
+    }
+
+    fn relative_motion(
+        &mut self,
+        state: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        _focus: Option<(PointerFocusTarget, Point<f64, Logical>)>,
+        event: &RelativeMotionEvent,
+    ) {
+        handle.relative_motion(state, None, event);
+    }
+
+    fn button(
+        &mut self,
+        state: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        event: &ButtonEvent,
+    ) {
+        let mut guard = self
+            .seat
+            .user_data()
+            .get::<SeatRegionGrabState>()
+            .unwrap()
+            .lock()
+            .unwrap();
+        let Some(selection) = guard.as_mut() else {
+            return;
+        };
+
+        match (event.button, event.state) {
+            (BTN_LEFT, ButtonState::Pressed) if selection.start.is_none() => {
+                selection.start = Some(selection.current);
+            }
+            (BTN_LEFT, ButtonState::Released) if selection.start.is_some() => {
+                let region = selection.rectangle();
+                std::mem::drop(guard);
+                handle.unset_grab(self, state, event.serial, event.time, true);
+
+                if let Some(region) = region {
+                    let seat = self.seat.clone();
+                    let clipboard = self.clipboard;
+                    state.common.event_loop_handle.insert_idle(move |state| {
+                        screenshot_region(state, region, "region", clipboard, &seat);
+                    });
+                }
+            }
+            // any other button cancels the selection
+            (_, ButtonState::Pressed) => {
+                std::mem::drop(guard);
+                handle.unset_grab(self, state, event.serial, event.time, true);
+            }
+            _ => {}
+        }
+    }
+
+    fn axis(
+        &mut self,
+        _state: &mut State,
+        _handle: &mut PointerInnerHandle<'_, State>,
+        _details: AxisFrame,
+    ) {
+    }
+
+    fn frame(&mut self, data: &mut State, handle: &mut PointerInnerHandle<'_, State>) {
+        handle.frame(data)
+    }
+
+    fn gesture_swipe_begin(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        event: &GestureSwipeBeginEvent,
+    ) {
+        handle.gesture_swipe_begin(data, event)
+    }
+
+    fn gesture_swipe_update(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        event: &GestureSwipeUpdateEvent,
+    ) {
+        handle.gesture_swipe_update(data, event)
+    }
+
+    fn gesture_swipe_end(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        event: &GestureSwipeEndEvent,
+    ) {
+        handle.gesture_swipe_end(data, event)
+    }
+
+    fn gesture_pinch_begin(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        event: &GesturePinchBeginEvent,
+    ) {
+        handle.gesture_pinch_begin(data, event)
+    }
+
+    fn gesture_pinch_update(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        event: &GesturePinchUpdateEvent,
+    ) {
+        handle.gesture_pinch_update(data, event)
+    }
+
+    fn gesture_pinch_end(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        event: &GesturePinchEndEvent,
+    ) {
+        handle.gesture_pinch_end(data, event)
+    }
+
+    fn gesture_hold_begin(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        event: &GestureHoldBeginEvent,
+    ) {
+        handle.gesture_hold_begin(data, event)
+    }
+
+    fn gesture_hold_end(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        event: &GestureHoldEndEvent,
+    ) {
+        handle.gesture_hold_end(data, event)
+    }
+
+    fn start_data(&self) -> &PointerGrabStartData<State> {
+        &self.start_data
+    }
+
+    fn unset(&mut self, data: &mut State) {
+        // remove the dimming right away, when cancelled without moving the pointer
+        self.seat
+            .user_data()
+            .get::<SeatRegionGrabState>()
+            .unwrap()
+            .lock()
+            .unwrap()
+            .take();
+        schedule_render(data);
+    }
+}
+
+fn schedule_render(state: &mut State) {
+    let outputs = state
+        .common
+        .shell
+        .read()
+        .unwrap()
+        .outputs()
+        .cloned()
+        .collect::<Vec<_>>();
+    for output in outputs {
+        state.backend.schedule_render(&output);
+    }
+}
+
+impl Drop for RegionGrab {
+    fn drop(&mut self) {
+        self.seat
+            .user_data()
+            .get::<SeatRegionGrabState>()
+            .unwrap()
+            .lock()
+            .unwrap()
+            .take();
+        let cursor_state = self.seat.user_data().get::<CursorState>().unwrap();
+        cursor_state.lock().unwrap().unset_shape();
+    }
+}
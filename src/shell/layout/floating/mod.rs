@@ -2,10 +2,14 @@
 
 use std::{
     collections::{HashMap, VecDeque},
-    sync::atomic::{AtomicBool, Ordering},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        MutexGuard,
+    },
     time::{Duration, Instant},
 };
 
+use cosmic_comp_config::{snapping::Zone, DecorationsConfig};
 use cosmic_settings_config::shortcuts::action::ResizeDirection;
 use keyframe::{ease, functions::EaseInOutCubic};
 use smithay::{
@@ -25,10 +29,11 @@ use smithay::{
 
 use crate::{
     backend::render::{element::AsGlowRenderer, IndicatorShader, Key, SplitRenderElements, Usage},
+    config::SnapPosition,
     shell::{
         element::{
             resize_indicator::ResizeIndicator,
-            stack::{CosmicStackRenderElement, MoveResult as StackMoveResult, TAB_HEIGHT},
+            stack::{CosmicStackRenderElement, MoveResult as StackMoveResult},
             window::CosmicWindowRenderElement,
             CosmicMapped, CosmicMappedRenderElement, CosmicWindow, MaximizedState,
         },
@@ -55,9 +60,9 @@ pub struct FloatingLayout {
     pub(crate) space: Space<CosmicMapped>,
     spawn_order: Vec<CosmicMapped>,
     animations: HashMap<CosmicMapped, Animation>,
-    hovered_stack: Option<(CosmicMapped, Rectangle<i32, Local>)>,
     dirty: AtomicBool,
     pub theme: cosmic::Theme,
+    pub decorations: DecorationsConfig,
 }
 
 #[derive(Debug)]
@@ -174,9 +179,92 @@ pub enum TiledCorners {
     BottomLeft,
     Left,
     TopLeft,
+    /// User-defined snapping zone
+    Zone(ZoneGeometry),
+}
+
+/// Rectangle in 1/10000th of the usable area of an output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ZoneGeometry {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl From<&Zone> for ZoneGeometry {
+    fn from(zone: &Zone) -> ZoneGeometry {
+        let clamp = |percent: f32| (percent * 100.).round().clamp(0., 10_000.) as u16;
+        // round the edges instead of the sizes, so neighbouring zones stay adjacent
+        let (x, y) = (clamp(zone.x), clamp(zone.y));
+        ZoneGeometry {
+            x,
+            y,
+            width: clamp(zone.x + zone.width).saturating_sub(x),
+            height: clamp(zone.y + zone.height).saturating_sub(y),
+        }
+    }
+}
+
+impl ZoneGeometry {
+    /// Area of the zone, without gaps
+    pub fn rectangle(&self, output_geometry: Rectangle<i32, Logical>) -> Rectangle<i32, Logical> {
+        let scale = |value: u16, size: i32| (value as i64 * size as i64 / 10_000) as i32;
+        let x0 = output_geometry.loc.x + scale(self.x, output_geometry.size.w);
+        let y0 = output_geometry.loc.y + scale(self.y, output_geometry.size.h);
+        let x1 = output_geometry.loc.x + scale(self.x + self.width, output_geometry.size.w);
+        let y1 = output_geometry.loc.y + scale(self.y + self.height, output_geometry.size.h);
+        Rectangle::from_loc_and_size((x0, y0), (x1 - x0, y1 - y0))
+    }
 }
 
+impl From<SnapPosition> for TiledCorners {
+    fn from(position: SnapPosition) -> TiledCorners {
+        match position {
+            SnapPosition::Top => TiledCorners::Top,
+            SnapPosition::TopRight => TiledCorners::TopRight,
+            SnapPosition::Right => TiledCorners::Right,
+            SnapPosition::BottomRight => TiledCorners::BottomRight,
+            SnapPosition::Bottom => TiledCorners::Bottom,
+            SnapPosition::BottomLeft => TiledCorners::BottomLeft,
+            SnapPosition::Left => TiledCorners::Left,
+            SnapPosition::TopLeft => TiledCorners::TopLeft,
+        }
+    }
+}
+
+/// Sizes cycled through when snapping repeatedly, in 1/10000th of the output
+const SNAP_FRACTIONS: [u16; 3] = [5_000, 3_333, 6_667];
+
 impl TiledCorners {
+    /// State covering `fraction` of the output towards this corner.
+    /// Corners only change their width, halves are the plain variants.
+    fn with_fraction(&self, fraction: u16) -> TiledCorners {
+        if fraction == 5_000 {
+            return *self;
+        }
+        let (x, width) = match self {
+            TiledCorners::Left | TiledCorners::TopLeft | TiledCorners::BottomLeft => (0, fraction),
+            TiledCorners::Right | TiledCorners::TopRight | TiledCorners::BottomRight => {
+                (10_000 - fraction, fraction)
+            }
+            _ => (0, 10_000),
+        };
+        let (y, height) = match self {
+            TiledCorners::Top => (0, fraction),
+            TiledCorners::Bottom => (10_000 - fraction, fraction),
+            TiledCorners::TopLeft | TiledCorners::TopRight => (0, 5_000),
+            TiledCorners::BottomLeft | TiledCorners::BottomRight => (5_000, 5_000),
+            _ => (0, 10_000),
+        };
+        TiledCorners::Zone(ZoneGeometry {
+            x,
+            y,
+            width,
+            height,
+        })
+    }
+
     pub fn relative_geometry(
         &self,
         output_geometry: Rectangle<i32, Logical>,
@@ -184,6 +272,19 @@ impl TiledCorners {
     ) -> Rectangle<i32, Local> {
         let (_, inner) = gaps;
         let (loc, size) = match self {
+            TiledCorners::Zone(zone) => {
+                // full gaps towards the edges of the output, half gaps between zones
+                let rect = zone.rectangle(output_geometry);
+                let gap = |at_edge: bool| if at_edge { inner } else { inner / 2 };
+                let left = gap(zone.x == 0);
+                let top = gap(zone.y == 0);
+                let right = gap(zone.x + zone.width >= 10_000);
+                let bottom = gap(zone.y + zone.height >= 10_000);
+                (
+                    Point::from((rect.loc.x + left, rect.loc.y + top)),
+                    Size::from((rect.size.w - left - right, rect.size.h - top - bottom)),
+                )
+            }
             TiledCorners::Bottom => (
                 Point::from((
                     output_geometry.loc.x + inner,
@@ -262,9 +363,14 @@ impl TiledCorners {
 }
 
 impl FloatingLayout {
-    pub fn new(theme: cosmic::Theme, output: &Output) -> FloatingLayout {
+    pub fn new(
+        theme: cosmic::Theme,
+        decorations: DecorationsConfig,
+        output: &Output,
+    ) -> FloatingLayout {
         let mut layout = Self {
             theme,
+            decorations,
             ..Default::default()
         };
         layout.space.map_output(output, (0, 0));
@@ -572,6 +678,7 @@ impl FloatingLayout {
             });
 
         mapped.set_tiled(false);
+        mapped.set_tiling_layer(false);
         mapped
             .set_geometry(Rectangle::from_loc_and_size(position, win_geo.size).to_global(&output));
         mapped.configure();
@@ -628,20 +735,16 @@ impl FloatingLayout {
     pub fn unmap(&mut self, window: &CosmicMapped) -> Option<Size<i32, Logical>> {
         let mut new_size = None;
 
-        if let Some(_) = window.floating_tiled.lock().unwrap().take() {
-            if let Some(last_size) = window.last_geometry.lock().unwrap().map(|geo| geo.size) {
-                if let Some(location) = self.space.element_location(window) {
-                    window.set_tiled(false);
-                    window.set_geometry(
-                        Rectangle::from_loc_and_size(location, last_size.as_logical())
-                            .as_local()
-                            .to_global(self.space.outputs().next().unwrap()),
-                    );
-                    window.configure();
-                    new_size = Some(last_size.as_logical());
-                }
-            }
-        } else if !window.is_maximized(true) && !window.is_fullscreen(true) {
+        if let Some(geometry) = self.untiled_geometry(window) {
+            window.floating_tiled.lock().unwrap().take();
+            window.set_tiled(false);
+            window.set_geometry(geometry.to_global(self.space.outputs().next().unwrap()));
+            window.configure();
+            new_size = Some(geometry.size.as_logical());
+        } else if window.floating_tiled.lock().unwrap().take().is_none()
+            && !window.is_maximized(true)
+            && !window.is_fullscreen(true)
+        {
             if let Some(location) = self.space.element_location(window) {
                 *window.last_geometry.lock().unwrap() = Some(
                     Rectangle::from_loc_and_size(
@@ -704,24 +807,54 @@ impl FloatingLayout {
         window: CosmicMapped,
         position: Point<i32, Local>,
     ) -> (CosmicMapped, Point<i32, Local>) {
-        if self
-            .hovered_stack
-            .as_ref()
-            .is_some_and(|(stack, _)| stack == &window || !stack.alive())
-        {
-            let _ = self.hovered_stack.take();
+        self.map_internal(window.clone(), Some(position), None, None);
+        (window, position)
+    }
+
+    /// Window or stack, whose header is under `location`
+    pub fn header_under(
+        &self,
+        location: Point<f64, Local>,
+    ) -> Option<(CosmicMapped, Rectangle<i32, Local>)> {
+        let (mapped, _) = self.space.element_under(location.as_logical())?;
+        let geometry = self.space.element_geometry(mapped)?.as_local();
+        let offset = location.y.round() as i32 - geometry.loc.y;
+        mapped
+            .header_height()
+            .is_some_and(|height| offset.is_positive() && offset <= height)
+            .then(|| (mapped.clone(), geometry))
+    }
+
+    /// Adds the windows of `window` to `target`, turning `target` into a stack first if necessary
+    pub fn stack_window(
+        &mut self,
+        target: &CosmicMapped,
+        window: &CosmicMapped,
+    ) -> Option<(CosmicMapped, Point<i32, Local>)> {
+        let geo = self.space.element_geometry(target)?.as_local();
+        let mut mapped = target.clone();
+
+        if mapped.is_window() {
+            let output = self.space.outputs().next().unwrap().clone();
+            self.space.unmap_elem(&mapped);
+            mapped.convert_to_stack(
+                (&output, mapped.bbox()),
+                self.theme.clone(),
+                self.decorations.clone(),
+            );
+            self.map_internal(
+                mapped.clone(),
+                Some(geo.loc),
+                Some(geo.size.as_logical()),
+                None,
+            );
         }
 
-        if let Some((mapped, geo)) = self.hovered_stack.take() {
-            let stack = mapped.stack_ref().unwrap();
-            for surface in window.windows().map(|s| s.0) {
-                stack.add_window(surface, None);
-            }
-            (mapped, geo.loc)
-        } else {
-            self.map_internal(window.clone(), Some(position), None, None);
-            (window, position)
+        let stack = mapped.stack_ref().unwrap();
+        for surface in window.windows().map(|s| s.0) {
+            stack.add_window(surface, None);
         }
+        Some((mapped, geo.loc))
     }
 
     pub fn element_geometry(&self, elem: &CosmicMapped) -> Option<Rectangle<i32, Local>> {
@@ -735,7 +868,7 @@ impl FloatingLayout {
     }
 
     pub fn surface_under(
-        &mut self,
+        &self,
         location: Point<f64, Local>,
     ) -> Option<(PointerFocusTarget, Point<f64, Local>)> {
         let res = self
@@ -743,18 +876,6 @@ impl FloatingLayout {
             .element_under(location.as_logical())
             .map(|(mapped, p)| (mapped.clone(), p.as_local()));
 
-        if let Some((mapped, _)) = res.as_ref() {
-            let geometry = self.space.element_geometry(mapped).unwrap();
-            let offset = location.y.round() as i32 - geometry.loc.y;
-            if mapped.is_stack() && offset.is_positive() && offset <= TAB_HEIGHT {
-                self.hovered_stack = Some((mapped.clone(), geometry.as_local()));
-            } else {
-                self.hovered_stack.take();
-            }
-        } else {
-            self.hovered_stack.take();
-        }
-
         res.and_then(|(element, space_offset)| {
             let point = location - space_offset.to_f64();
             element
@@ -765,10 +886,6 @@ impl FloatingLayout {
         })
     }
 
-    pub fn stacking_indicator(&self) -> Option<Rectangle<i32, Local>> {
-        self.hovered_stack.as_ref().map(|(_, geo)| geo.clone())
-    }
-
     pub fn resize_request(
         &mut self,
         mapped: &CosmicMapped,
@@ -899,7 +1016,11 @@ impl FloatingLayout {
         if mapped.is_window() {
             // if it is just a window
             self.space.unmap_elem(&mapped);
-            mapped.convert_to_stack((&output, mapped.bbox()), self.theme.clone());
+            mapped.convert_to_stack(
+                (&output, mapped.bbox()),
+                self.theme.clone(),
+                self.decorations.clone(),
+            );
             self.map_internal(
                 mapped.clone(),
                 Some(location.as_local()),
@@ -916,7 +1037,12 @@ impl FloatingLayout {
 
             self.space.unmap_elem(&mapped);
             let handle = mapped.loop_handle();
-            mapped.convert_to_surface(first, (&output, mapped.bbox()), self.theme.clone());
+            mapped.convert_to_surface(
+                first,
+                (&output, mapped.bbox()),
+                self.theme.clone(),
+                self.decorations.clone(),
+            );
             let mut new_elements = vec![mapped.clone()];
 
             // map the rest
@@ -928,6 +1054,7 @@ impl FloatingLayout {
                     other,
                     handle.clone(),
                     self.theme.clone(),
+                    self.decorations.clone(),
                 ));
                 window.output_enter(&output, window.bbox());
 
@@ -977,8 +1104,13 @@ impl FloatingLayout {
         match element.handle_move(direction) {
             StackMoveResult::Handled => MoveResult::Done,
             StackMoveResult::MoveOut(surface, loop_handle) => {
-                let mapped: CosmicMapped =
-                    CosmicWindow::new(surface, loop_handle, theme.clone()).into();
+                let mapped: CosmicMapped = CosmicWindow::new(
+                    surface,
+                    loop_handle,
+                    theme.clone(),
+                    self.decorations.clone(),
+                )
+                .into();
                 let output = seat.active_output();
                 let pos = self.space.element_geometry(element).unwrap().loc
                     + match direction {
@@ -1099,40 +1231,235 @@ impl FloatingLayout {
                     (Direction::Left, _) => TiledCorners::Left,
                 };
 
-                let new_geo = new_state.relative_geometry(output_geometry, self.gaps());
-                let (new_pos, new_size) = (new_geo.loc, new_geo.size);
-                element.set_tiled(true); // TODO: More fine grained?
-                element.set_maximized(false);
-
-                if tiled_state.is_none() {
-                    let last_geometry = element
-                        .maximized_state
-                        .lock()
-                        .unwrap()
-                        .take()
-                        .map(|state| state.original_geometry)
-                        .or_else(|| self.space.element_geometry(element).map(RectExt::as_local));
-
-                    *element.last_geometry.lock().unwrap() = last_geometry;
-                }
-
-                *tiled_state = Some(new_state);
-                std::mem::drop(tiled_state);
-
-                element.moved_since_mapped.store(true, Ordering::SeqCst);
-                let element = element.clone();
-                self.map_internal(
+                self.tile_element(
                     element,
-                    Some(new_pos),
-                    Some(new_size.as_logical()),
-                    Some(start_rectangle),
+                    tiled_state,
+                    new_state,
+                    output_geometry,
+                    start_rectangle,
                 );
-
                 MoveResult::Done
             }
         }
     }
 
+    /// Tiles a floating element into a user-defined snapping zone
+    pub fn snap_to_zone(&mut self, element: &CosmicMapped, zone: ZoneGeometry) {
+        self.snap_element_with(element, |_| TiledCorners::Zone(zone));
+    }
+
+    /// Tiles a floating element towards `corner`, cycling through
+    /// 1/2, 1/3 and 2/3 of the output, when it is already snapped there
+    pub fn snap_element(&mut self, element: &CosmicMapped, corner: TiledCorners) {
+        self.snap_element_with(element, |current| {
+            let sizes = SNAP_FRACTIONS.map(|fraction| corner.with_fraction(fraction));
+            let next = current
+                .and_then(|current| sizes.iter().position(|size| size == current))
+                .map_or(0, |idx| (idx + 1) % sizes.len());
+            sizes[next]
+        });
+    }
+
+    /// Moves a floating element by `step` in `direction`, untiling it like a move grab would
+    pub fn nudge_element(&mut self, element: &CosmicMapped, direction: Direction, step: i32) {
+        let Some(mut geometry) = self
+            .untiled_geometry(element)
+            .or_else(|| self.space.element_geometry(element).map(RectExt::as_local))
+        else {
+            return;
+        };
+        match direction {
+            Direction::Left => geometry.loc.x -= step,
+            Direction::Right => geometry.loc.x += step,
+            Direction::Up => geometry.loc.y -= step,
+            Direction::Down => geometry.loc.y += step,
+        }
+        self.set_element_geometry(element, geometry);
+    }
+
+    /// Grows or shrinks a floating element by `step` at the given edge
+    pub fn resize_element_by(
+        &mut self,
+        element: &CosmicMapped,
+        direction: ResizeDirection,
+        edge: Direction,
+        step: i32,
+    ) {
+        let Some(mut geometry) = self.space.element_geometry(element).map(RectExt::as_local) else {
+            return;
+        };
+        let step = if direction == ResizeDirection::Inwards {
+            -step
+        } else {
+            step
+        };
+        let min_size = element.min_size().unwrap_or((360, 240).into());
+        let max_size = element
+            .max_size()
+            .filter(|size| size.w > 0 && size.h > 0)
+            .unwrap_or((i32::MAX, i32::MAX).into());
+
+        match edge {
+            Direction::Left | Direction::Right => {
+                let width = (geometry.size.w + step).clamp(min_size.w, max_size.w.max(min_size.w));
+                if matches!(edge, Direction::Left) {
+                    geometry.loc.x -= width - geometry.size.w;
+                }
+                geometry.size.w = width;
+            }
+            Direction::Up | Direction::Down => {
+                let height = (geometry.size.h + step).clamp(min_size.h, max_size.h.max(min_size.h));
+                if matches!(edge, Direction::Up) {
+                    geometry.loc.y -= height - geometry.size.h;
+                }
+                geometry.size.h = height;
+            }
+        }
+
+        let output_geometry = self.non_exclusive_geometry().as_local();
+        let Some(geometry) = geometry.intersection(output_geometry) else {
+            return;
+        };
+        self.set_element_geometry(element, geometry);
+    }
+
+    /// Centers a floating element on the output
+    pub fn center_element(&mut self, element: &CosmicMapped) {
+        let Some(mut geometry) = self
+            .untiled_geometry(element)
+            .or_else(|| self.space.element_geometry(element).map(RectExt::as_local))
+        else {
+            return;
+        };
+        let output_geometry = self.non_exclusive_geometry().as_local();
+        geometry.loc = (
+            output_geometry.loc.x + (output_geometry.size.w - geometry.size.w) / 2,
+            output_geometry.loc.y + (output_geometry.size.h - geometry.size.h) / 2,
+        )
+            .into();
+        self.set_element_geometry(element, geometry);
+    }
+
+    fn non_exclusive_geometry(&self) -> Rectangle<i32, Logical> {
+        let output = self.space.outputs().next().unwrap();
+        let layers = layer_map_for_output(output);
+        layers.non_exclusive_zone()
+    }
+
+    /// Moves an element to `geometry`, untiling it, animated from its current geometry
+    fn set_element_geometry(&mut self, element: &CosmicMapped, geometry: Rectangle<i32, Local>) {
+        let mut tiled_state = element.floating_tiled.lock().unwrap();
+        let Some(current_geometry) = self.space.element_geometry(element).map(RectExt::as_local)
+        else {
+            return;
+        };
+        let start_rectangle = if let Some(anim) = self.animations.remove(element) {
+            anim.geometry(
+                self.non_exclusive_geometry(),
+                current_geometry,
+                tiled_state.as_ref(),
+                self.gaps(),
+            )
+        } else {
+            current_geometry
+        };
+        if tiled_state.take().is_some() {
+            element.set_tiled(false);
+        }
+        std::mem::drop(tiled_state);
+
+        element.moved_since_mapped.store(true, Ordering::SeqCst);
+        self.map_internal(
+            element.clone(),
+            Some(geometry.loc),
+            Some(geometry.size.as_logical()),
+            Some(start_rectangle),
+        );
+    }
+
+    /// Geometry of a tiled element at its current location with the size it had before tiling
+    fn untiled_geometry(&self, element: &CosmicMapped) -> Option<Rectangle<i32, Local>> {
+        element.floating_tiled.lock().unwrap().as_ref()?;
+        let last_size = element.last_geometry.lock().unwrap().map(|geo| geo.size)?;
+        let location = self.space.element_location(element)?;
+        Some(Rectangle::from_loc_and_size(location.as_local(), last_size))
+    }
+
+    fn snap_element_with(
+        &mut self,
+        element: &CosmicMapped,
+        new_state: impl FnOnce(Option<&TiledCorners>) -> TiledCorners,
+    ) {
+        let tiled_state = element.floating_tiled.lock().unwrap();
+
+        let output = self.space.outputs().next().unwrap().clone();
+        let layers = layer_map_for_output(&output);
+        let output_geometry = layers.non_exclusive_zone();
+        std::mem::drop(layers);
+
+        let Some(current_geometry) = self.space.element_geometry(element).map(RectExt::as_local)
+        else {
+            return;
+        };
+        let start_rectangle = if let Some(anim) = self.animations.remove(element) {
+            anim.geometry(
+                output_geometry,
+                current_geometry,
+                tiled_state.as_ref(),
+                self.gaps(),
+            )
+        } else {
+            current_geometry
+        };
+
+        let new_state = new_state(tiled_state.as_ref());
+        self.tile_element(
+            element,
+            tiled_state,
+            new_state,
+            output_geometry,
+            start_rectangle,
+        );
+    }
+
+    fn tile_element(
+        &mut self,
+        element: &CosmicMapped,
+        mut tiled_state: MutexGuard<'_, Option<TiledCorners>>,
+        new_state: TiledCorners,
+        output_geometry: Rectangle<i32, Logical>,
+        start_rectangle: Rectangle<i32, Local>,
+    ) {
+        let new_geo = new_state.relative_geometry(output_geometry, self.gaps());
+        let (new_pos, new_size) = (new_geo.loc, new_geo.size);
+        element.set_tiled(true); // TODO: More fine grained?
+        element.set_maximized(false);
+
+        if tiled_state.is_none() {
+            let last_geometry = element
+                .maximized_state
+                .lock()
+                .unwrap()
+                .take()
+                .map(|state| state.original_geometry)
+                .or_else(|| self.space.element_geometry(element).map(RectExt::as_local));
+
+            *element.last_geometry.lock().unwrap() = last_geometry;
+        }
+
+        *tiled_state = Some(new_state);
+        std::mem::drop(tiled_state);
+
+        element.moved_since_mapped.store(true, Ordering::SeqCst);
+        let element = element.clone();
+        self.map_internal(
+            element,
+            Some(new_pos),
+            Some(new_size.as_logical()),
+            Some(start_rectangle),
+        );
+    }
+
     pub fn move_current_element(
         &mut self,
         direction: Direction,
@@ -1169,6 +1496,19 @@ impl FloatingLayout {
         self.space.elements().rev()
     }
 
+    /// Moves windows, that are kept on top, above all others, keeping their order
+    pub fn raise_always_on_top(&mut self) {
+        for mapped in self
+            .space
+            .elements()
+            .filter(|m| m.always_on_top.load(Ordering::SeqCst))
+            .cloned()
+            .collect::<Vec<_>>()
+        {
+            self.space.raise_element(&mapped, false);
+        }
+    }
+
     pub fn windows(&self) -> impl Iterator<Item = CosmicSurface> + '_ {
         self.mapped().flat_map(|e| e.windows().map(|(w, _)| w))
     }
@@ -1211,6 +1551,7 @@ impl FloatingLayout {
     #[profiling::function]
     pub fn refresh(&mut self) {
         self.space.refresh();
+        self.raise_always_on_top();
 
         if let Some(pos) = self.spawn_order.iter().position(|w| !w.alive()) {
             self.spawn_order.truncate(pos);
@@ -1435,3 +1776,106 @@ impl FloatingLayout {
         (g.0 as i32, g.1 as i32)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmic_comp_config::snapping::ZoneLayout;
+
+    fn zone(x: f32, y: f32, width: f32, height: f32) -> Zone {
+        Zone {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn test_zone_geometry_rounding() {
+        assert_eq!(
+            ZoneGeometry::from(&zone(33.333, 0., 33.333, 100.)),
+            ZoneGeometry {
+                x: 3333,
+                y: 0,
+                width: 3334,
+                height: 10_000,
+            }
+        );
+        // out of bounds zones are clipped to the output
+        assert_eq!(
+            ZoneGeometry::from(&zone(-10., 75., 50., 50.)),
+            ZoneGeometry {
+                x: 0,
+                y: 7500,
+                width: 4000,
+                height: 2500,
+            }
+        );
+        assert_eq!(
+            ZoneGeometry::from(&zone(120., 0., 10., 100.)),
+            ZoneGeometry {
+                x: 10_000,
+                y: 0,
+                width: 0,
+                height: 10_000,
+            }
+        );
+    }
+
+    #[test]
+    fn test_zone_rectangles_are_adjacent() {
+        let zones = ZoneLayout::Grid {
+            columns: 3,
+            rows: 3,
+        }
+        .zones();
+        for (w, h) in [(1920, 1080), (1001, 767), (1366, 768), (7, 5)] {
+            let output = Rectangle::from_loc_and_size((100, -50), (w, h));
+            let rectangles = zones
+                .iter()
+                .map(|zone| ZoneGeometry::from(zone).rectangle(output))
+                .collect::<Vec<_>>();
+            for row in rectangles.chunks(3) {
+                assert_eq!(row[0].loc.x, output.loc.x);
+                assert_eq!(row[0].loc.x + row[0].size.w, row[1].loc.x);
+                assert_eq!(row[1].loc.x + row[1].size.w, row[2].loc.x);
+                assert_eq!(row[2].loc.x + row[2].size.w, output.loc.x + w);
+            }
+            for column in 0..3 {
+                let (top, middle, bottom) = (
+                    rectangles[column],
+                    rectangles[column + 3],
+                    rectangles[column + 6],
+                );
+                assert_eq!(top.loc.y, output.loc.y);
+                assert_eq!(top.loc.y + top.size.h, middle.loc.y);
+                assert_eq!(middle.loc.y + middle.size.h, bottom.loc.y);
+                assert_eq!(bottom.loc.y + bottom.size.h, output.loc.y + h);
+            }
+        }
+    }
+
+    #[test]
+    fn test_zone_rectangle_edges() {
+        let output = Rectangle::from_loc_and_size((1920, 0), (1280, 720));
+        let full = ZoneGeometry::from(&zone(0., 0., 100., 100.));
+        assert_eq!(full.rectangle(output), output);
+
+        let empty = ZoneGeometry::from(&zone(100., 0., 10., 100.));
+        assert_eq!(
+            empty.rectangle(output),
+            Rectangle::from_loc_and_size((3200, 0), (0, 720))
+        );
+
+        let half = ZoneGeometry::from(&zone(50., 50., 50., 50.));
+        assert_eq!(
+            half.rectangle(Rectangle::from_loc_and_size((0, 0), (0, 0))),
+            Rectangle::from_loc_and_size((0, 0), (0, 0))
+        );
+        assert_eq!(
+            half.rectangle(Rectangle::from_loc_and_size((0, 0), (3, 3))),
+            Rectangle::from_loc_and_size((1, 1), (2, 2))
+        );
+    }
+}
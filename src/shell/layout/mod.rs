@@ -1,5 +1,6 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
+use cosmic_comp_config::{window_rules::WindowRule, workspace::TilingOrientation};
 use cosmic_settings_config::{shortcuts::action::Orientation, window_rules::ApplicationException};
 use regex::{Regex, RegexSet};
 use smithay::{
@@ -12,6 +13,7 @@ use tracing::warn;
 use super::CosmicSurface;
 
 pub mod floating;
+pub mod scrolling;
 pub mod tiling;
 
 pub fn is_dialog(window: &CosmicSurface) -> bool {
@@ -54,6 +56,20 @@ pub fn is_dialog(window: &CosmicSurface) -> bool {
     false
 }
 
+pub fn orientation_from_config(orientation: TilingOrientation) -> Orientation {
+    match orientation {
+        TilingOrientation::Horizontal => Orientation::Horizontal,
+        TilingOrientation::Vertical => Orientation::Vertical,
+    }
+}
+
+pub fn orientation_to_config(orientation: Orientation) -> TilingOrientation {
+    match orientation {
+        Orientation::Horizontal => TilingOrientation::Horizontal,
+        Orientation::Vertical => TilingOrientation::Vertical,
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct TilingExceptions {
     app_ids: RegexSet,
@@ -63,7 +79,7 @@ pub struct TilingExceptions {
 impl TilingExceptions {
     pub fn new<'a, I>(exceptions_config: I) -> Self
     where
-      I: Iterator<Item=&'a ApplicationException>
+        I: Iterator<Item = &'a ApplicationException>,
     {
         let mut app_ids = Vec::new();
         let mut titles = Vec::new();
@@ -101,3 +117,140 @@ pub fn has_floating_exception(exceptions: &TilingExceptions, window: &CosmicSurf
 
     false
 }
+
+#[derive(Debug, Clone, Default)]
+pub struct WindowRules {
+    rules: Vec<(Option<Regex>, Option<Regex>, WindowRule)>,
+}
+
+impl WindowRules {
+    pub fn new<'a, I>(rules_config: I) -> Self
+    where
+        I: Iterator<Item = &'a WindowRule>,
+    {
+        let mut rules = Vec::new();
+
+        for rule in rules_config {
+            let app_id = match rule.appid.as_deref().map(Regex::new).transpose() {
+                Ok(app_id) => app_id,
+                Err(e) => {
+                    warn!("Invalid regex for appid: {:?}, {}", rule.appid, e);
+                    continue;
+                }
+            };
+            let title = match rule.title.as_deref().map(Regex::new).transpose() {
+                Ok(title) => title,
+                Err(e) => {
+                    warn!("Invalid regex for title: {:?}, {}", rule.title, e);
+                    continue;
+                }
+            };
+
+            rules.push((app_id, title, rule.clone()));
+        }
+
+        Self { rules }
+    }
+
+    /// Merges all rules matching `window`, later rules take precedence
+    pub fn matching(&self, window: &CosmicSurface) -> WindowRule {
+        self.matching_ids(&window.app_id(), &window.title())
+    }
+
+    fn matching_ids(&self, app_id: &str, title: &str) -> WindowRule {
+        let mut result = WindowRule::default();
+        for (app_id_regex, title_regex, rule) in &self.rules {
+            if app_id_regex.as_ref().map_or(true, |r| r.is_match(app_id))
+                && title_regex.as_ref().map_or(true, |r| r.is_match(title))
+            {
+                result.merge(rule);
+            }
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn rule(appid: Option<&str>, title: Option<&str>) -> WindowRule {
+        WindowRule {
+            appid: appid.map(String::from),
+            title: title.map(String::from),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_matching_rules() {
+        let rules = [
+            WindowRule {
+                floating: Some(true),
+                ..rule(Some("^firefox$"), None)
+            },
+            WindowRule {
+                workspace: Some(2),
+                ..rule(None, Some("Picture-in-Picture"))
+            },
+            WindowRule {
+                sticky: Some(true),
+                ..rule(Some("^firefox$"), Some("Picture-in-Picture"))
+            },
+        ];
+        let rules = WindowRules::new(rules.iter());
+
+        let matched = rules.matching_ids("firefox", "Mozilla Firefox");
+        assert_eq!(matched.floating, Some(true));
+        assert_eq!(matched.workspace, None);
+        assert_eq!(matched.sticky, None);
+
+        let matched = rules.matching_ids("firefox", "Picture-in-Picture");
+        assert_eq!(matched.floating, Some(true));
+        assert_eq!(matched.workspace, Some(2));
+        assert_eq!(matched.sticky, Some(true));
+
+        let matched = rules.matching_ids("org.mozilla.firefox", "Picture-in-Picture");
+        assert_eq!(matched.floating, None);
+        assert_eq!(matched.workspace, Some(2));
+        assert_eq!(matched.sticky, None);
+
+        assert_eq!(rules.matching_ids("kitty", "~"), WindowRule::default());
+    }
+
+    #[test]
+    fn test_later_rules_take_precedence() {
+        let rules = [
+            WindowRule {
+                workspace: Some(1),
+                opacity: Some(0.5),
+                ..rule(Some("kitty"), None)
+            },
+            WindowRule {
+                workspace: Some(3),
+                ..rule(Some("kitty"), None)
+            },
+        ];
+        let matched = WindowRules::new(rules.iter()).matching_ids("kitty", "~");
+        assert_eq!(matched.workspace, Some(3));
+        assert_eq!(matched.opacity, Some(0.5));
+    }
+
+    #[test]
+    fn test_invalid_rules_are_skipped() {
+        let rules = [
+            WindowRule {
+                floating: Some(true),
+                ..rule(Some("(kitty"), None)
+            },
+            WindowRule {
+                sticky: Some(true),
+                ..rule(None, Some("[~"))
+            },
+        ];
+        let rules = WindowRules::new(rules.iter());
+        assert!(rules.rules.is_empty());
+        assert_eq!(rules.matching_ids("(kitty", "[~"), WindowRule::default());
+    }
+}
@@ -0,0 +1,677 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::time::Instant;
+
+use cosmic_comp_config::DecorationsConfig;
+use cosmic_settings_config::shortcuts::action::FocusDirection;
+use smithay::{
+    backend::renderer::{element::RenderElement, ImportAll, ImportMem, Renderer},
+    desktop::{layer_map_for_output, space::SpaceElement},
+    input::Seat,
+    output::Output,
+    utils::{IsAlive, Point, Rectangle},
+};
+
+use crate::{
+    backend::render::{
+        animations::spring::{Spring, SpringParams},
+        element::AsGlowRenderer,
+        IndicatorShader, Key, SplitRenderElements, Usage,
+    },
+    shell::{
+        element::{
+            stack::CosmicStackRenderElement, window::CosmicWindowRenderElement, CosmicMapped,
+            CosmicMappedRenderElement,
+        },
+        focus::target::{KeyboardFocusTarget, PointerFocusTarget},
+        Direction, FocusResult, MoveResult,
+    },
+    state::State,
+    utils::prelude::*,
+};
+
+/// Width of new columns relative to the usable output width
+const DEFAULT_COLUMN_WIDTH: f64 = 0.5;
+
+#[derive(Debug, Clone)]
+struct Column<W = CosmicMapped> {
+    windows: Vec<W>,
+    /// Window of the column, that was focused last
+    active: usize,
+    /// Fraction of the usable output width
+    width: f64,
+}
+
+impl<W> Column<W> {
+    fn new(window: W, width: f64) -> Column<W> {
+        Column {
+            windows: vec![window],
+            active: 0,
+            width,
+        }
+    }
+
+    fn active_window(&self) -> &W {
+        &self.windows[self.active]
+    }
+}
+
+/// Column and row of `window`
+fn position<W: PartialEq>(columns: &[Column<W>], window: &W) -> Option<(usize, usize)> {
+    columns.iter().enumerate().find_map(|(i, column)| {
+        column
+            .windows
+            .iter()
+            .position(|w| w == window)
+            .map(|j| (i, j))
+    })
+}
+
+/// Removes the window at `row` of `column`, dropping the column once it is empty
+fn remove_window<W>(columns: &mut Vec<Column<W>>, column: usize, row: usize) -> W {
+    let col = &mut columns[column];
+    let window = col.windows.remove(row);
+    if col.windows.is_empty() {
+        columns.remove(column);
+    } else {
+        col.active = col.active.min(col.windows.len() - 1);
+    }
+    window
+}
+
+/// Position in the strip and width of columns with the relative `widths`
+fn column_spans(widths: impl Iterator<Item = f64>, zone_width: i32, inner: i32) -> Vec<(i32, i32)> {
+    let mut x = 0;
+    widths
+        .map(|width| {
+            let width = (((zone_width + inner) as f64 * width).round() as i32 - inner).max(1);
+            let span = (x, width);
+            x += width + inner;
+            span
+        })
+        .collect()
+}
+
+/// Height of each of `count` windows sharing a column
+fn row_height(zone_height: i32, count: usize, inner: i32) -> i32 {
+    ((zone_height + inner) / count as i32 - inner).max(1)
+}
+
+/// View offset, that makes the column at `span` fully visible, `None` if it already is
+fn scroll_target(view_offset: f64, (x, width): (i32, i32), zone_width: i32) -> Option<f64> {
+    if (x as f64) < view_offset || width > zone_width {
+        Some(x as f64)
+    } else if (x + width) as f64 > view_offset + zone_width as f64 {
+        Some((x + width - zone_width) as f64)
+    } else {
+        None
+    }
+}
+
+/// Largest view offset, that doesn't scroll past the end of the strip
+fn max_view_offset(spans: &[(i32, i32)], zone_width: i32) -> f64 {
+    let strip_width = spans.last().map(|(x, width)| x + width).unwrap_or(0);
+    (strip_width - zone_width).max(0) as f64
+}
+
+/// Places tiled windows into an endless horizontal strip of columns.
+///
+/// Instead of shrinking windows to make room, the viewport scrolls along the strip
+/// to keep the focused column visible.
+#[derive(Debug)]
+pub struct ScrollingLayout {
+    output: Output,
+    columns: Vec<Column>,
+    /// Position of the viewport in the strip, once all animations are done
+    view_offset: f64,
+    view_animation: Option<(Instant, Spring)>,
+    pub theme: cosmic::Theme,
+    pub decorations: DecorationsConfig,
+}
+
+impl ScrollingLayout {
+    pub fn new(
+        theme: cosmic::Theme,
+        decorations: DecorationsConfig,
+        output: &Output,
+    ) -> ScrollingLayout {
+        ScrollingLayout {
+            output: output.clone(),
+            columns: Vec::new(),
+            view_offset: 0.0,
+            view_animation: None,
+            theme,
+            decorations,
+        }
+    }
+
+    pub fn set_output(&mut self, output: &Output) {
+        for mapped in self.columns.iter().flat_map(|c| c.windows.iter()) {
+            mapped.output_leave(&self.output);
+            mapped.output_enter(output, mapped.bbox());
+        }
+        self.output = output.clone();
+        self.recalculate();
+    }
+
+    pub fn map<'a>(
+        &mut self,
+        window: CosmicMapped,
+        focus_stack: Option<impl Iterator<Item = &'a CosmicMapped> + 'a>,
+    ) {
+        window.output_enter(&self.output, window.bbox());
+        window.set_bounds(self.output.geometry().size.as_logical());
+
+        // new columns open right of the focused one
+        let idx = focus_stack
+            .and_then(|mut focus_stack| focus_stack.find_map(|focused| self.position(focused)))
+            .map(|(column, _)| column + 1)
+            .unwrap_or(self.columns.len());
+        self.columns
+            .insert(idx, Column::new(window, DEFAULT_COLUMN_WIDTH));
+        self.recalculate();
+        self.scroll_to_column(idx);
+    }
+
+    pub fn unmap(&mut self, window: &CosmicMapped) -> bool {
+        let Some((column, row)) = self.position(window) else {
+            return false;
+        };
+
+        remove_window(&mut self.columns, column, row);
+        window.output_leave(&self.output);
+        window.set_tiled(false);
+        window.set_tiling_layer(false);
+
+        // don't leave the viewport scrolled past the end of the strip
+        let zone = self.zone();
+        let max_offset = max_view_offset(&self.column_spans(zone), zone.size.w);
+        if self.view_offset > max_offset {
+            self.animate_view(max_offset);
+        }
+        self.recalculate();
+        true
+    }
+
+    /// Puts `new` in the place of `old`, returns whether `old` was found
+    pub fn replace_window(&mut self, old: &CosmicMapped, new: &CosmicMapped) -> bool {
+        let Some((column, row)) = self.position(old) else {
+            return false;
+        };
+
+        self.columns[column].windows[row] = new.clone();
+        old.output_leave(&self.output);
+        old.set_tiling_layer(false);
+        new.output_enter(&self.output, new.bbox());
+        new.set_bounds(self.output.geometry().size.as_logical());
+        self.recalculate();
+        true
+    }
+
+    fn position(&self, window: &CosmicMapped) -> Option<(usize, usize)> {
+        position(&self.columns, window)
+    }
+
+    pub fn mapped(&self) -> impl Iterator<Item = (&CosmicMapped, Rectangle<i32, Local>)> {
+        self.geometries(self.current_offset()).into_iter()
+    }
+
+    pub fn element_geometry(&self, elem: &CosmicMapped) -> Option<Rectangle<i32, Local>> {
+        self.mapped()
+            .find(|(mapped, _)| *mapped == elem)
+            .map(|(_, geo)| geo)
+    }
+
+    /// Returns the window or stack, whose header is under `location`
+    pub fn header_under(
+        &self,
+        location: Point<f64, Local>,
+    ) -> Option<(CosmicMapped, Rectangle<i32, Local>)> {
+        let location = location.to_i32_round();
+        self.mapped()
+            .find(|(mapped, geo)| {
+                let offset = location.y - geo.loc.y;
+                geo.contains(location)
+                    && mapped
+                        .header_height()
+                        .is_some_and(|height| offset.is_positive() && offset <= height)
+            })
+            .map(|(mapped, geo)| (mapped.clone(), geo))
+    }
+
+    /// Adds the windows of `window` to `target`, turning it into a stack if necessary
+    pub fn stack_window(
+        &mut self,
+        target: &CosmicMapped,
+        window: &CosmicMapped,
+    ) -> Option<(CosmicMapped, Point<i32, Local>)> {
+        let (column, row) = self.position(target)?;
+        let mapped = &mut self.columns[column].windows[row];
+        if mapped.is_window() {
+            mapped.convert_to_stack(
+                (&self.output, mapped.bbox()),
+                self.theme.clone(),
+                self.decorations.clone(),
+            );
+        }
+        let mapped = mapped.clone();
+
+        let stack = mapped.stack_ref().unwrap();
+        for surface in window.windows().map(|s| s.0) {
+            stack.add_window(surface, None);
+        }
+        self.recalculate();
+
+        let location = self.element_geometry(&mapped)?.loc;
+        Some((mapped, location))
+    }
+
+    /// Usable area of the output, windows are arranged in
+    fn zone(&self) -> Rectangle<i32, Local> {
+        let (outer, _) = self.gaps();
+        let mut geo = layer_map_for_output(&self.output)
+            .non_exclusive_zone()
+            .as_local();
+        geo.loc.x += outer;
+        geo.loc.y += outer;
+        geo.size.w -= outer * 2;
+        geo.size.h -= outer * 2;
+        geo
+    }
+
+    /// Position and width of every column in the strip
+    fn column_spans(&self, zone: Rectangle<i32, Local>) -> Vec<(i32, i32)> {
+        let (_, inner) = self.gaps();
+        column_spans(
+            self.columns.iter().map(|column| column.width),
+            zone.size.w,
+            inner,
+        )
+    }
+
+    fn geometries(&self, offset: f64) -> Vec<(&CosmicMapped, Rectangle<i32, Local>)> {
+        let zone = self.zone();
+        let (_, inner) = self.gaps();
+        let offset = offset.round() as i32;
+
+        self.columns
+            .iter()
+            .zip(self.column_spans(zone))
+            .flat_map(|(column, (x, width))| {
+                let height = row_height(zone.size.h, column.windows.len(), inner);
+                column.windows.iter().enumerate().map(move |(i, mapped)| {
+                    (
+                        mapped,
+                        Rectangle::from_loc_and_size(
+                            (
+                                zone.loc.x + x - offset,
+                                zone.loc.y + i as i32 * (height + inner),
+                            ),
+                            (width, height),
+                        ),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    pub fn recalculate(&mut self) {
+        for (mapped, geo) in self.geometries(self.view_offset) {
+            mapped.set_tiling_layer(true);
+            if !(mapped.is_fullscreen(true) || mapped.is_maximized(true)) {
+                mapped.set_tiled(true);
+                mapped.set_geometry(geo.to_global(&self.output));
+                mapped.configure();
+            }
+        }
+    }
+
+    pub fn refresh(&mut self) {
+        let dead_windows = self
+            .columns
+            .iter()
+            .flat_map(|c| c.windows.iter())
+            .filter(|w| !w.alive())
+            .cloned()
+            .collect::<Vec<_>>();
+        for dead_window in dead_windows.iter() {
+            self.unmap(dead_window);
+        }
+
+        for mapped in self.columns.iter().flat_map(|c| c.windows.iter()) {
+            mapped.refresh();
+        }
+    }
+
+    fn current_offset(&self) -> f64 {
+        match self.view_animation.as_ref() {
+            Some((start, spring)) => spring.value_at(Instant::now().duration_since(*start)),
+            None => self.view_offset,
+        }
+    }
+
+    fn animate_view(&mut self, target: f64) {
+        let current = self.current_offset();
+        self.view_animation = Some((
+            Instant::now(),
+            Spring {
+                from: current,
+                to: target,
+                initial_velocity: 0.0,
+                params: SpringParams::new(1.0, 800.0, 0.0001),
+            },
+        ));
+        self.view_offset = target;
+    }
+
+    /// Scrolls the viewport just enough to make the column of `window` fully visible
+    pub fn scroll_to(&mut self, window: &CosmicMapped) {
+        if let Some((column, row)) = self.position(window) {
+            self.columns[column].active = row;
+            self.scroll_to_column(column);
+        }
+    }
+
+    fn scroll_to_column(&mut self, idx: usize) {
+        let zone = self.zone();
+        let Some((x, width)) = self.column_spans(zone).get(idx).copied() else {
+            return;
+        };
+
+        let Some(target) = scroll_target(self.view_offset, (x, width), zone.size.w) else {
+            return;
+        };
+        self.animate_view(target);
+        self.recalculate();
+    }
+
+    pub fn animations_going(&self) -> bool {
+        self.view_animation.is_some()
+    }
+
+    pub fn update_animation_state(&mut self) {
+        if self.view_animation.as_ref().is_some_and(|(start, spring)| {
+            Instant::now().duration_since(*start) >= spring.duration()
+        }) {
+            self.view_animation = None;
+        }
+    }
+
+    pub fn next_focus(&self, direction: FocusDirection, focused: &CosmicMapped) -> FocusResult {
+        let Some((column, row)) = self.position(focused) else {
+            return FocusResult::None;
+        };
+
+        let next = match direction {
+            FocusDirection::Left => column
+                .checked_sub(1)
+                .map(|idx| self.columns[idx].active_window()),
+            FocusDirection::Right => self.columns.get(column + 1).map(Column::active_window),
+            FocusDirection::Up => row
+                .checked_sub(1)
+                .map(|idx| &self.columns[column].windows[idx]),
+            FocusDirection::Down => self.columns[column].windows.get(row + 1),
+            _ => None,
+        };
+
+        next.map(|mapped| FocusResult::Some(KeyboardFocusTarget::Element(mapped.clone())))
+            .unwrap_or(FocusResult::None)
+    }
+
+    /// Moves the focused window inside the strip.
+    ///
+    /// Windows sharing a column are expelled into a new one, when moved sideways,
+    /// otherwise the whole column swaps places with its neighbor.
+    pub fn move_current_element(&mut self, direction: Direction, seat: &Seat<State>) -> MoveResult {
+        let Some(KeyboardFocusTarget::Element(focused)) =
+            seat.get_keyboard().unwrap().current_focus()
+        else {
+            return MoveResult::None;
+        };
+        let Some((column, row)) = self.position(&focused) else {
+            return MoveResult::None;
+        };
+
+        let new_column = match direction {
+            Direction::Left | Direction::Right => {
+                let right = matches!(direction, Direction::Right);
+                if self.columns[column].windows.len() > 1 {
+                    let width = self.columns[column].width;
+                    let window = remove_window(&mut self.columns, column, row);
+                    let idx = if right { column + 1 } else { column };
+                    self.columns.insert(idx, Column::new(window, width));
+                    idx
+                } else {
+                    let other = if right {
+                        Some(column + 1).filter(|idx| *idx < self.columns.len())
+                    } else {
+                        column.checked_sub(1)
+                    };
+                    let Some(other) = other else {
+                        return MoveResult::MoveFurther(focused.into());
+                    };
+                    self.columns.swap(column, other);
+                    other
+                }
+            }
+            Direction::Up | Direction::Down => {
+                let col = &mut self.columns[column];
+                let other = if matches!(direction, Direction::Down) {
+                    Some(row + 1).filter(|idx| *idx < col.windows.len())
+                } else {
+                    row.checked_sub(1)
+                };
+                let Some(other) = other else {
+                    return MoveResult::MoveFurther(focused.into());
+                };
+                col.windows.swap(row, other);
+                col.active = other;
+                column
+            }
+        };
+
+        self.recalculate();
+        self.scroll_to_column(new_column);
+        MoveResult::Done
+    }
+
+    pub fn element_under(&self, location_f64: Point<f64, Local>) -> Option<KeyboardFocusTarget> {
+        let location = location_f64.to_i32_round();
+
+        for (mapped, geo) in self.mapped() {
+            if !mapped.bbox().contains((location - geo.loc).as_logical()) {
+                continue;
+            }
+            if mapped.is_in_input_region(
+                &((location_f64 - geo.loc.to_f64()).as_logical() + mapped.geometry().loc.to_f64()),
+            ) {
+                return Some(mapped.clone().into());
+            }
+        }
+
+        None
+    }
+
+    pub fn surface_under(
+        &self,
+        location_f64: Point<f64, Local>,
+    ) -> Option<(PointerFocusTarget, Point<f64, Local>)> {
+        let location = location_f64.to_i32_round();
+
+        for (mapped, geo) in self.mapped() {
+            if !mapped.bbox().contains((location - geo.loc).as_logical()) {
+                continue;
+            }
+            if let Some((target, surface_offset)) = mapped.focus_under(
+                (location_f64 - geo.loc.to_f64()).as_logical() + mapped.geometry().loc.to_f64(),
+            ) {
+                return Some((
+                    target,
+                    geo.loc.to_f64() - mapped.geometry().loc.as_local().to_f64()
+                        + surface_offset.as_local(),
+                ));
+            }
+        }
+
+        None
+    }
+
+    pub fn render<R>(
+        &self,
+        renderer: &mut R,
+        focused: Option<&CosmicMapped>,
+        indicator_thickness: u8,
+        alpha: f32,
+        theme: &cosmic::theme::CosmicTheme,
+    ) -> SplitRenderElements<CosmicMappedRenderElement<R>>
+    where
+        R: Renderer + ImportAll + ImportMem + AsGlowRenderer,
+        <R as Renderer>::TextureId: Send + Clone + 'static,
+        CosmicMappedRenderElement<R>: RenderElement<R>,
+        CosmicWindowRenderElement<R>: RenderElement<R>,
+        CosmicStackRenderElement<R>: RenderElement<R>,
+    {
+        let output_scale = self.output.current_scale().fractional_scale();
+        let output_geo =
+            Rectangle::from_loc_and_size((0, 0), self.output.geometry().size.as_local());
+
+        let mut elements = SplitRenderElements::default();
+
+        for (elem, geometry) in self.mapped() {
+            // maximized and fullscreen windows are drawn by the workspace
+            if elem.is_maximized(false) || elem.is_fullscreen(false) {
+                continue;
+            }
+            if !geometry.overlaps(output_geo) {
+                continue;
+            }
+
+            let render_location = geometry.loc - elem.geometry().loc.as_local();
+            let SplitRenderElements {
+                w_elements,
+                p_elements,
+            } = elem.split_render_elements(
+                renderer,
+                render_location
+                    .as_logical()
+                    .to_physical_precise_round(output_scale),
+                output_scale.into(),
+                alpha,
+            );
+
+            if focused == Some(elem) && indicator_thickness > 0 {
+                let active_window_hint = crate::theme::active_window_hint(theme);
+                let element = IndicatorShader::focus_element(
+                    renderer,
+                    Key::Window(Usage::FocusIndicator, elem.key()),
+                    geometry,
+                    indicator_thickness,
+                    output_scale,
+                    alpha,
+                    [
+                        active_window_hint.red,
+                        active_window_hint.green,
+                        active_window_hint.blue,
+                    ],
+                );
+                elements.w_elements.push(element.into());
+            }
+
+            elements.w_elements.extend(w_elements);
+            elements.p_elements.extend(p_elements);
+        }
+
+        elements
+    }
+
+    fn gaps(&self) -> (i32, i32) {
+        let g = self.theme.cosmic().gaps;
+        (g.0 as i32, g.1 as i32)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn columns(windows: &[&[u32]]) -> Vec<Column<u32>> {
+        windows
+            .iter()
+            .map(|windows| Column {
+                windows: windows.to_vec(),
+                active: 0,
+                width: DEFAULT_COLUMN_WIDTH,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_position() {
+        let columns = columns(&[&[1, 2], &[3]]);
+        assert_eq!(position(&columns, &1), Some((0, 0)));
+        assert_eq!(position(&columns, &2), Some((0, 1)));
+        assert_eq!(position(&columns, &3), Some((1, 0)));
+        assert_eq!(position(&columns, &4), None);
+    }
+
+    #[test]
+    fn test_remove_window() {
+        let mut columns = columns(&[&[1, 2], &[3], &[4]]);
+        columns[0].active = 1;
+
+        // the active window stays inside the column
+        assert_eq!(remove_window(&mut columns, 0, 1), 2);
+        assert_eq!(columns.len(), 3);
+        assert_eq!(columns[0].windows, vec![1]);
+        assert_eq!(*columns[0].active_window(), 1);
+
+        // empty columns are dropped
+        assert_eq!(remove_window(&mut columns, 1, 0), 3);
+        assert_eq!(columns.len(), 2);
+        assert_eq!(position(&columns, &4), Some((1, 0)));
+    }
+
+    #[test]
+    fn test_column_spans() {
+        // columns are separated by the inner gap, which is part of their relative width
+        assert_eq!(
+            column_spans([0.5, 0.5, 1.0].into_iter(), 1000, 10),
+            vec![(0, 495), (505, 495), (1010, 1000)]
+        );
+        assert_eq!(column_spans([0.5].into_iter(), 1000, 0), vec![(0, 500)]);
+        assert!(column_spans(std::iter::empty(), 1000, 10).is_empty());
+        // never collapse a column completely
+        assert_eq!(column_spans([0.0].into_iter(), 1000, 10), vec![(0, 1)]);
+    }
+
+    #[test]
+    fn test_row_height() {
+        assert_eq!(row_height(1000, 1, 10), 1000);
+        assert_eq!(row_height(1000, 2, 10), 495);
+        assert_eq!(row_height(1000, 3, 10), 326);
+    }
+
+    #[test]
+    fn test_scroll_target() {
+        // already visible
+        assert_eq!(scroll_target(0.0, (0, 500), 1000), None);
+        assert_eq!(scroll_target(500.0, (1000, 500), 1000), None);
+        // left of the viewport, align the left edges
+        assert_eq!(scroll_target(600.0, (505, 495), 1000), Some(505.0));
+        // right of the viewport, align the right edges
+        assert_eq!(scroll_target(0.0, (1010, 495), 1000), Some(505.0));
+        // wider than the viewport, show its start
+        assert_eq!(scroll_target(0.0, (500, 1500), 1000), Some(500.0));
+    }
+
+    #[test]
+    fn test_max_view_offset() {
+        assert_eq!(
+            max_view_offset(&[(0, 495), (505, 495), (1010, 1000)], 1000),
+            1010.0
+        );
+        // a strip narrower than the output doesn't scroll
+        assert_eq!(max_view_offset(&[(0, 495)], 1000), 0.0);
+        assert_eq!(max_view_offset(&[], 1000), 0.0);
+    }
+}
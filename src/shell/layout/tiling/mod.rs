@@ -21,7 +21,8 @@ use crate::{
             FocusStackMut,
         },
         grabs::ResizeEdge,
-        layout::Orientation,
+        layout::{orientation_to_config, Orientation},
+        restore::{SavedNode, WindowKey},
         CosmicSurface, Direction, FocusResult, MoveResult, OutputNotMapped, OverviewMode,
         ResizeMode, Trigger,
     },
@@ -38,6 +39,7 @@ use crate::{
     },
 };
 
+use cosmic_comp_config::{workspace::TilingAlgorithm, DecorationsConfig};
 use cosmic_settings_config::shortcuts::action::{FocusDirection, ResizeDirection};
 use id_tree::{InsertBehavior, MoveBehavior, Node, NodeId, NodeIdError, RemoveBehavior, Tree};
 use keyframe::{
@@ -135,6 +137,151 @@ pub struct TilingLayout {
     swapping_stack_surface_id: Id,
     last_overview_hover: Option<(Option<Instant>, TargetZone)>,
     pub theme: cosmic::Theme,
+    pub decorations: DecorationsConfig,
+    /// Orientation used for new splits instead of guessing by the available space
+    pub default_orientation: Option<Orientation>,
+    /// Automatic arrangement applied to the tree after every change
+    pub algorithm: TilingAlgorithm,
+}
+
+/// Target structure of the tree computed by a [`TilingAlgorithm`]
+#[derive(Debug)]
+enum Arrangement {
+    Window(NodeId),
+    Group(Orientation, Vec<Arrangement>, Vec<f64>),
+}
+
+impl Arrangement {
+    fn even(orientation: Orientation, mut children: Vec<Arrangement>) -> Arrangement {
+        if children.len() == 1 {
+            return children.pop().unwrap();
+        }
+        let ratios = vec![1.0 / children.len() as f64; children.len()];
+        Arrangement::Group(orientation, children, ratios)
+    }
+
+    /// Arranges `windows` with `algorithm`, `None` for manual tiling.
+    ///
+    /// Every arrangement has to keep the pre-order of the windows,
+    /// otherwise the next call would shuffle them around again.
+    fn for_algorithm(
+        algorithm: TilingAlgorithm,
+        mut windows: Vec<Arrangement>,
+    ) -> Option<Arrangement> {
+        Some(match algorithm {
+            TilingAlgorithm::Manual => return None,
+            TilingAlgorithm::MasterStack { masters, ratio } => {
+                let masters = (masters as usize).max(1);
+                if windows.len() <= masters {
+                    Arrangement::even(Orientation::Horizontal, windows)
+                } else {
+                    let stack = windows.split_off(masters);
+                    let ratio = (ratio as f64).clamp(0.1, 0.9);
+                    Arrangement::Group(
+                        Orientation::Vertical,
+                        vec![
+                            Arrangement::even(Orientation::Horizontal, windows),
+                            Arrangement::even(Orientation::Horizontal, stack),
+                        ],
+                        vec![ratio, 1.0 - ratio],
+                    )
+                }
+            }
+            TilingAlgorithm::Spiral => {
+                let mut windows = windows.into_iter().enumerate().rev();
+                let mut arrangement = windows.next().unwrap().1;
+                for (i, window) in windows {
+                    let orientation = if i % 2 == 0 {
+                        Orientation::Vertical
+                    } else {
+                        Orientation::Horizontal
+                    };
+                    arrangement =
+                        Arrangement::Group(orientation, vec![window, arrangement], vec![0.5, 0.5]);
+                }
+                arrangement
+            }
+            TilingAlgorithm::Columns { count } => {
+                let count = (count as usize).clamp(1, windows.len());
+                let (per_column, remainder) = (windows.len() / count, windows.len() % count);
+                let mut windows = windows.into_iter();
+                let columns = (0..count)
+                    .map(|i| {
+                        let len = per_column + usize::from(i < remainder);
+                        Arrangement::even(
+                            Orientation::Horizontal,
+                            windows.by_ref().take(len).collect(),
+                        )
+                    })
+                    .collect();
+                Arrangement::even(Orientation::Vertical, columns)
+            }
+        })
+    }
+
+    fn matches(&self, tree: &Tree<Data>, node_id: &NodeId) -> bool {
+        match (self, tree.get(node_id).unwrap().data()) {
+            (Arrangement::Window(id), data) if !data.is_group() => id == node_id,
+            (Arrangement::Group(orientation, children, _), data @ Data::Group { .. }) => {
+                data.orientation() == *orientation
+                    && children.len() == data.len()
+                    && children
+                        .iter()
+                        .zip(tree.children_ids(node_id).unwrap())
+                        .all(|(child, child_id)| child.matches(tree, child_id))
+            }
+            _ => false,
+        }
+    }
+
+    /// Restructures `tree` into this arrangement, unless it already has this shape.
+    ///
+    /// Windows are moved instead of recreated, so their node ids stay valid.
+    fn apply(self, tree: &mut Tree<Data>) {
+        let old_root = tree.root_node_id().unwrap().clone();
+        if self.matches(tree, &old_root) {
+            return;
+        }
+
+        // the windows are moved into the new root, leaving only empty groups behind
+        self.build(tree, None);
+        tree.remove_node(old_root, RemoveBehavior::DropChildren)
+            .unwrap();
+    }
+
+    fn build(self, tree: &mut Tree<Data>, parent: Option<&NodeId>) {
+        match self {
+            Arrangement::Window(id) => {
+                tree.move_node(&id, MoveBehavior::ToParent(parent.unwrap()))
+                    .unwrap();
+            }
+            Arrangement::Group(orientation, children, ratios) => {
+                // sizes are relative to `last_geometry` and get scaled by `update_positions`
+                let group = Node::new(Data::Group {
+                    orientation,
+                    sizes: ratios
+                        .iter()
+                        .map(|ratio| (ratio * 1000.0).round() as i32)
+                        .collect(),
+                    last_geometry: Rectangle::from_loc_and_size((0, 0), (1000, 1000)),
+                    alive: Arc::new(()),
+                    pill_indicator: None,
+                });
+                let group_id = tree
+                    .insert(
+                        group,
+                        match parent {
+                            Some(parent) => InsertBehavior::UnderNode(parent),
+                            None => InsertBehavior::AsRoot,
+                        },
+                    )
+                    .unwrap();
+                for child in children {
+                    child.build(tree, Some(&group_id));
+                }
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -236,6 +383,24 @@ impl Data {
         }
     }
 
+    fn apply_ratios(&mut self, ratios: &[f64]) {
+        match self {
+            Data::Group { sizes, .. } => {
+                if sizes.len() != ratios.len() {
+                    return;
+                }
+                let total: i32 = sizes.iter().sum();
+                for (size, ratio) in sizes.iter_mut().zip(ratios) {
+                    *size = (*ratio * total as f64).round() as i32;
+                }
+                // fix rounding issues
+                let used_size: i32 = sizes.iter().sum();
+                *sizes.last_mut().unwrap() += total - used_size;
+            }
+            _ => panic!("Applying ratios to a leaf?"),
+        }
+    }
+
     fn swap_windows(&mut self, i: usize, j: usize) {
         match self {
             Data::Group { sizes, .. } => {
@@ -342,7 +507,11 @@ pub struct MinimizedTilingState {
 }
 
 impl TilingLayout {
-    pub fn new(theme: cosmic::Theme, output: &Output) -> TilingLayout {
+    pub fn new(
+        theme: cosmic::Theme,
+        decorations: DecorationsConfig,
+        output: &Output,
+    ) -> TilingLayout {
         TilingLayout {
             queue: TreeQueue {
                 trees: {
@@ -358,6 +527,9 @@ impl TilingLayout {
             swapping_stack_surface_id: Id::new(),
             last_overview_hover: None,
             theme,
+            decorations,
+            default_orientation: None,
+            algorithm: TilingAlgorithm::Manual,
         }
     }
 
@@ -377,7 +549,7 @@ impl TilingLayout {
             }
         }
 
-        let blocker = TilingLayout::update_positions(output, &mut tree, gaps);
+        let blocker = TilingLayout::update_positions(output, &mut tree, gaps, self.algorithm);
         self.queue.push_tree(tree, None, blocker);
         self.output = output.clone();
     }
@@ -419,11 +591,133 @@ impl TilingLayout {
             last_active,
             direction,
             minimize_rect,
+            self.default_orientation,
         );
-        let blocker = TilingLayout::update_positions(&self.output, &mut tree, gaps);
+        let blocker = TilingLayout::update_positions(&self.output, &mut tree, gaps, self.algorithm);
         self.queue.push_tree(tree, duration, blocker);
     }
 
+    /// Maps `window` next to the `depth`-th ancestor of `neighbor`, splitting in `orientation`.
+    ///
+    /// Used to rebuild a previously saved tree. Returns `false`, if `neighbor` isn't part of this layout.
+    pub fn map_next_to(
+        &mut self,
+        window: CosmicMapped,
+        neighbor: &CosmicMapped,
+        depth: usize,
+        orientation: Orientation,
+        after: bool,
+        ratios: &[f64],
+    ) -> bool {
+        let gaps = self.gaps();
+        let mut tree = self.queue.trees.back().unwrap().0.copy_clone();
+
+        let Some(mut node_id) = neighbor
+            .tiling_node_id
+            .lock()
+            .unwrap()
+            .clone()
+            .filter(|id| tree.get(id).is_ok())
+        else {
+            return false;
+        };
+        for _ in 0..depth {
+            match tree.get(&node_id).unwrap().parent() {
+                Some(parent_id) => node_id = parent_id.clone(),
+                None => break,
+            }
+        }
+
+        window.output_enter(&self.output, window.bbox());
+        window.set_bounds(self.output.geometry().size.as_logical());
+
+        let new_window = Node::new(Data::Mapped {
+            mapped: window.clone(),
+            last_geometry: Rectangle::from_loc_and_size((0, 0), (100, 100)),
+            minimize_rect: None,
+        });
+        let parent_id = tree
+            .get(&node_id)
+            .unwrap()
+            .parent()
+            .filter(|parent_id| tree.get(parent_id).unwrap().data().orientation() == orientation)
+            .cloned();
+        let (new_id, parent_id) = if let Some(parent_id) = parent_id {
+            let idx = tree
+                .children_ids(&parent_id)
+                .unwrap()
+                .position(|id| id == &node_id)
+                .unwrap()
+                + after as usize;
+            let new_id = tree
+                .insert(new_window, InsertBehavior::UnderNode(&parent_id))
+                .unwrap();
+            tree.make_nth_sibling(&new_id, idx).unwrap();
+            tree.get_mut(&parent_id).unwrap().data_mut().add_window(idx);
+            (new_id, parent_id)
+        } else {
+            let new_id = tree.insert(new_window, InsertBehavior::AsRoot).unwrap();
+            let group_id =
+                TilingLayout::new_group(&mut tree, &node_id, &new_id, orientation).unwrap();
+            tree.make_nth_sibling(&new_id, after as usize).unwrap();
+            (new_id, group_id)
+        };
+        tree.get_mut(&parent_id)
+            .unwrap()
+            .data_mut()
+            .apply_ratios(ratios);
+        *window.tiling_node_id.lock().unwrap() = Some(new_id);
+
+        let blocker = TilingLayout::update_positions(&self.output, &mut tree, gaps, self.algorithm);
+        self.queue.push_tree(tree, ANIMATION_DURATION, blocker);
+        true
+    }
+
+    /// Serializable representation of the current tree
+    pub fn saved_tree(&self) -> Option<SavedNode> {
+        fn save(tree: &Tree<Data>, node_id: &NodeId) -> Option<SavedNode> {
+            match tree.get(node_id).unwrap().data() {
+                Data::Group {
+                    orientation, sizes, ..
+                } => {
+                    let children = tree
+                        .children_ids(node_id)
+                        .unwrap()
+                        .zip(sizes.iter())
+                        .filter_map(|(id, size)| save(tree, id).map(|node| (node, *size)))
+                        .collect::<Vec<_>>();
+                    let total = children
+                        .iter()
+                        .map(|(_, size)| *size as f64)
+                        .sum::<f64>()
+                        .max(1.0);
+                    let (children, ratios) = children
+                        .into_iter()
+                        .map(|(node, size)| (node, size as f64 / total))
+                        .unzip();
+                    Some(SavedNode::Group {
+                        orientation: orientation_to_config(*orientation),
+                        ratios,
+                        children,
+                    })
+                }
+                Data::Mapped { mapped, .. } if mapped.is_stack() => Some(SavedNode::Stack(
+                    mapped
+                        .windows()
+                        .map(|(window, _)| WindowKey::from(&window))
+                        .collect(),
+                )),
+                Data::Mapped { mapped, .. } => {
+                    Some(SavedNode::Window(WindowKey::from(&mapped.active_window())))
+                }
+                Data::Placeholder { .. } => None,
+            }
+        }
+
+        let tree = &self.queue.trees.back().unwrap().0;
+        tree.root_node_id().and_then(|root_id| save(tree, root_id))
+    }
+
     pub fn remap_minimized<'a>(
         &mut self,
         window: CosmicMapped,
@@ -485,7 +779,12 @@ impl TilingLayout {
                     tree.make_nth_sibling(&new_id, idx).unwrap();
                     *window.tiling_node_id.lock().unwrap() = Some(new_id);
 
-                    let blocker = TilingLayout::update_positions(&self.output, &mut tree, gaps);
+                    let blocker = TilingLayout::update_positions(
+                        &self.output,
+                        &mut tree,
+                        gaps,
+                        self.algorithm,
+                    );
                     self.queue
                         .push_tree(tree, MINIMIZE_ANIMATION_DURATION, blocker);
                     return;
@@ -524,7 +823,8 @@ impl TilingLayout {
 
                 *window.tiling_node_id.lock().unwrap() = Some(new_id);
 
-                let blocker = TilingLayout::update_positions(&self.output, &mut tree, gaps);
+                let blocker =
+                    TilingLayout::update_positions(&self.output, &mut tree, gaps, self.algorithm);
                 self.queue
                     .push_tree(tree, MINIMIZE_ANIMATION_DURATION, blocker);
                 return;
@@ -542,6 +842,7 @@ impl TilingLayout {
         node: Option<NodeId>,
         direction: Option<Direction>,
         minimize_rect: Option<Rectangle<i32, Local>>,
+        default_orientation: Option<Orientation>,
     ) {
         let window = window.into();
         let new_window = Node::new(Data::Mapped {
@@ -573,28 +874,28 @@ impl TilingLayout {
             }
         } else {
             if let Some(ref node_id) = node {
-                let orientation = {
+                let orientation = default_orientation.unwrap_or_else(|| {
                     let window_size = tree.get(node_id).unwrap().data().geometry().size;
                     if window_size.w > window_size.h {
                         Orientation::Vertical
                     } else {
                         Orientation::Horizontal
                     }
-                };
+                });
                 let new_id = tree.insert(new_window, InsertBehavior::AsRoot).unwrap();
                 TilingLayout::new_group(&mut tree, &node_id, &new_id, orientation).unwrap();
                 new_id
             } else {
                 // nothing? then we add to the root
                 if let Some(root_id) = tree.root_node_id().cloned() {
-                    let orientation = {
+                    let orientation = default_orientation.unwrap_or_else(|| {
                         let output_size = output.geometry().size;
                         if output_size.w > output_size.h {
                             Orientation::Vertical
                         } else {
                             Orientation::Horizontal
                         }
-                    };
+                    });
                     let new_id = tree.insert(new_window, InsertBehavior::AsRoot).unwrap();
                     TilingLayout::new_group(&mut tree, &root_id, &new_id, orientation).unwrap();
                     new_id
@@ -629,7 +930,8 @@ impl TilingLayout {
             old.output_leave(&self.output);
             new.output_enter(&self.output, new.bbox());
 
-            let blocker = TilingLayout::update_positions(&self.output, &mut tree, gaps);
+            let blocker =
+                TilingLayout::update_positions(&self.output, &mut tree, gaps, self.algorithm);
             self.queue.push_tree(tree, ANIMATION_DURATION, blocker);
         }
     }
@@ -663,9 +965,13 @@ impl TilingLayout {
                     this.unmap(&this_mapped);
                 }
 
-                let mapped: CosmicMapped =
-                    CosmicWindow::new(stack_surface, this_stack.loop_handle(), this.theme.clone())
-                        .into();
+                let mapped: CosmicMapped = CosmicWindow::new(
+                    stack_surface,
+                    this_stack.loop_handle(),
+                    this.theme.clone(),
+                    this.decorations.clone(),
+                )
+                .into();
                 if this.output != other.output {
                     mapped.output_leave(&this.output);
                     mapped.output_enter(&other.output, mapped.bbox());
@@ -802,12 +1108,20 @@ impl TilingLayout {
                 let other_gaps = other.gaps();
 
                 TilingLayout::unmap_internal(&mut this_tree, &desc.node);
-                let blocker =
-                    TilingLayout::update_positions(&this.output, &mut this_tree, this_gaps);
+                let blocker = TilingLayout::update_positions(
+                    &this.output,
+                    &mut this_tree,
+                    this_gaps,
+                    this.algorithm,
+                );
                 this.queue.push_tree(this_tree, ANIMATION_DURATION, blocker);
 
-                let blocker =
-                    TilingLayout::update_positions(&other.output, &mut other_tree, other_gaps);
+                let blocker = TilingLayout::update_positions(
+                    &other.output,
+                    &mut other_tree,
+                    other_gaps,
+                    other.algorithm,
+                );
                 other
                     .queue
                     .push_tree(other_tree, ANIMATION_DURATION, blocker);
@@ -1070,6 +1384,7 @@ impl TilingLayout {
                     this_surface.clone(),
                     this_stack.loop_handle(),
                     this.theme.clone(),
+                    this.decorations.clone(),
                 )
                 .into();
                 mapped.set_tiled(true);
@@ -1157,6 +1472,7 @@ impl TilingLayout {
                     other_surface.clone(),
                     other_stack.loop_handle(),
                     this.theme.clone(),
+                    this.decorations.clone(),
                 )
                 .into();
                 mapped.set_tiled(true);
@@ -1244,18 +1560,20 @@ impl TilingLayout {
         }
 
         let this_gaps = this.gaps();
-        let blocker = TilingLayout::update_positions(&this.output, &mut this_tree, this_gaps);
+        let blocker =
+            TilingLayout::update_positions(&this.output, &mut this_tree, this_gaps, this.algorithm);
         this.queue.push_tree(this_tree, ANIMATION_DURATION, blocker);
 
         let has_other_tree = other_tree.is_some();
         if let Some(mut other_tree) = other_tree {
-            let (other_queue, gaps) = if let Some(other) = other.as_mut() {
+            let (other_queue, gaps, algorithm) = if let Some(other) = other.as_mut() {
                 let other_gaps = other.gaps();
-                (&mut other.queue, other_gaps)
+                (&mut other.queue, other_gaps, other.algorithm)
             } else {
-                (&mut this.queue, this_gaps)
+                (&mut this.queue, this_gaps, this.algorithm)
             };
-            let blocker = TilingLayout::update_positions(&other_output, &mut other_tree, gaps);
+            let blocker =
+                TilingLayout::update_positions(&other_output, &mut other_tree, gaps, algorithm);
             other_queue.push_tree(other_tree, ANIMATION_DURATION, blocker);
         }
 
@@ -1295,6 +1613,7 @@ impl TilingLayout {
         if self.unmap_window_internal(window, false) {
             window.output_leave(&self.output);
             window.set_tiled(false);
+            window.set_tiling_layer(false);
             *window.tiling_node_id.lock().unwrap() = None;
             true
         } else {
@@ -1321,6 +1640,7 @@ impl TilingLayout {
 
         window.output_leave(&self.output);
         window.set_tiled(false);
+        window.set_tiling_layer(false);
         Some(node_id)
     }
 
@@ -1411,7 +1731,8 @@ impl TilingLayout {
                 } else {
                     ANIMATION_DURATION
                 };
-                let blocker = TilingLayout::update_positions(&self.output, &mut tree, gaps);
+                let blocker =
+                    TilingLayout::update_positions(&self.output, &mut tree, gaps, self.algorithm);
                 self.queue.push_tree(tree, duration, blocker);
 
                 return true;
@@ -1501,8 +1822,13 @@ impl TilingLayout {
             match window.handle_move(direction) {
                 StackMoveResult::Handled => return MoveResult::Done,
                 StackMoveResult::MoveOut(surface, loop_handle) => {
-                    let mapped: CosmicMapped =
-                        CosmicWindow::new(surface, loop_handle, self.theme.clone()).into();
+                    let mapped: CosmicMapped = CosmicWindow::new(
+                        surface,
+                        loop_handle,
+                        self.theme.clone(),
+                        self.decorations.clone(),
+                    )
+                    .into();
                     mapped.output_enter(&self.output, mapped.bbox());
                     let orientation = match direction {
                         Direction::Left | Direction::Right => Orientation::Vertical,
@@ -1526,7 +1852,12 @@ impl TilingLayout {
                     .unwrap();
                     *mapped.tiling_node_id.lock().unwrap() = Some(new_id);
 
-                    let blocker = TilingLayout::update_positions(&self.output, &mut tree, gaps);
+                    let blocker = TilingLayout::update_positions(
+                        &self.output,
+                        &mut tree,
+                        gaps,
+                        self.algorithm,
+                    );
                     self.queue.push_tree(tree, ANIMATION_DURATION, blocker);
                     return MoveResult::ShiftFocus(mapped.into());
                 }
@@ -1602,7 +1933,8 @@ impl TilingLayout {
                     .data_mut()
                     .remove_window(og_idx);
 
-                let blocker = TilingLayout::update_positions(&self.output, &mut tree, gaps);
+                let blocker =
+                    TilingLayout::update_positions(&self.output, &mut tree, gaps, self.algorithm);
                 self.queue.push_tree(tree, ANIMATION_DURATION, blocker);
                 return MoveResult::Done;
             }
@@ -1628,7 +1960,8 @@ impl TilingLayout {
                     .data_mut()
                     .remove_window(og_idx);
 
-                let blocker = TilingLayout::update_positions(&self.output, &mut tree, gaps);
+                let blocker =
+                    TilingLayout::update_positions(&self.output, &mut tree, gaps, self.algorithm);
                 self.queue.push_tree(tree, ANIMATION_DURATION, blocker);
                 return MoveResult::Done;
             }
@@ -1784,7 +2117,8 @@ impl TilingLayout {
                     MoveResult::Done
                 };
 
-                let blocker = TilingLayout::update_positions(&self.output, &mut tree, gaps);
+                let blocker =
+                    TilingLayout::update_positions(&self.output, &mut tree, gaps, self.algorithm);
                 self.queue.push_tree(tree, ANIMATION_DURATION, blocker);
                 return result;
             }
@@ -2110,7 +2444,12 @@ impl TilingLayout {
 
                     *orientation = new_orientation;
 
-                    let blocker = TilingLayout::update_positions(&self.output, &mut tree, gaps);
+                    let blocker = TilingLayout::update_positions(
+                        &self.output,
+                        &mut tree,
+                        gaps,
+                        self.algorithm,
+                    );
                     self.queue.push_tree(tree, ANIMATION_DURATION, blocker);
                 }
             }
@@ -2137,7 +2476,11 @@ impl TilingLayout {
             // if it is just a window
             match tree.get_mut(&node_id).unwrap().data_mut() {
                 Data::Mapped { mapped, .. } => {
-                    mapped.convert_to_stack((&self.output, mapped.bbox()), self.theme.clone());
+                    mapped.convert_to_stack(
+                        (&self.output, mapped.bbox()),
+                        self.theme.clone(),
+                        self.decorations.clone(),
+                    );
                     focus_stack.append(&mapped);
                     KeyboardFocusTarget::Element(mapped.clone())
                 }
@@ -2158,6 +2501,7 @@ impl TilingLayout {
                         first,
                         (&self.output, mapped.bbox()),
                         self.theme.clone(),
+                        self.decorations.clone(),
                     );
                     new_elements.push(mapped.clone());
                     handle
@@ -2175,6 +2519,7 @@ impl TilingLayout {
                     other,
                     handle.clone(),
                     self.theme.clone(),
+                    self.decorations.clone(),
                 ));
                 window.output_enter(&self.output, window.bbox());
 
@@ -2190,6 +2535,7 @@ impl TilingLayout {
                     Some(current_node),
                     None,
                     None,
+                    self.default_orientation,
                 );
 
                 let node = window.tiling_node_id.lock().unwrap().clone().unwrap();
@@ -2228,7 +2574,7 @@ impl TilingLayout {
             }
         };
 
-        let blocker = TilingLayout::update_positions(&self.output, &mut tree, gaps);
+        let blocker = TilingLayout::update_positions(&self.output, &mut tree, gaps, self.algorithm);
         self.queue.push_tree(tree, ANIMATION_DURATION, blocker);
 
         Some(result)
@@ -2274,7 +2620,12 @@ impl TilingLayout {
                         return None;
                     }
                     let handle = handle.unwrap();
-                    let stack = CosmicStack::new(surfaces.into_iter(), handle, self.theme.clone());
+                    let stack = CosmicStack::new(
+                        surfaces.into_iter(),
+                        handle,
+                        self.theme.clone(),
+                        self.decorations.clone(),
+                    );
 
                     for child in tree
                         .children_ids(&last_active)
@@ -2304,7 +2655,12 @@ impl TilingLayout {
                         minimize_rect: None,
                     };
 
-                    let blocker = TilingLayout::update_positions(&self.output, &mut tree, gaps);
+                    let blocker = TilingLayout::update_positions(
+                        &self.output,
+                        &mut tree,
+                        gaps,
+                        self.algorithm,
+                    );
                     self.queue.push_tree(tree, ANIMATION_DURATION, blocker);
 
                     return Some(KeyboardFocusTarget::Element(mapped));
@@ -2315,11 +2671,19 @@ impl TilingLayout {
         None
     }
 
+    pub fn set_algorithm(&mut self, algorithm: TilingAlgorithm) {
+        if self.algorithm == algorithm {
+            return;
+        }
+        self.algorithm = algorithm;
+        self.recalculate();
+    }
+
     pub fn recalculate(&mut self) {
         let gaps = self.gaps();
 
         let mut tree = self.queue.trees.back().unwrap().0.copy_clone();
-        let blocker = TilingLayout::update_positions(&self.output, &mut tree, gaps);
+        let blocker = TilingLayout::update_positions(&self.output, &mut tree, gaps, self.algorithm);
         self.queue.push_tree(tree, ANIMATION_DURATION, blocker);
     }
 
@@ -2589,7 +2953,8 @@ impl TilingLayout {
                 }
                 _ => unreachable!(),
             }
-            let blocker = TilingLayout::update_positions(&self.output, &mut tree, gaps);
+            let blocker =
+                TilingLayout::update_positions(&self.output, &mut tree, gaps, self.algorithm);
             self.queue.push_tree(tree, None, blocker);
 
             return true;
@@ -2608,6 +2973,82 @@ impl TilingLayout {
         }
     }
 
+    /// Whether a window dropped right now would be placed at a hovered target zone
+    pub fn has_drop_target(&self) -> bool {
+        self.last_overview_hover.is_some()
+    }
+
+    /// Tiled window or stack, whose header is under `location`
+    pub fn header_under(
+        &self,
+        location: Point<f64, Local>,
+    ) -> Option<(CosmicMapped, Rectangle<i32, Local>)> {
+        let location = location.to_i32_round();
+        self.mapped()
+            .find(|(mapped, geo)| {
+                let offset = location.y - geo.loc.y;
+                geo.contains(location)
+                    && mapped
+                        .header_height()
+                        .is_some_and(|height| offset.is_positive() && offset <= height)
+            })
+            .map(|(mapped, geo)| (mapped.clone(), geo))
+    }
+
+    /// Adds the windows of `window` to the tiled `target`, turning it into a stack if necessary
+    pub fn stack_window(
+        &mut self,
+        target: &CosmicMapped,
+        window: &CosmicMapped,
+    ) -> Option<(CosmicMapped, Point<i32, Local>)> {
+        let gaps = self.gaps();
+        let node_id = target.tiling_node_id.lock().unwrap().clone()?;
+        let mut tree = self.queue.trees.back().unwrap().0.copy_clone();
+        if !tree
+            .get(&node_id)
+            .is_ok_and(|node| node.data().is_mapped(None))
+        {
+            return None;
+        }
+
+        let mapped = TilingLayout::stack_onto_node(
+            &mut tree,
+            &node_id,
+            window,
+            &self.output,
+            self.theme.clone(),
+            self.decorations.clone(),
+        );
+        let blocker = TilingLayout::update_positions(&self.output, &mut tree, gaps, self.algorithm);
+        self.queue.push_tree(tree, ANIMATION_DURATION, blocker);
+
+        let location = self.element_geometry(&mapped).unwrap().loc;
+        Some((mapped, location))
+    }
+
+    fn stack_onto_node(
+        tree: &mut Tree<Data>,
+        node_id: &NodeId,
+        window: &CosmicMapped,
+        output: &Output,
+        theme: cosmic::Theme,
+        decorations: DecorationsConfig,
+    ) -> CosmicMapped {
+        match tree.get_mut(node_id).unwrap().data_mut() {
+            Data::Mapped { mapped, .. } => {
+                mapped.convert_to_stack((output, mapped.bbox()), theme, decorations);
+                let Some(stack) = mapped.stack_ref_mut() else {
+                    unreachable!()
+                };
+                for surface in window.windows().map(|s| s.0) {
+                    stack.add_window(surface, None);
+                }
+                mapped.clone()
+            }
+            _ => unreachable!(),
+        }
+    }
+
     pub fn cleanup_drag(&mut self) {
         let gaps = self.gaps();
 
@@ -2629,7 +3070,8 @@ impl TilingLayout {
                 }
             }
 
-            let blocker = TilingLayout::update_positions(&self.output, &mut tree, gaps);
+            let blocker =
+                TilingLayout::update_positions(&self.output, &mut tree, gaps, self.algorithm);
             self.queue.push_tree(tree, ANIMATION_DURATION, blocker);
         }
     }
@@ -2739,19 +3181,14 @@ impl TilingLayout {
                 window
             }
             Some(TargetZone::WindowStack(window_id, _)) if tree.get(&window_id).is_ok() => {
-                match tree.get_mut(window_id).unwrap().data_mut() {
-                    Data::Mapped { mapped, .. } => {
-                        mapped.convert_to_stack((&self.output, mapped.bbox()), self.theme.clone());
-                        let Some(stack) = mapped.stack_ref_mut() else {
-                            unreachable!()
-                        };
-                        for surface in window.windows().map(|s| s.0) {
-                            stack.add_window(surface, None);
-                        }
-                        mapped.clone()
-                    }
-                    _ => unreachable!(),
-                }
+                TilingLayout::stack_onto_node(
+                    &mut tree,
+                    window_id,
+                    &window,
+                    &self.output,
+                    self.theme.clone(),
+                    self.decorations.clone(),
+                )
             }
             _ => {
                 TilingLayout::map_to_tree(
@@ -2761,6 +3198,7 @@ impl TilingLayout {
                     None,
                     None,
                     None,
+                    self.default_orientation,
                 );
                 window
             }
@@ -2783,7 +3221,7 @@ impl TilingLayout {
             }
         }
 
-        let blocker = TilingLayout::update_positions(&self.output, &mut tree, gaps);
+        let blocker = TilingLayout::update_positions(&self.output, &mut tree, gaps, self.algorithm);
         self.queue.push_tree(tree, ANIMATION_DURATION, blocker);
 
         let location = self.element_geometry(&mapped).unwrap().loc;
@@ -2952,12 +3390,45 @@ impl TilingLayout {
         }
     }
 
+    /// Computes the structure `algorithm` wants for the windows of `tree`, keeping their order
+    fn arrangement(tree: &Tree<Data>, algorithm: TilingAlgorithm) -> Option<Arrangement> {
+        if algorithm == TilingAlgorithm::Manual {
+            return None;
+        }
+
+        let root_id = tree.root_node_id()?;
+        let mut windows = Vec::new();
+        for node_id in tree.traverse_pre_order_ids(root_id).unwrap() {
+            match tree.get(&node_id).unwrap().data() {
+                Data::Mapped { .. } => windows.push(Arrangement::Window(node_id)),
+                // don't interfere with an ongoing drag and drop
+                Data::Placeholder { .. } => return None,
+                Data::Group { .. } => {}
+            }
+        }
+        if windows.len() < 2 {
+            return None;
+        }
+
+        Arrangement::for_algorithm(algorithm, windows)
+    }
+
+    /// Restructures `tree` according to `algorithm`, unless it already has the desired shape
+    fn arrange(tree: &mut Tree<Data>, algorithm: TilingAlgorithm) {
+        if let Some(arrangement) = TilingLayout::arrangement(tree, algorithm) {
+            arrangement.apply(tree);
+        }
+    }
+
     #[profiling::function]
     fn update_positions(
         output: &Output,
         tree: &mut Tree<Data>,
         gaps: (i32, i32),
+        algorithm: TilingAlgorithm,
     ) -> Option<TilingBlocker> {
+        TilingLayout::arrange(tree, algorithm);
+
         if let Some(root_id) = tree.root_node_id() {
             let mut configures = Vec::new();
 
@@ -3077,6 +3548,7 @@ impl TilingLayout {
                             }
                         },
                         Data::Mapped { mapped, .. } => {
+                            mapped.set_tiling_layer(true);
                             if !(mapped.is_fullscreen(true) || mapped.is_maximized(true)) {
                                 mapped.set_tiled(true);
                                 let internal_geometry = geo.to_global(&output);
@@ -3703,6 +4175,7 @@ impl TilingLayout {
                                         &self.output,
                                         &mut tree,
                                         gaps,
+                                        self.algorithm,
                                     );
                                     self.queue.push_tree(tree, duration, blocker);
                                 }
@@ -3810,7 +4283,7 @@ impl TilingLayout {
         };
         TilingLayout::merge_trees(src, &mut dst, orientation);
 
-        let blocker = TilingLayout::update_positions(&self.output, &mut dst, gaps);
+        let blocker = TilingLayout::update_positions(&self.output, &mut dst, gaps, self.algorithm);
         self.queue.push_tree(dst, ANIMATION_DURATION, blocker);
     }
 
@@ -4936,17 +5409,26 @@ where
     }
 
     // render single stack window when swapping separately
-    if let Some(window) = swap_desc
+    if let Some((desc, window)) = swap_desc
         .as_ref()
-        .and_then(|desc| desc.stack_window.clone())
+        .and_then(|desc| desc.stack_window.clone().map(|window| (desc, window)))
     {
+        let tab_height = swap_tree
+            .unwrap_or(target_tree)
+            .get(&desc.node)
+            .ok()
+            .and_then(|node| match node.data() {
+                Data::Mapped { mapped, .. } => mapped.stack_ref().map(CosmicStack::tab_height),
+                _ => None,
+            })
+            .unwrap_or(STACK_TAB_HEIGHT);
         let window_geo = window.geometry();
         let swap_geo = ease(
             Linear,
             EaseRectangle({
                 let mut geo = focused_geo.clone();
-                geo.loc.x += STACK_TAB_HEIGHT;
-                geo.size.h -= STACK_TAB_HEIGHT;
+                geo.loc.x += tab_height;
+                geo.size.h -= tab_height;
                 geo
             }),
             EaseRectangle(swap_geometry(window_geo.size, focused_geo)),
@@ -5413,3 +5895,253 @@ fn scale_to_center<C>(
         )
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use smithay::output::{Mode, PhysicalProperties, Subpixel};
+
+    /// Window node ids and a function rendering arrangements as e.g. `V[0,H[1,2]]`
+    fn window_ids(count: usize) -> (Vec<Arrangement>, impl Fn(&Arrangement) -> String) {
+        let mut tree = Tree::<()>::new();
+        let root = tree.insert(Node::new(()), InsertBehavior::AsRoot).unwrap();
+        let ids = (0..count)
+            .map(|_| {
+                tree.insert(Node::new(()), InsertBehavior::UnderNode(&root))
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        fn shape(arrangement: &Arrangement, ids: &[NodeId]) -> String {
+            match arrangement {
+                Arrangement::Window(id) => ids.iter().position(|i| i == id).unwrap().to_string(),
+                Arrangement::Group(orientation, children, _) => format!(
+                    "{}[{}]",
+                    match orientation {
+                        Orientation::Horizontal => "H",
+                        Orientation::Vertical => "V",
+                    },
+                    children
+                        .iter()
+                        .map(|child| shape(child, ids))
+                        .collect::<Vec<_>>()
+                        .join(",")
+                ),
+            }
+        }
+
+        let windows = ids.iter().cloned().map(Arrangement::Window).collect();
+        (windows, move |arrangement: &Arrangement| {
+            shape(arrangement, &ids)
+        })
+    }
+
+    fn ratios(arrangement: &Arrangement) -> Vec<f64> {
+        match arrangement {
+            Arrangement::Group(_, _, ratios) => ratios.clone(),
+            Arrangement::Window(_) => Vec::new(),
+        }
+    }
+
+    fn assert_ratios(arrangement: &Arrangement, expected: &[f64]) {
+        let ratios = ratios(arrangement);
+        assert_eq!(ratios.len(), expected.len());
+        for (ratio, expected) in ratios.iter().zip(expected) {
+            assert!(
+                (ratio - expected).abs() < 1e-6,
+                "{ratios:?} != {expected:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_manual_arrangement() {
+        let (windows, _) = window_ids(3);
+        assert!(Arrangement::for_algorithm(TilingAlgorithm::Manual, windows).is_none());
+    }
+
+    #[test]
+    fn test_master_stack_arrangement() {
+        let algorithm = TilingAlgorithm::MasterStack {
+            masters: 1,
+            ratio: 0.6,
+        };
+        let (windows, shape) = window_ids(4);
+        let arrangement = Arrangement::for_algorithm(algorithm, windows).unwrap();
+        assert_eq!(shape(&arrangement), "V[0,H[1,2,3]]");
+        assert_ratios(&arrangement, &[0.6, 0.4]);
+
+        let algorithm = TilingAlgorithm::MasterStack {
+            masters: 2,
+            ratio: 0.95,
+        };
+        let (windows, shape) = window_ids(3);
+        let arrangement = Arrangement::for_algorithm(algorithm, windows).unwrap();
+        assert_eq!(shape(&arrangement), "V[H[0,1],2]");
+        assert_ratios(&arrangement, &[0.9, 0.1]);
+
+        // not enough windows for a stack
+        let (windows, shape) = window_ids(2);
+        let arrangement = Arrangement::for_algorithm(algorithm, windows).unwrap();
+        assert_eq!(shape(&arrangement), "H[0,1]");
+        assert_ratios(&arrangement, &[0.5, 0.5]);
+
+        // at least one master
+        let algorithm = TilingAlgorithm::MasterStack {
+            masters: 0,
+            ratio: 0.5,
+        };
+        let (windows, shape) = window_ids(3);
+        let arrangement = Arrangement::for_algorithm(algorithm, windows).unwrap();
+        assert_eq!(shape(&arrangement), "V[0,H[1,2]]");
+    }
+
+    fn leaf(geo: Rectangle<i32, Local>) -> Data {
+        Data::Placeholder {
+            last_geometry: geo,
+            initial_placeholder: false,
+        }
+    }
+
+    /// Renders `tree` like the arrangements, with leaves named by their index in `windows`
+    fn tree_shape(tree: &Tree<Data>, node_id: &NodeId, windows: &[NodeId]) -> String {
+        let node = tree.get(node_id).unwrap();
+        match node.data() {
+            Data::Group { orientation, .. } => format!(
+                "{}[{}]",
+                match orientation {
+                    Orientation::Horizontal => "H",
+                    Orientation::Vertical => "V",
+                },
+                node.children()
+                    .iter()
+                    .map(|child| tree_shape(tree, child, windows))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ),
+            _ => windows
+                .iter()
+                .position(|id| id == node_id)
+                .unwrap()
+                .to_string(),
+        }
+    }
+
+    #[test]
+    fn test_apply_arrangement() {
+        let output = Output::new(
+            "test".into(),
+            PhysicalProperties {
+                size: (0, 0).into(),
+                subpixel: Subpixel::Unknown,
+                make: "test".into(),
+                model: "test".into(),
+            },
+        );
+        let mode = Mode {
+            size: (2000, 1000).into(),
+            refresh: 60_000,
+        };
+        output.change_current_state(Some(mode), None, None, Some((0, 0).into()));
+
+        // a manually tiled H[V[0,1],2]
+        let geo = Rectangle::from_loc_and_size((0, 0), (1000, 1000));
+        let mut tree = Tree::new();
+        let root = tree
+            .insert(
+                Node::new(Data::new_group(Orientation::Horizontal, geo)),
+                InsertBehavior::AsRoot,
+            )
+            .unwrap();
+        let group = tree
+            .insert(
+                Node::new(Data::new_group(Orientation::Vertical, geo)),
+                InsertBehavior::UnderNode(&root),
+            )
+            .unwrap();
+        let windows = vec![
+            tree.insert(Node::new(leaf(geo)), InsertBehavior::UnderNode(&group))
+                .unwrap(),
+            tree.insert(Node::new(leaf(geo)), InsertBehavior::UnderNode(&group))
+                .unwrap(),
+            tree.insert(Node::new(leaf(geo)), InsertBehavior::UnderNode(&root))
+                .unwrap(),
+        ];
+
+        let algorithm = TilingAlgorithm::MasterStack {
+            masters: 1,
+            ratio: 0.6,
+        };
+        let arrangement = Arrangement::for_algorithm(
+            algorithm,
+            windows.iter().cloned().map(Arrangement::Window).collect(),
+        )
+        .unwrap();
+        arrangement.apply(&mut tree);
+
+        // the old groups are gone, while the windows kept their ids
+        let root = tree.root_node_id().unwrap().clone();
+        assert_eq!(tree_shape(&tree, &root, &windows), "V[0,H[1,2]]");
+        assert_eq!(tree.traverse_pre_order_ids(&root).unwrap().count(), 5);
+        assert!(tree.get(&group).is_err());
+
+        // applying the same shape again keeps the tree as it is
+        let arrangement = Arrangement::for_algorithm(
+            algorithm,
+            windows.iter().cloned().map(Arrangement::Window).collect(),
+        )
+        .unwrap();
+        arrangement.apply(&mut tree);
+        assert_eq!(tree.root_node_id(), Some(&root));
+
+        // the ratios are scaled to the output
+        TilingLayout::update_positions(&output, &mut tree, (0, 0), TilingAlgorithm::Manual);
+        let geometries = windows
+            .iter()
+            .map(|id| *tree.get(id).unwrap().data().geometry())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            geometries,
+            vec![
+                Rectangle::from_loc_and_size((0, 0), (1200, 1000)),
+                Rectangle::from_loc_and_size((1200, 0), (800, 500)),
+                Rectangle::from_loc_and_size((1200, 500), (800, 500)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_spiral_arrangement() {
+        let (windows, shape) = window_ids(4);
+        let arrangement = Arrangement::for_algorithm(TilingAlgorithm::Spiral, windows).unwrap();
+        assert_eq!(shape(&arrangement), "V[0,H[1,V[2,3]]]");
+        assert_ratios(&arrangement, &[0.5, 0.5]);
+
+        let (windows, shape) = window_ids(2);
+        let arrangement = Arrangement::for_algorithm(TilingAlgorithm::Spiral, windows).unwrap();
+        assert_eq!(shape(&arrangement), "V[0,1]");
+    }
+
+    #[test]
+    fn test_columns_arrangement() {
+        // the first columns take the remainder
+        let (windows, shape) = window_ids(5);
+        let arrangement =
+            Arrangement::for_algorithm(TilingAlgorithm::Columns { count: 3 }, windows).unwrap();
+        assert_eq!(shape(&arrangement), "V[H[0,1],H[2,3],4]");
+        assert_ratios(&arrangement, &[1.0 / 3.0; 3]);
+
+        // never more columns than windows
+        let (windows, shape) = window_ids(2);
+        let arrangement =
+            Arrangement::for_algorithm(TilingAlgorithm::Columns { count: 10 }, windows).unwrap();
+        assert_eq!(shape(&arrangement), "V[0,1]");
+
+        // at least one column
+        let (windows, shape) = window_ids(3);
+        let arrangement =
+            Arrangement::for_algorithm(TilingAlgorithm::Columns { count: 0 }, windows).unwrap();
+        assert_eq!(shape(&arrangement), "H[0,1,2]");
+        assert_ratios(&arrangement, &[1.0 / 3.0; 3]);
+    }
+}
@@ -8,12 +8,14 @@ use std::{
     sync::atomic::Ordering,
     time::{Duration, Instant},
 };
+use tracing::debug;
 use wayland_backend::server::ClientId;
 
-use crate::wayland::protocols::workspace::WorkspaceCapabilities;
+use crate::wayland::protocols::workspace::{GroupCapabilities, WorkspaceCapabilities};
 use cosmic_comp_config::{
-    workspace::{WorkspaceLayout, WorkspaceMode},
-    TileBehavior,
+    window_rules::WindowRule,
+    workspace::{NamedWorkspace, TilingAlgorithm, WorkspaceLayout, WorkspaceMode},
+    DecorationsConfig, SwallowingConfig, TileBehavior,
 };
 use cosmic_protocols::workspace::v1::server::zcosmic_workspace_handle_v1::{
     State as WState, TilingState,
@@ -58,6 +60,7 @@ use smithay::wayland::shell::wlr_layer::Layer as WlrLayer;
 use crate::{
     backend::render::animations::spring::{Spring, SpringParams},
     config::Config,
+    ipc,
     utils::{prelude::*, quirks::WORKSPACE_OVERVIEW_NAMESPACE},
     wayland::{
         handlers::{
@@ -84,7 +87,9 @@ pub mod element;
 pub mod focus;
 pub mod grabs;
 pub mod layout;
+pub mod restore;
 mod seats;
+mod swallowing;
 mod workspace;
 pub use self::element::{CosmicMapped, CosmicMappedRenderElement, CosmicSurface};
 pub use self::seats::*;
@@ -92,6 +97,7 @@ pub use self::workspace::*;
 
 use self::{
     element::{
+        capture_indicator::{capture_indicator, CaptureIndicator},
         resize_indicator::{resize_indicator, ResizeIndicator},
         swap_indicator::{swap_indicator, SwapIndicator},
         CosmicWindow, MaximizedState,
@@ -241,11 +247,13 @@ pub struct Shell {
     pub pending_windows: Vec<(CosmicSurface, Seat<State>, Option<Output>)>,
     pub pending_layers: Vec<(LayerSurface, Output, Seat<State>)>,
     pub pending_activations: HashMap<ActivationKey, ActivationContext>,
+    scratchpads: HashMap<Seat<State>, Scratchpad>,
     pub override_redirect_windows: Vec<X11Surface>,
     pub session_lock: Option<SessionLock>,
     pub seats: Seats,
 
     theme: cosmic::Theme,
+    decorations: DecorationsConfig,
     pub active_hint: bool,
     overview_mode: OverviewMode,
     swap_indicator: Option<SwapIndicator>,
@@ -259,12 +267,28 @@ pub struct Shell {
         Output,
     )>,
     resize_indicator: Option<ResizeIndicator>,
+    capture_indicator: Option<CaptureIndicator>,
+    tablet_mode: bool,
     tiling_exceptions: TilingExceptions,
+    window_rules: layout::WindowRules,
+    swallowing: SwallowingConfig,
+    swallowed: Vec<swallowing::SwallowedWindow>,
+    pub session_restore: restore::SessionRestore,
+    pub ipc_events: ipc::EventQueue,
 
     #[cfg(feature = "debug")]
     pub debug_active: bool,
 }
 
+/// Windows a seat moved to its scratchpad
+#[derive(Debug, Default)]
+struct Scratchpad {
+    /// Every window of the scratchpad, the last one is shown next
+    windows: Vec<CosmicMapped>,
+    /// Windows of the scratchpad, that are currently not mapped anywhere
+    hidden: Vec<(MinimizedWindow, Output, Option<WorkspaceHandle>)>,
+}
+
 #[derive(Debug)]
 pub struct SessionLock {
     pub ext_session_lock: ExtSessionLockV1,
@@ -317,20 +341,32 @@ pub struct WorkspaceSet {
     pub group: WorkspaceGroupHandle,
     idx: usize,
     tiling_enabled: bool,
+    tiling_algorithm: TilingAlgorithm,
     output: Output,
     theme: cosmic::Theme,
+    decorations: DecorationsConfig,
     pub sticky_layer: FloatingLayout,
     pub minimized_windows: Vec<MinimizedWindow>,
     pub workspaces: Vec<Workspace>,
 }
 
+const WORKSPACE_CAPABILITIES: [WorkspaceCapabilities; 5] = [
+    WorkspaceCapabilities::Activate,
+    WorkspaceCapabilities::Deactivate,
+    WorkspaceCapabilities::Remove,
+    WorkspaceCapabilities::Rename,
+    WorkspaceCapabilities::SetTilingState,
+];
+
 fn create_workspace(
     state: &mut WorkspaceUpdateGuard<'_, State>,
     output: &Output,
     group_handle: &WorkspaceGroupHandle,
     active: bool,
     tiling: bool,
+    algorithm: TilingAlgorithm,
     theme: cosmic::Theme,
+    decorations: DecorationsConfig,
 ) -> Workspace {
     let workspace_handle = state
         .create_workspace(
@@ -345,11 +381,25 @@ fn create_workspace(
     if active {
         state.add_workspace_state(&workspace_handle, WState::Active);
     }
-    state.set_workspace_capabilities(
-        &workspace_handle,
-        [WorkspaceCapabilities::Activate].into_iter(),
+    state.set_workspace_capabilities(&workspace_handle, WORKSPACE_CAPABILITIES.into_iter());
+    let mut workspace = Workspace::new(
+        workspace_handle,
+        output.clone(),
+        tiling,
+        theme.clone(),
+        decorations.clone(),
     );
-    Workspace::new(workspace_handle, output.clone(), tiling, theme.clone())
+    workspace.tiling_layer.algorithm = algorithm;
+    workspace
+}
+
+/// Whether the workspace is kept, even if it is empty and not active
+fn is_persistent(workspace: &Workspace, named: &[NamedWorkspace]) -> bool {
+    workspace.keep_until_used
+        || workspace
+            .name
+            .as_ref()
+            .is_some_and(|name| named.iter().any(|n| &n.name == name))
 }
 
 fn move_workspace_to_group(
@@ -368,10 +418,8 @@ fn move_workspace_to_group(
             },
         )
         .unwrap();
-    workspace_state.set_workspace_capabilities(
-        &workspace.handle,
-        [WorkspaceCapabilities::Activate].into_iter(),
-    );
+    workspace_state
+        .set_workspace_capabilities(&workspace.handle, WORKSPACE_CAPABILITIES.into_iter());
     for window in workspace.mapped() {
         for (surface, _) in window.windows() {
             toplevel_leave_workspace(&surface, &old_workspace_handle);
@@ -419,9 +467,15 @@ impl WorkspaceSet {
         output: &Output,
         idx: usize,
         tiling_enabled: bool,
+        tiling_algorithm: TilingAlgorithm,
         theme: cosmic::Theme,
+        decorations: DecorationsConfig,
     ) -> WorkspaceSet {
         let group_handle = state.create_workspace_group();
+        state.set_group_capabilities(
+            &group_handle,
+            [GroupCapabilities::CreateWorkspace].into_iter(),
+        );
         let workspaces = {
             let workspace = create_workspace(
                 state,
@@ -429,16 +483,15 @@ impl WorkspaceSet {
                 &group_handle,
                 true,
                 tiling_enabled,
+                tiling_algorithm,
                 theme.clone(),
+                decorations.clone(),
             );
-            workspace_set_idx(state, 1, idx, &workspace.handle);
-            state.set_workspace_capabilities(
-                &workspace.handle,
-                [WorkspaceCapabilities::Activate].into_iter(),
-            );
+            workspace_set_idx(state, 1, idx, &workspace);
+            state.set_workspace_capabilities(&workspace.handle, WORKSPACE_CAPABILITIES.into_iter());
             vec![workspace]
         };
-        let sticky_layer = FloatingLayout::new(theme.clone(), output);
+        let sticky_layer = FloatingLayout::new(theme.clone(), decorations.clone(), output);
 
         WorkspaceSet {
             previously_active: None,
@@ -446,7 +499,9 @@ impl WorkspaceSet {
             group: group_handle,
             idx,
             tiling_enabled,
+            tiling_algorithm,
             theme,
+            decorations,
             sticky_layer,
             minimized_windows: Vec::new(),
             workspaces,
@@ -560,24 +615,30 @@ impl WorkspaceSet {
             &self.group,
             false,
             self.tiling_enabled,
+            self.tiling_algorithm,
             self.theme.clone(),
+            self.decorations.clone(),
         );
         workspace_set_idx(
             state,
             self.workspaces.len() as u8 + 1,
             self.idx,
-            &workspace.handle,
+            &workspace,
             // this method is only used by code paths related to dynamic workspaces, so this should be fine
         );
         self.workspaces.push(workspace);
     }
 
-    fn ensure_last_empty(&mut self, state: &mut WorkspaceUpdateGuard<State>) {
+    fn ensure_last_empty(
+        &mut self,
+        named: &[NamedWorkspace],
+        state: &mut WorkspaceUpdateGuard<State>,
+    ) {
         // add empty at the end, if necessary
         if self
             .workspaces
             .last()
-            .map(|last| !last.is_empty())
+            .map(|last| !last.is_empty() || is_persistent(last, named))
             .unwrap_or(true)
         {
             self.add_empty_workspace(state);
@@ -587,7 +648,11 @@ impl WorkspaceSet {
         let len = self.workspaces.len();
         let mut keep = vec![true; len];
         for (i, workspace) in self.workspaces.iter().enumerate() {
-            if workspace.is_empty() && i != self.active && i != len - 1 {
+            if workspace.is_empty()
+                && !is_persistent(workspace, named)
+                && i != self.active
+                && i != len - 1
+            {
                 state.remove_workspace(workspace.handle);
                 keep[i] = false;
             }
@@ -603,15 +668,87 @@ impl WorkspaceSet {
 
         if keep.iter().any(|val| *val == false) {
             for (i, workspace) in self.workspaces.iter().enumerate() {
-                workspace_set_idx(state, i as u8 + 1, self.idx, &workspace.handle);
+                workspace_set_idx(state, i as u8 + 1, self.idx, workspace);
+            }
+        }
+    }
+
+    fn add_named_workspace(
+        &mut self,
+        named: &NamedWorkspace,
+        pin: bool,
+        state: &mut WorkspaceUpdateGuard<'_, State>,
+    ) {
+        let mut workspace = create_workspace(
+            state,
+            &self.output,
+            &self.group,
+            false,
+            named.tiling.unwrap_or(self.tiling_enabled),
+            named.algorithm.unwrap_or(self.tiling_algorithm),
+            self.theme.clone(),
+            self.decorations.clone(),
+        );
+        workspace.name = Some(named.name.clone());
+        workspace.tiling_layer.default_orientation =
+            named.orientation.map(layout::orientation_from_config);
+        // remember the pinned output, so the workspace moves back once it is connected
+        if let Some(output) = named.output.as_ref().filter(|_| pin) {
+            if output != &self.output.name() {
+                workspace.output_stack.push_front(output.clone());
             }
         }
+
+        // named workspaces are kept in front of dynamic ones in the order they are declared
+        let idx = self
+            .workspaces
+            .iter()
+            .take_while(|w| w.name.is_some())
+            .count();
+        self.workspaces.insert(idx, workspace);
+        if self.active >= idx {
+            self.active += 1;
+        }
+        self.previously_active = None;
+        self.update_idx(state, self.idx);
+    }
+
+    /// Moves named workspaces in front of dynamic ones, in the order they are declared in `named`
+    fn sort_named(
+        &mut self,
+        named: &[NamedWorkspace],
+        state: &mut WorkspaceUpdateGuard<'_, State>,
+    ) {
+        let order = |workspace: &Workspace| match workspace.name.as_ref() {
+            Some(name) => named
+                .iter()
+                .position(|n| &n.name == name)
+                .unwrap_or(named.len()),
+            None => usize::MAX,
+        };
+        if self
+            .workspaces
+            .windows(2)
+            .all(|pair| order(&pair[0]) <= order(&pair[1]))
+        {
+            return;
+        }
+
+        let active = self.workspaces[self.active].handle;
+        self.workspaces.sort_by_key(order);
+        self.active = self
+            .workspaces
+            .iter()
+            .position(|w| w.handle == active)
+            .unwrap();
+        self.previously_active = None;
+        self.update_idx(state, self.idx);
     }
 
     fn update_idx(&mut self, state: &mut WorkspaceUpdateGuard<'_, State>, idx: usize) {
         self.idx = idx;
         for (i, workspace) in self.workspaces.iter().enumerate() {
-            workspace_set_idx(state, i as u8 + 1, idx, &workspace.handle);
+            workspace_set_idx(state, i as u8 + 1, idx, workspace);
         }
     }
 }
@@ -624,7 +761,10 @@ pub struct Workspaces {
     mode: WorkspaceMode,
     autotile: bool,
     autotile_behavior: TileBehavior,
+    tiling_algorithm: TilingAlgorithm,
+    named: Vec<NamedWorkspace>,
     theme: cosmic::Theme,
+    decorations: DecorationsConfig,
 }
 
 impl Workspaces {
@@ -636,7 +776,10 @@ impl Workspaces {
             mode: config.cosmic_conf.workspaces.workspace_mode,
             autotile: config.cosmic_conf.autotile,
             autotile_behavior: config.cosmic_conf.autotile_behavior,
+            tiling_algorithm: config.cosmic_conf.workspaces.tiling_algorithm,
+            named: config.cosmic_conf.workspaces.named_workspaces.clone(),
             theme,
+            decorations: config.cosmic_conf.decorations.clone(),
         }
     }
 
@@ -663,7 +806,9 @@ impl Workspaces {
                     &output,
                     self.sets.len(),
                     self.autotile,
+                    self.tiling_algorithm,
                     self.theme.clone(),
+                    self.decorations.clone(),
                 )
             });
         workspace_state.add_group_output(&set.group, &output);
@@ -691,12 +836,53 @@ impl Workspaces {
             for (i, workspace) in set.workspaces.iter_mut().enumerate() {
                 workspace.set_output(output);
                 workspace.refresh(xdg_activation_state);
-                workspace_set_idx(workspace_state, i as u8 + 1, set.idx, &workspace.handle);
+                workspace_set_idx(workspace_state, i as u8 + 1, set.idx, workspace);
                 if i == set.active {
                     workspace_state.add_workspace_state(&workspace.handle, WState::Active);
                 }
             }
         }
+
+        self.ensure_named_workspaces(workspace_state);
+    }
+
+    /// Creates missing named workspaces and keeps all of them at their configured position
+    fn ensure_named_workspaces(&mut self, workspace_state: &mut WorkspaceUpdateGuard<'_, State>) {
+        if self.sets.is_empty() {
+            return;
+        }
+
+        for named in &self.named {
+            if self.mode == WorkspaceMode::Global {
+                // every output has its own copy of a named workspace
+                for set in self.sets.values_mut() {
+                    if !set
+                        .workspaces
+                        .iter()
+                        .any(|w| w.name.as_ref() == Some(&named.name))
+                    {
+                        set.add_named_workspace(named, false, workspace_state);
+                    }
+                }
+            } else if !self
+                .sets
+                .values()
+                .flat_map(|set| set.workspaces.iter())
+                .any(|w| w.name.as_ref() == Some(&named.name))
+            {
+                // fall back to the first output, if the pinned one isn't connected
+                let idx = named
+                    .output
+                    .as_ref()
+                    .and_then(|name| self.sets.keys().position(|o| &o.name() == name))
+                    .unwrap_or(0);
+                self.sets[idx].add_named_workspace(named, true, workspace_state);
+            }
+        }
+
+        for set in self.sets.values_mut() {
+            set.sort_named(&self.named, workspace_state);
+        }
     }
 
     pub fn remove_output<'a>(
@@ -755,6 +941,7 @@ impl Workspaces {
                     }
                 }
                 new_set.minimized_windows.extend(set.minimized_windows);
+                new_set.sort_named(&self.named, workspace_state);
 
                 if self.mode == WorkspaceMode::OutputBound {
                     workspace_state.remove_workspace_group(set.group);
@@ -794,7 +981,8 @@ impl Workspaces {
             move_workspace_to_group(&mut workspace, &new_set.group, workspace_state);
             workspace.set_output(to);
             workspace.refresh(xdg_activation_state);
-            new_set.workspaces.insert(new_set.active + 1, workspace)
+            new_set.workspaces.insert(new_set.active + 1, workspace);
+            new_set.sort_named(&self.named, workspace_state);
         }
     }
 
@@ -808,6 +996,73 @@ impl Workspaces {
         self.mode = config.cosmic_conf.workspaces.workspace_mode;
         self.layout = config.cosmic_conf.workspaces.workspace_layout;
 
+        let old_algorithm = self.tiling_algorithm;
+        self.tiling_algorithm = config.cosmic_conf.workspaces.tiling_algorithm;
+        if old_algorithm != self.tiling_algorithm {
+            // workspaces still using the previous default follow the new one
+            for set in self.sets.values_mut() {
+                set.tiling_algorithm = self.tiling_algorithm;
+                for workspace in &mut set.workspaces {
+                    let named = workspace
+                        .name
+                        .as_ref()
+                        .and_then(|name| self.named.iter().find(|n| &n.name == name));
+                    if workspace.tiling_layer.algorithm == old_algorithm
+                        && named.map_or(true, |n| n.algorithm.is_none())
+                    {
+                        workspace.tiling_layer.set_algorithm(self.tiling_algorithm);
+                    }
+                }
+            }
+        }
+
+        if self.named != config.cosmic_conf.workspaces.named_workspaces {
+            let old_named = std::mem::replace(
+                &mut self.named,
+                config.cosmic_conf.workspaces.named_workspaces.clone(),
+            );
+            // an entry replaced by a new name at the same position is a rename
+            let renamed = old_named
+                .iter()
+                .zip(self.named.iter())
+                .filter(|(old, new)| {
+                    !self.named.iter().any(|n| n.name == old.name)
+                        && !old_named.iter().any(|n| n.name == new.name)
+                })
+                .map(|(old, new)| (old.name.clone(), new.name.clone()))
+                .collect::<Vec<_>>();
+            // workspaces removed from the config become regular dynamic workspaces
+            for set in self.sets.values_mut() {
+                for workspace in &mut set.workspaces {
+                    if let Some((_, new)) = renamed
+                        .iter()
+                        .find(|(old, _)| workspace.name.as_ref() == Some(old))
+                    {
+                        workspace.name = Some(new.clone());
+                    }
+                    if workspace.name.as_ref().is_some_and(|name| {
+                        old_named.iter().any(|n| &n.name == name)
+                            && !self.named.iter().any(|n| &n.name == name)
+                    }) {
+                        workspace.name = None;
+                    }
+                    if let Some(named) = workspace
+                        .name
+                        .as_ref()
+                        .and_then(|name| self.named.iter().find(|n| &n.name == name))
+                    {
+                        workspace
+                            .tiling_layer
+                            .set_algorithm(named.algorithm.unwrap_or(self.tiling_algorithm));
+                        workspace.tiling_layer.default_orientation =
+                            named.orientation.map(layout::orientation_from_config);
+                    }
+                }
+                set.update_idx(workspace_state, set.idx);
+            }
+            self.ensure_named_workspaces(workspace_state);
+        }
+
         if self.sets.len() <= 1 {
             return;
         }
@@ -852,7 +1107,9 @@ impl Workspaces {
                                     &set.group,
                                     false,
                                     config.cosmic_conf.autotile,
+                                    self.tiling_algorithm,
                                     self.theme.clone(),
+                                    self.decorations.clone(),
                                 ),
                             );
                         }
@@ -863,6 +1120,8 @@ impl Workspaces {
                 for (i, set) in self.sets.values_mut().enumerate() {
                     set.update_idx(workspace_state, i);
                 }
+                // named workspaces only existed on a single output so far
+                self.ensure_named_workspaces(workspace_state);
             }
             _ => {}
         };
@@ -870,6 +1129,158 @@ impl Workspaces {
         self.refresh(workspace_state, xdg_activation_state)
     }
 
+    /// Creates a named workspace in the group, or on every output when workspaces span all outputs.
+    ///
+    /// Unlike workspaces declared in the config, these are not persisted and once they were used,
+    /// they get cleaned up like any other dynamic workspace.
+    pub fn create_named(
+        &mut self,
+        group: &WorkspaceGroupHandle,
+        name: String,
+        workspace_state: &mut WorkspaceUpdateGuard<'_, State>,
+    ) -> bool {
+        if name.is_empty() || self.spaces().any(|w| w.name.as_ref() == Some(&name)) {
+            return false;
+        }
+        let Some(set_idx) = self.sets.values().position(|set| &set.group == group) else {
+            return false;
+        };
+
+        let named = NamedWorkspace {
+            name,
+            output: None,
+            tiling: None,
+            orientation: None,
+            algorithm: None,
+        };
+        match self.mode {
+            WorkspaceMode::OutputBound => {
+                self.sets[set_idx].add_named_workspace(&named, true, workspace_state);
+            }
+            WorkspaceMode::Global => {
+                for set in self.sets.values_mut() {
+                    set.add_named_workspace(&named, false, workspace_state);
+                }
+            }
+        }
+        for workspace in self
+            .sets
+            .values_mut()
+            .flat_map(|set| set.workspaces.iter_mut())
+            .filter(|w| w.name.as_ref() == Some(&named.name))
+        {
+            workspace.keep_until_used = true;
+        }
+        true
+    }
+
+    /// Returns the outputs and index of a workspace, that may be removed.
+    ///
+    /// Workspaces named in the config and the last workspace of an output can't be removed.
+    fn removable(&self, handle: &WorkspaceHandle) -> Option<(Vec<Output>, usize)> {
+        let (output, idx) = self.sets.iter().find_map(|(output, set)| {
+            set.workspaces
+                .iter()
+                .position(|w| &w.handle == handle)
+                .map(|idx| (output.clone(), idx))
+        })?;
+        let workspace = &self.sets[&output].workspaces[idx];
+        if workspace
+            .name
+            .as_ref()
+            .is_some_and(|name| self.named.iter().any(|n| &n.name == name))
+        {
+            return None;
+        }
+
+        let outputs = match self.mode {
+            WorkspaceMode::OutputBound => vec![output],
+            WorkspaceMode::Global => self.sets.keys().cloned().collect(),
+        };
+        if outputs.iter().any(|output| self.len(output) <= 1) {
+            return None;
+        }
+        Some((outputs, idx))
+    }
+
+    /// Removes a workspace, moving its minimized windows to the neighbouring workspace.
+    ///
+    /// Mapped windows have to be moved beforehand, see [`Shell::remove_workspace`].
+    fn remove(
+        &mut self,
+        handle: &WorkspaceHandle,
+        workspace_state: &mut WorkspaceUpdateGuard<'_, State>,
+    ) -> bool {
+        let Some((outputs, idx)) = self.removable(handle) else {
+            return false;
+        };
+        let target = if idx > 0 { idx - 1 } else { idx };
+
+        for output in outputs {
+            let set = self.sets.get_mut(&output).unwrap();
+            let mut workspace = set.workspaces.remove(idx);
+            for window in workspace.minimized_windows.iter() {
+                for (surface, _) in window.window.windows() {
+                    toplevel_leave_workspace(&surface, &workspace.handle);
+                    toplevel_enter_workspace(&surface, &set.workspaces[target].handle);
+                }
+            }
+            set.workspaces[target]
+                .minimized_windows
+                .append(&mut workspace.minimized_windows);
+            workspace_state.remove_workspace(workspace.handle);
+
+            if set.active == idx {
+                set.active = target;
+                workspace_state.add_workspace_state(&set.workspaces[target].handle, WState::Active);
+            } else if set.active > idx {
+                set.active -= 1;
+            }
+            set.previously_active = None;
+            set.update_idx(workspace_state, set.idx);
+        }
+
+        true
+    }
+
+    /// Renames a workspace, an empty name turns it back into a regular dynamic workspace.
+    pub fn rename(
+        &mut self,
+        handle: &WorkspaceHandle,
+        name: String,
+        workspace_state: &mut WorkspaceUpdateGuard<'_, State>,
+    ) -> bool {
+        let Some((set_idx, idx)) = self.sets.values().enumerate().find_map(|(i, set)| {
+            set.workspaces
+                .iter()
+                .position(|w| &w.handle == handle)
+                .map(|idx| (i, idx))
+        }) else {
+            return false;
+        };
+        let sets = match self.mode {
+            WorkspaceMode::OutputBound => set_idx..set_idx + 1,
+            WorkspaceMode::Global => 0..self.sets.len(),
+        };
+        if !name.is_empty()
+            && self.sets[sets.clone()].values().any(|set| {
+                set.workspaces
+                    .iter()
+                    .enumerate()
+                    .any(|(i, w)| i != idx && w.name.as_ref() == Some(&name))
+            })
+        {
+            return false;
+        }
+
+        let name = (!name.is_empty()).then_some(name);
+        for set in self.sets[sets].values_mut() {
+            set.workspaces[idx].name = name.clone();
+            set.update_idx(workspace_state, set.idx);
+        }
+        true
+    }
+
     pub fn recalculate(&mut self) {
         for set in self.sets.values_mut() {
             set.sticky_layer.recalculate();
@@ -882,6 +1293,15 @@ impl Workspaces {
         workspace_state: &mut WorkspaceUpdateGuard<'_, State>,
         xdg_activation_state: &XdgActivationState,
     ) {
+        for workspace in self
+            .sets
+            .values_mut()
+            .flat_map(|set| set.workspaces.iter_mut())
+            .filter(|w| !w.is_empty())
+        {
+            workspace.keep_until_used = false;
+        }
+
         match self.mode {
             WorkspaceMode::Global => {
                 let Some(max) = self.sets.values().map(|set| set.workspaces.len()).max() else {
@@ -903,7 +1323,7 @@ impl Workspaces {
                     .sets
                     .values()
                     .flat_map(|set| set.workspaces.last())
-                    .any(|w| w.mapped().next().is_some())
+                    .any(|w| w.mapped().next().is_some() || is_persistent(w, &self.named))
                 {
                     for set in self.sets.values_mut() {
                         set.add_empty_workspace(workspace_state);
@@ -915,9 +1335,11 @@ impl Workspaces {
                 let mut active = self.sets[0].active;
                 let mut keep = vec![true; len];
                 for i in 0..len {
-                    let has_windows = self.sets.values().any(|s| !s.workspaces[i].is_empty());
+                    let keep_alive = self.sets.values().any(|s| {
+                        !s.workspaces[i].is_empty() || is_persistent(&s.workspaces[i], &self.named)
+                    });
 
-                    if !has_windows && i != active && i != len - 1 {
+                    if !keep_alive && i != active && i != len - 1 {
                         for workspace in self.sets.values().map(|s| &s.workspaces[i]) {
                             workspace_state.remove_workspace(workspace.handle);
                         }
@@ -937,19 +1359,14 @@ impl Workspaces {
                 if keep.iter().any(|val| *val == false) {
                     for set in self.sets.values_mut() {
                         for (i, workspace) in set.workspaces.iter().enumerate() {
-                            workspace_set_idx(
-                                workspace_state,
-                                i as u8 + 1,
-                                set.idx,
-                                &workspace.handle,
-                            );
+                            workspace_set_idx(workspace_state, i as u8 + 1, set.idx, workspace);
                         }
                     }
                 }
             }
             WorkspaceMode::OutputBound => {
                 for set in self.sets.values_mut() {
-                    set.ensure_last_empty(workspace_state);
+                    set.ensure_last_empty(&self.named, workspace_state);
                 }
             }
         }
@@ -965,6 +1382,31 @@ impl Workspaces {
             .and_then(|set| set.workspaces.get(num))
     }
 
+    /// Returns the workspace at `num` on `output`, appending empty workspaces up to it if necessary
+    pub fn get_or_create(
+        &mut self,
+        num: usize,
+        output: &Output,
+        workspace_state: &mut WorkspaceUpdateGuard<'_, State>,
+    ) -> Option<&Workspace> {
+        let len = self.sets.get(output)?.workspaces.len();
+        if num >= len {
+            let sets = match self.mode {
+                WorkspaceMode::OutputBound => {
+                    let idx = self.sets.get_index_of(output).unwrap();
+                    idx..idx + 1
+                }
+                WorkspaceMode::Global => 0..self.sets.len(),
+            };
+            for set in self.sets[sets].values_mut() {
+                while set.workspaces.len() <= num {
+                    set.add_empty_workspace(workspace_state);
+                }
+            }
+        }
+        self.get(num, output)
+    }
+
     pub fn get_mut(&mut self, num: usize, output: &Output) -> Option<&mut Workspace> {
         self.sets
             .get_mut(output)
@@ -1060,6 +1502,42 @@ impl Workspaces {
         self.force_redraw(xdg_activation_state);
     }
 
+    pub fn set_decorations(
+        &mut self,
+        decorations: DecorationsConfig,
+        xdg_activation_state: &XdgActivationState,
+    ) {
+        self.decorations = decorations.clone();
+        for (_, s) in &mut self.sets {
+            s.decorations = decorations.clone();
+
+            s.sticky_layer.decorations = decorations.clone();
+            s.sticky_layer.mapped().for_each(|m| {
+                m.update_decorations(decorations.clone());
+            });
+            s.minimized_windows.iter().for_each(|m| {
+                m.window.update_decorations(decorations.clone());
+            });
+
+            for w in &mut s.workspaces {
+                w.tiling_layer.decorations = decorations.clone();
+                w.scrolling_layer.decorations = decorations.clone();
+                w.floating_layer.decorations = decorations.clone();
+
+                w.mapped().for_each(|m| {
+                    m.update_decorations(decorations.clone());
+                });
+                w.minimized_windows.iter().for_each(|m| {
+                    m.window.update_decorations(decorations.clone());
+                });
+            }
+        }
+
+        // header heights might have changed
+        self.recalculate();
+        self.force_redraw(xdg_activation_state);
+    }
+
     pub fn force_redraw(&mut self, xdg_activation_state: &XdgActivationState) {
         for (_, s) in &mut self.sets {
             s.sticky_layer.mapped().for_each(|m| {
@@ -1134,9 +1612,19 @@ impl Common {
             &mut self.workspace_state.update(),
             &self.xdg_activation_state,
         );
+        if let Some(indicator) = shell.capture_indicator.as_ref() {
+            indicator.output_enter(output, indicator.bbox());
+        }
 
         std::mem::drop(shell);
         self.refresh(); // fixes indicies of any moved workspaces
+
+        let shell = self.shell.read().unwrap();
+        if shell.ipc_events.is_active() {
+            shell.ipc_events.publish(ipc::Event::OutputAdded {
+                output: ipc::output_info(&shell, output),
+            });
+        }
     }
 
     pub fn remove_output(&mut self, output: &Output) {
@@ -1148,9 +1636,20 @@ impl Common {
             &mut self.workspace_state.update(),
             &self.xdg_activation_state,
         );
+        if let Some(indicator) = shell_ref.capture_indicator.as_ref() {
+            indicator.output_leave(output);
+        }
 
         std::mem::drop(shell);
         self.refresh(); // cleans up excess of workspaces and empty workspaces
+
+        self.shell
+            .read()
+            .unwrap()
+            .ipc_events
+            .publish(ipc::Event::OutputRemoved {
+                name: output.name(),
+            });
     }
 
     pub fn migrate_workspace(&mut self, from: &Output, to: &Output, handle: &WorkspaceHandle) {
@@ -1237,6 +1736,7 @@ impl Shell {
         let theme = cosmic::theme::system_preference();
 
         let tiling_exceptions = layout::TilingExceptions::new(config.tiling_exceptions.iter());
+        let window_rules = layout::WindowRules::new(config.cosmic_conf.window_rules.iter());
 
         Shell {
             workspaces: Workspaces::new(config, theme.clone()),
@@ -1245,17 +1745,26 @@ impl Shell {
             pending_windows: Vec::new(),
             pending_layers: Vec::new(),
             pending_activations: HashMap::new(),
+            scratchpads: HashMap::new(),
             override_redirect_windows: Vec::new(),
             session_lock: None,
 
             theme,
+            decorations: config.cosmic_conf.decorations.clone(),
             active_hint: config.cosmic_conf.active_hint,
             overview_mode: OverviewMode::None,
             swap_indicator: None,
             resize_mode: ResizeMode::None,
             resize_state: None,
             resize_indicator: None,
+            capture_indicator: None,
+            tablet_mode: false,
             tiling_exceptions,
+            window_rules,
+            swallowing: config.cosmic_conf.swallowing.clone(),
+            swallowed: Vec::new(),
+            session_restore: restore::SessionRestore::new(config.dynamic_conf.session().clone()),
+            ipc_events: ipc::EventQueue::default(),
 
             #[cfg(feature = "debug")]
             debug_active: false,
@@ -1279,6 +1788,10 @@ impl Shell {
                         set.workspaces[set.active].tiling_layer.cleanup_drag();
                     }
                     set.activate(idx, workspace_delta, workspace_state)?;
+                    self.ipc_events.publish(ipc::Event::WorkspaceActivated {
+                        output: output.name(),
+                        index: idx,
+                    });
 
                     let output_geo = output.geometry();
                     Ok(Some(
@@ -1292,12 +1805,66 @@ impl Shell {
             WorkspaceMode::Global => {
                 for set in self.workspaces.sets.values_mut() {
                     set.activate(idx, workspace_delta, workspace_state)?;
+                    self.ipc_events.publish(ipc::Event::WorkspaceActivated {
+                        output: set.output.name(),
+                        index: idx,
+                    });
                 }
                 Ok(None)
             }
         }
     }
 
+    /// Switches away from the workspace, if it is active
+    pub fn deactivate(
+        &mut self,
+        handle: &WorkspaceHandle,
+        workspace_state: &mut WorkspaceUpdateGuard<'_, State>,
+    ) {
+        let Some((output, idx)) = self.workspaces.iter().find_map(|(output, set)| {
+            (set.workspaces[set.active].handle == *handle).then(|| (output.clone(), set.active))
+        }) else {
+            return;
+        };
+        if self.workspaces.len(&output) <= 1 {
+            return;
+        }
+
+        let target = if idx > 0 { idx - 1 } else { idx + 1 };
+        let _ = self.activate(
+            &output,
+            target,
+            WorkspaceDelta::new_shortcut(),
+            workspace_state,
+        );
+    }
+
+    /// Removes a workspace, moving its windows to the neighbouring workspace.
+    ///
+    /// When workspaces span all outputs, the workspace is removed on every output.
+    pub fn remove_workspace(
+        &mut self,
+        handle: &WorkspaceHandle,
+        workspace_state: &mut WorkspaceUpdateGuard<'_, State>,
+    ) -> bool {
+        let Some((outputs, idx)) = self.workspaces.removable(handle) else {
+            return false;
+        };
+        let target = if idx > 0 { idx - 1 } else { idx + 1 };
+
+        for output in outputs {
+            let set = &self.workspaces.sets[&output];
+            let from = set.workspaces[idx].handle;
+            let to = set.workspaces[target].handle;
+            let windows = set.workspaces[idx].mapped().cloned().collect::<Vec<_>>();
+            for window in windows {
+                let _ = self.move_window(None, &window, &from, &to, false, None, workspace_state);
+            }
+        }
+
+        self.workspaces.remove(handle, workspace_state)
+    }
+
     pub fn update_workspace_delta(&mut self, output: &Output, delta: f64) {
         match &mut self.workspaces.mode {
             WorkspaceMode::OutputBound => {
@@ -1809,6 +2376,21 @@ impl Shell {
         })
     }
 
+    /// Whether the device was folded or detached into a tablet, as reported by its tablet-mode switch.
+    ///
+    /// While active, new floating windows are opened maximized.
+    pub fn tablet_mode(&self) -> bool {
+        self.tablet_mode
+    }
+
+    pub fn set_tablet_mode(&mut self, enabled: bool) {
+        if self.tablet_mode != enabled {
+            self.tablet_mode = enabled;
+            self.ipc_events
+                .publish(ipc::Event::TabletMode { active: enabled });
+        }
+    }
+
     pub fn global_space(&self) -> Rectangle<i32, Global> {
         self.outputs()
             .fold(
@@ -1864,6 +2446,8 @@ impl Shell {
                     self.swap_indicator = Some(swap_indicator(evlh, self.theme.clone()));
                 }
                 self.overview_mode = OverviewMode::Started(trigger, Instant::now());
+                self.ipc_events
+                    .publish(ipc::Event::OverviewMode { active: true });
             }
         } else {
             if matches!(
@@ -1882,10 +2466,33 @@ impl Shell {
                     };
                 self.overview_mode =
                     OverviewMode::Ended(trigger, Instant::now() - reverse_duration);
+                self.ipc_events
+                    .publish(ipc::Event::OverviewMode { active: false });
+            }
+        }
+    }
+
+    /// Shows or hides the indicator drawn on all outputs while the screen is being captured
+    pub fn set_capture_indicator(
+        &mut self,
+        active: bool,
+        evlh: &LoopHandle<'static, crate::state::State>,
+    ) {
+        if !active {
+            self.capture_indicator = None;
+        } else if self.capture_indicator.is_none() {
+            let indicator = capture_indicator(evlh.clone(), self.theme.clone());
+            for output in self.outputs() {
+                indicator.output_enter(output, indicator.bbox());
             }
+            self.capture_indicator = Some(indicator);
         }
     }
 
+    pub fn capture_indicator(&self) -> Option<CaptureIndicator> {
+        self.capture_indicator.clone()
+    }
+
     pub fn overview_mode(&self) -> (OverviewMode, Option<SwapIndicator>) {
         if let OverviewMode::Started(trigger, timestamp) = &self.overview_mode {
             if Instant::now().duration_since(*timestamp) > ANIMATION_DURATION {
@@ -1916,6 +2523,8 @@ impl Shell {
                 *old_direction = direction;
             } else {
                 self.resize_mode = ResizeMode::Started(pattern, Instant::now(), direction);
+                self.ipc_events
+                    .publish(ipc::Event::ResizeMode { active: true });
             }
             self.resize_indicator = Some(resize_indicator(
                 direction,
@@ -1926,6 +2535,8 @@ impl Shell {
         } else {
             if let Some(direction) = self.resize_mode.active_direction() {
                 self.resize_mode = ResizeMode::Ended(Instant::now(), direction);
+                self.ipc_events
+                    .publish(ipc::Event::ResizeMode { active: false });
                 if let Some((_, direction, edge, _, _, _)) = self.resize_state.as_ref() {
                     self.finish_resize(*direction, *edge);
                 }
@@ -1951,23 +2562,76 @@ impl Shell {
         (self.resize_mode.clone(), self.resize_indicator.clone())
     }
 
+    /// Geometry of the window or stack on `output`, that a window moved from `layer` would be stacked onto
     pub fn stacking_indicator(
         &self,
         output: &Output,
         layer: ManagedLayer,
+        location: Point<f64, Global>,
     ) -> Option<Rectangle<i32, Local>> {
-        match layer {
-            ManagedLayer::Sticky => self
-                .workspaces
-                .sets
-                .get(output)
-                .and_then(|set| set.sticky_layer.stacking_indicator()),
-            ManagedLayer::Floating => self
-                .active_space(output)
+        let location = location.to_local(output);
+        let workspace = self.active_space(output);
+        self.workspaces
+            .sets
+            .get(output)
+            .and_then(|set| set.sticky_layer.header_under(location))
+            .or_else(|| workspace.floating_layer.header_under(location))
+            .or_else(|| workspace.scrolling_layer.header_under(location))
+            .map(|(_, geo)| geo)
+            .or_else(|| match layer {
+                ManagedLayer::Tiling => workspace.tiling_layer.stacking_indicator(),
+                _ => workspace
+                    .tiling_layer
+                    .header_under(location)
+                    .map(|(_, geo)| geo),
+            })
+    }
+
+    /// Stacks a window moved from `layer` onto a window or stack, whose header is under `location`.
+    ///
+    /// Drops of tiled windows onto tiled windows are handled by the tiling layer itself.
+    pub fn stack_dropped_window(
+        &mut self,
+        window: &CosmicMapped,
+        output: &Output,
+        layer: ManagedLayer,
+        location: Point<f64, Global>,
+    ) -> Option<(CosmicMapped, Point<i32, Global>)> {
+        let location = location.to_local(output);
+        let set = self.workspaces.sets.get_mut(output)?;
+        let res = if let Some((target, _)) = set
+            .sticky_layer
+            .header_under(location)
+            .filter(|(target, _)| target != window)
+        {
+            set.sticky_layer.stack_window(&target, window)
+        } else {
+            let workspace = self.active_space_mut(output);
+            if let Some((target, _)) = workspace
                 .floating_layer
-                .stacking_indicator(),
-            ManagedLayer::Tiling => self.active_space(output).tiling_layer.stacking_indicator(),
+                .header_under(location)
+                .filter(|(target, _)| target != window)
+            {
+                workspace.floating_layer.stack_window(&target, window)
+            } else if let Some((target, _)) = workspace
+                .scrolling_layer
+                .header_under(location)
+                .filter(|(target, _)| target != window)
+            {
+                workspace.scrolling_layer.stack_window(&target, window)
+            } else if layer != ManagedLayer::Tiling {
+                let (target, _) = workspace.tiling_layer.header_under(location)?;
+                workspace.tiling_layer.stack_window(&target, window)
+            } else {
+                None
+            }
+        };
+
+        if res.is_some() && layer == ManagedLayer::Tiling {
+            // remove the placeholder of the dragged window
+            self.active_space_mut(output).tiling_layer.cleanup_drag();
         }
+        res.map(|(mapped, location)| (mapped, location.to_global(output)))
     }
 
     fn refresh(
@@ -2070,9 +2734,7 @@ impl Shell {
                     .map(mapped, None)
             }
             ManagedLayer::Tiling if new_workspace.tiling_enabled => {
-                new_workspace
-                    .tiling_layer
-                    .map(mapped, Option::<std::iter::Empty<_>>::None, None)
+                new_workspace.map_tiled(mapped, None, None)
             }
             _ => new_workspace.floating_layer.map(mapped, None),
         };
@@ -2117,11 +2779,57 @@ impl Shell {
             _ => None,
         };
 
-        let should_be_fullscreen = output.is_some();
-        let mut output = output.unwrap_or_else(|| seat.active_output());
+        let rule = self.window_rules.matching(&window);
+        if let Some(opacity) = rule.opacity {
+            window.set_opacity(opacity);
+        }
+
+        let should_be_fullscreen = rule.fullscreen.unwrap_or(output.is_some());
+        let mut output = output
+            .or_else(|| {
+                rule.output
+                    .as_ref()
+                    .and_then(|name| self.outputs().find(|o| &o.name() == name).cloned())
+            })
+            .unwrap_or_else(|| seat.active_output());
+        let rule_workspace = rule
+            .workspace
+            .and_then(|num| num.checked_sub(1))
+            .and_then(|idx| {
+                self.workspaces
+                    .get_or_create(idx as usize, &output, &mut workspace_state.update())
+            })
+            .map(|workspace| workspace.handle);
+        let restored = self.session_restore.take(&window);
+        let restored_workspace = restored.as_ref().and_then(|restored| {
+            let (output_name, idx, name) = self.session_restore.workspace(restored);
+            let workspace = match name {
+                Some(name) => self
+                    .workspaces
+                    .spaces()
+                    .find(|workspace| workspace.name.as_deref() == Some(name)),
+                None => {
+                    let output = self.outputs().find(|o| o.name() == output_name).cloned();
+                    output.and_then(|o| {
+                        self.workspaces
+                            .get_or_create(idx, &o, &mut workspace_state.update())
+                    })
+                }
+            };
+            if workspace.is_none() {
+                debug!(
+                    output_name,
+                    idx,
+                    ?name,
+                    "Saved workspace doesn't exist anymore"
+                );
+            }
+            workspace.map(|workspace| workspace.handle)
+        });
+        let target_handle = workspace_handle.or(rule_workspace).or(restored_workspace);
 
         // this is beyond stupid, just to make the borrow checker happy
-        let workspace = if let Some(handle) = workspace_handle.filter(|handle| {
+        let workspace = if let Some(handle) = target_handle.filter(|handle| {
             self.workspaces
                 .spaces()
                 .any(|space| &space.handle == handle)
@@ -2150,7 +2858,7 @@ impl Shell {
         };
 
         let active_handle = self.active_space(&output).handle;
-        let workspace = if let Some(handle) = workspace_handle.filter(|handle| {
+        let workspace = if let Some(handle) = target_handle.filter(|handle| {
             self.workspaces
                 .spaces()
                 .any(|space| &space.handle == handle)
@@ -2175,17 +2883,26 @@ impl Shell {
             && (workspace_output != seat.active_output() || active_handle != workspace.handle);
         let workspace_handle = workspace.handle;
         let is_dialog = layout::is_dialog(&window);
-        let floating_exception = layout::has_floating_exception(&self.tiling_exceptions, &window);
+        let floating_exception = rule.floating.unwrap_or_else(|| match restored.as_ref() {
+            Some(restored) => self.session_restore.is_floating(restored),
+            None => layout::has_floating_exception(&self.tiling_exceptions, &window),
+        });
+        let is_dialog = is_dialog && rule.floating != Some(false);
 
         let maybe_focused = workspace.focus_stack.get(&seat).iter().next().cloned();
         if let Some(focused) = maybe_focused {
             if (focused.is_stack() && !is_dialog && !should_be_fullscreen)
                 && !(workspace.is_tiled(&focused) && floating_exception)
+                && restored.is_none()
             {
-                focused.stack_ref().unwrap().add_window(window, None);
+                focused
+                    .stack_ref()
+                    .unwrap()
+                    .add_window(window.clone(), None);
                 if was_activated {
                     workspace_state.add_workspace_state(&workspace_handle, WState::Urgent);
                 }
+                self.publish_window_mapped(&window);
                 return None;
             }
         }
@@ -2194,15 +2911,65 @@ impl Shell {
             window.clone(),
             evlh.clone(),
             self.theme.clone(),
+            self.decorations.clone(),
         ));
         #[cfg(feature = "debug")]
         {
             mapped.set_debug(self.debug_active);
         }
 
+        // windows, that were stacked in the previous session, join the stack again
+        let restacked = restored
+            .as_ref()
+            .and_then(|restored| self.session_restore.stack_target(restored))
+            .and_then(|target| {
+                if workspace.is_tiled(&target) {
+                    workspace.tiling_layer.stack_window(&target, &mapped)
+                } else if workspace.is_floating(&target) {
+                    workspace.floating_layer.stack_window(&target, &mapped)
+                } else {
+                    None
+                }
+            });
+        if let Some((stack, _)) = restacked {
+            self.session_restore
+                .restored(restored.as_ref().unwrap(), &stack);
+            if was_activated {
+                workspace_state.add_workspace_state(&workspace_handle, WState::Urgent);
+            }
+            self.publish_window_mapped(&window);
+            return None;
+        }
+
         let workspace_empty = workspace.mapped().next().is_none();
+        // floating windows are hard to move and resize by touch, so open them maximized in tablet mode
+        let tablet_maximize =
+            self.tablet_mode && !is_dialog && (floating_exception || !workspace.tiling_enabled);
         if is_dialog || floating_exception || !workspace.tiling_enabled {
-            workspace.floating_layer.map(mapped.clone(), None);
+            let geometry = rule
+                .geometry
+                .map(|geometry| {
+                    (
+                        (geometry.x, geometry.y).into(),
+                        (geometry.width, geometry.height).into(),
+                    )
+                })
+                .or_else(|| {
+                    restored
+                        .as_ref()
+                        .and_then(|restored| self.session_restore.floating_geometry(restored))
+                        .map(|geometry| (geometry.loc, geometry.size.as_logical()))
+                });
+            if let Some((position, size)) = geometry {
+                workspace.floating_layer.map_internal(
+                    mapped.clone(),
+                    Some(position),
+                    Some(size),
+                    None,
+                );
+            } else {
+                workspace.floating_layer.map(mapped.clone(), None);
+            }
         } else {
             for mapped in workspace
                 .mapped()
@@ -2213,10 +2980,42 @@ impl Shell {
             {
                 workspace.unmaximize_request(&mapped);
             }
-            let focus_stack = workspace.focus_stack.get(&seat);
-            workspace
-                .tiling_layer
-                .map(mapped.clone(), Some(focus_stack.iter()), None);
+            let terminal = restored
+                .is_none()
+                .then(|| swallowing::swallow_target(&self.swallowing, &window, workspace))
+                .flatten();
+            if let Some(terminal) = terminal {
+                // hide the terminal, until its child goes away
+                if !workspace.scrolling_layer.replace_window(&terminal, &mapped) {
+                    workspace.tiling_layer.replace_window(&terminal, &mapped);
+                }
+                workspace.focus_stack.get_mut(&seat).remove(&terminal);
+                self.swallowed.push(swallowing::SwallowedWindow {
+                    child: window.clone(),
+                    terminal,
+                    workspace: workspace.handle,
+                });
+            } else {
+                let hint = restored
+                    .as_ref()
+                    .and_then(|restored| self.session_restore.tiling_hint(restored));
+                let restored_next_to = hint.is_some_and(|hint| {
+                    workspace.tiling_layer.map_next_to(
+                        mapped.clone(),
+                        &hint.neighbor,
+                        hint.depth,
+                        hint.orientation,
+                        hint.after,
+                        &hint.ratios,
+                    )
+                });
+                if !restored_next_to {
+                    workspace.map_tiled(mapped.clone(), Some(&seat), None);
+                }
+            }
+        }
+        if let Some(restored) = restored.as_ref() {
+            self.session_restore.restored(restored, &mapped);
         }
 
         if !parent_is_sticky && should_be_fullscreen {
@@ -2225,12 +3024,17 @@ impl Shell {
             workspace.fullscreen_request(&mapped.active_window(), None, from, &seat);
         }
 
-        if parent_is_sticky {
+        if !should_be_fullscreen && rule.maximized.unwrap_or(tablet_maximize) {
+            self.maximize_request(&mapped, &seat);
+        }
+
+        if parent_is_sticky || rule.sticky == Some(true) {
             self.toggle_sticky(&seat, &mapped);
         }
 
-        let new_target = if (workspace_output == seat.active_output()
-            && active_handle == workspace_handle)
+        let new_target = if rule.no_focus == Some(true) {
+            None
+        } else if (workspace_output == seat.active_output() && active_handle == workspace_handle)
             || parent_is_sticky
         {
             // TODO: enforce focus stealing prevention by also checking the same rules as for the else case.
@@ -2248,9 +3052,18 @@ impl Shell {
             self.update_reactive_popups(mapped);
         }
 
+        self.publish_window_mapped(&window);
         new_target
     }
 
+    fn publish_window_mapped(&self, window: &CosmicSurface) {
+        if self.ipc_events.is_active() {
+            if let Some(window) = ipc::find_window_info(self, window) {
+                self.ipc_events.publish(ipc::Event::WindowMapped { window });
+            }
+        }
+    }
+
     pub fn map_override_redirect(&mut self, window: X11Surface) {
         let geo = window.geometry();
         for (output, overlap) in self.outputs().cloned().filter_map(|o| {
@@ -2302,6 +3115,70 @@ impl Shell {
     ) where
         CosmicSurface: PartialEq<S>,
     {
+        if let Some(idx) = self
+            .swallowed
+            .iter()
+            .position(|swallowed| swallowed.terminal.windows().any(|(s, _)| &s == surface))
+        {
+            // the terminal went away, while it was hidden by its child
+            let surface = self.swallowed.remove(idx).terminal.active_window();
+            self.ipc_events.publish(ipc::Event::WindowUnmapped {
+                id: ipc::window_id(&surface),
+                app_id: surface.app_id(),
+                title: surface.title(),
+            });
+            toplevel_info.remove_toplevel(&surface);
+            remove_foreign_toplevel(&surface, foreign_toplevel_list);
+            self.pending_windows.push((surface, seat.clone(), None));
+            return;
+        }
+
+        // a window launched from a terminal went away, so the terminal comes back
+        let mut swallowed = self
+            .swallowed
+            .iter()
+            .position(|swallowed| &swallowed.child == surface)
+            .map(|idx| self.swallowed.remove(idx));
+
+        if let Some(scratchpad) = self.scratchpads.values_mut().find(|scratchpad| {
+            scratchpad
+                .hidden
+                .iter()
+                .any(|(m, _, _)| m.window.windows().any(|(s, _)| &s == surface))
+        }) {
+            // the window went away, while it was hidden in the scratchpad
+            let idx = scratchpad
+                .hidden
+                .iter()
+                .position(|(m, _, _)| m.window.windows().any(|(s, _)| &s == surface))
+                .unwrap();
+            let window = &mut scratchpad.hidden[idx].0.window;
+            let surface = if window.is_stack() {
+                let stack = window.stack_ref_mut().unwrap();
+                let idx = stack.surfaces().position(|s| &s == surface);
+                idx.and_then(|idx| stack.remove_idx(idx))
+            } else {
+                let (minimized, _, _) = scratchpad.hidden.remove(idx);
+                scratchpad.windows.retain(|m| m != &minimized.window);
+                Some(minimized.window.active_window())
+            };
+
+            if let Some(surface) = surface {
+                self.ipc_events.publish(ipc::Event::WindowUnmapped {
+                    id: ipc::window_id(&surface),
+                    app_id: surface.app_id(),
+                    title: surface.title(),
+                });
+                toplevel_info.remove_toplevel(&surface);
+                remove_foreign_toplevel(&surface, foreign_toplevel_list);
+                self.pending_windows.push((surface, seat.clone(), None));
+            }
+            if let Some(swallowed) = swallowed {
+                self.restore_swallowed(swallowed, seat);
+            }
+            return;
+        }
+
         for set in self.workspaces.sets.values_mut() {
             let sticky_res = set.sticky_layer.mapped().find_map(|m| {
                 m.windows()
@@ -2339,6 +3216,19 @@ impl Shell {
                     let idx = stack.surfaces().position(|s| &s == surface);
                     idx.and_then(|idx| stack.remove_idx(idx))
                 } else {
+                    if let Some(terminal) = swallowed.as_ref().map(|s| s.terminal.clone()) {
+                        // give the tile back to the terminal
+                        let replaced = if workspace.tiling_layer.mapped().any(|(m, _)| m == &elem) {
+                            workspace.tiling_layer.replace_window(&elem, &terminal);
+                            true
+                        } else {
+                            workspace.scrolling_layer.replace_window(&elem, &terminal)
+                        };
+                        if replaced {
+                            swallowed = None;
+                            workspace.focus_stack.get_mut(seat).append(&terminal);
+                        }
+                    }
                     workspace.unmap(&elem);
                     Some(elem.active_window())
                 }
@@ -2347,12 +3237,50 @@ impl Shell {
             };
 
             if let Some(surface) = surface {
+                self.ipc_events.publish(ipc::Event::WindowUnmapped {
+                    id: ipc::window_id(&surface),
+                    app_id: surface.app_id(),
+                    title: surface.title(),
+                });
                 toplevel_info.remove_toplevel(&surface);
                 remove_foreign_toplevel(&surface, foreign_toplevel_list);
                 self.pending_windows.push((surface, seat.clone(), None));
-                return;
+                break;
             }
         }
+
+        if let Some(swallowed) = swallowed {
+            self.restore_swallowed(swallowed, seat);
+        }
+    }
+
+    /// Maps a hidden terminal again, when it is activated while its child is still around
+    pub fn unswallow(&mut self, terminal: &CosmicSurface, seat: &Seat<State>) {
+        if let Some(idx) = self
+            .swallowed
+            .iter()
+            .position(|swallowed| swallowed.terminal.windows().any(|(s, _)| &s == terminal))
+        {
+            let swallowed = self.swallowed.remove(idx);
+            self.restore_swallowed(swallowed, seat);
+        }
+    }
+
+    /// Maps a terminal again, after the window, that took over its tile, went away
+    fn restore_swallowed(&mut self, swallowed: swallowing::SwallowedWindow, seat: &Seat<State>) {
+        let handle = if self.space_for_handle(&swallowed.workspace).is_some() {
+            swallowed.workspace
+        } else {
+            self.active_space(&seat.active_output()).handle
+        };
+        let Some(workspace) = self.space_for_handle_mut(&handle) else {
+            return;
+        };
+        workspace.map_tiled(swallowed.terminal.clone(), Some(seat), None);
+        workspace
+            .focus_stack
+            .get_mut(seat)
+            .append(&swallowed.terminal);
     }
 
     pub fn element_under(
@@ -2430,15 +3358,10 @@ impl Shell {
 
         let any_seat = seat.unwrap_or(self.seats.last_active()).clone();
         let mut to_workspace = self.workspaces.space_for_handle_mut(to).unwrap(); // checked above
-        let focus_stack = seat.map(|seat| to_workspace.focus_stack.get(&seat));
         if window_state.layer == ManagedLayer::Floating || !to_workspace.tiling_enabled {
             to_workspace.floating_layer.map(mapped.clone(), None);
         } else {
-            to_workspace.tiling_layer.map(
-                mapped.clone(),
-                focus_stack.as_ref().map(|x| x.iter()),
-                direction,
-            );
+            to_workspace.map_tiled(mapped.clone(), seat, direction);
         }
 
         let focus_target = if let Some(f) = window_state.was_fullscreen {
@@ -2753,8 +3676,13 @@ impl Shell {
             .unwrap();
 
         let mapped = if move_out_of_stack {
-            let new_mapped: CosmicMapped =
-                CosmicWindow::new(window.clone(), evlh.clone(), self.theme.clone()).into();
+            let new_mapped: CosmicMapped = CosmicWindow::new(
+                window.clone(),
+                evlh.clone(),
+                self.theme.clone(),
+                self.decorations.clone(),
+            )
+            .into();
             start_data.set_focus(new_mapped.focus_under((0., 0.).into()));
             new_mapped
         } else {
@@ -2810,16 +3738,21 @@ impl Shell {
 
             let layer = if mapped == old_mapped {
                 let was_floating = workspace.floating_layer.unmap(&mapped);
-                let was_tiled = workspace.tiling_layer.unmap_as_placeholder(&mapped);
-                assert!(was_floating.is_some() != was_tiled.is_some());
+                let was_tiled = workspace
+                    .tiling_layer
+                    .unmap_as_placeholder(&mapped)
+                    .is_some()
+                    || workspace.scrolling_layer.unmap(&mapped);
+                assert!(was_floating.is_some() != was_tiled);
                 if was_floating.is_some_and(|size| size != elem_geo.size.as_logical()) {
                     new_size = was_floating;
                 }
-                was_tiled.is_some()
+                was_tiled
             } else {
                 workspace
                     .tiling_layer
                     .mapped()
+                    .chain(workspace.scrolling_layer.mapped())
                     .any(|(m, _)| m == &old_mapped)
             }
             .then_some(ManagedLayer::Tiling)
@@ -2913,6 +3846,7 @@ impl Shell {
             cursor_output,
             active_hint,
             layer,
+            move_out_of_stack,
             release,
             evlh.clone(),
         );
@@ -3027,6 +3961,14 @@ impl Shell {
                 return FocusResult::None;
             }
 
+            if workspace
+                .scrolling_layer
+                .mapped()
+                .any(|(m, _)| m == &focused)
+            {
+                return workspace.scrolling_layer.next_focus(direction, &focused);
+            }
+
             let focus_stack = workspace.focus_stack.get(seat);
             let swap_desc = match overview.active_trigger() {
                 Some(Trigger::KeyboardSwap(_, desc)) => Some(desc.clone()),
@@ -3157,10 +4099,51 @@ impl Shell {
             workspace
                 .floating_layer
                 .move_current_element(direction, seat, ManagedLayer::Floating, theme)
+                .or_else(|| {
+                    workspace
+                        .scrolling_layer
+                        .move_current_element(direction, seat)
+                })
                 .or_else(|| workspace.tiling_layer.move_current_node(direction, seat))
         }
     }
 
+    /// Runs `f` with the focused element and its floating layer, if it is floating
+    pub fn with_focused_floating(
+        &mut self,
+        seat: &Seat<State>,
+        f: impl FnOnce(&mut FloatingLayout, &CosmicMapped),
+    ) {
+        let Some(output) = seat.focused_output() else {
+            return;
+        };
+        let Some(focused) = seat
+            .get_keyboard()
+            .unwrap()
+            .current_focus()
+            .and_then(|target| self.focused_element(&target))
+        else {
+            return;
+        };
+        if focused.is_fullscreen(true) || focused.is_maximized(true) {
+            return;
+        }
+
+        if let Some(set) = self
+            .workspaces
+            .sets
+            .values_mut()
+            .find(|set| set.sticky_layer.mapped().any(|m| m == &focused))
+        {
+            f(&mut set.sticky_layer, &focused);
+        } else {
+            let workspace = self.active_space_mut(&output);
+            if workspace.floating_layer.mapped().any(|m| m == &focused) {
+                f(&mut workspace.floating_layer, &focused);
+            }
+        }
+    }
+
     pub fn menu_resize_request(
         &mut self,
         mapped: &CosmicMapped,
@@ -3268,6 +4251,33 @@ impl Shell {
     }
 
     pub fn minimize_request(&mut self, mapped: &CosmicMapped) {
+        let Some((minimized, output, workspace)) = self.unmap_minimized(mapped) else {
+            return;
+        };
+        match workspace {
+            Some(handle) => self
+                .workspaces
+                .space_for_handle_mut(&handle)
+                .unwrap()
+                .minimized_windows
+                .push(minimized),
+            None => self
+                .workspaces
+                .sets
+                .get_mut(&output)
+                .unwrap()
+                .minimized_windows
+                .push(minimized),
+        }
+    }
+
+    /// Unmaps `mapped` with the minimize animation, returning where it was shown.
+    ///
+    /// The workspace is `None` for sticky windows.
+    fn unmap_minimized(
+        &mut self,
+        mapped: &CosmicMapped,
+    ) -> Option<(MinimizedWindow, Output, Option<WorkspaceHandle>)> {
         if let Some(set) = self
             .workspaces
             .sets
@@ -3276,24 +4286,176 @@ impl Shell {
         {
             let to = minimize_rectangle(&set.output, &mapped.active_window());
             let (window, position) = set.sticky_layer.unmap_minimize(mapped, to).unwrap();
-            set.minimized_windows.push(MinimizedWindow {
-                window,
-                previous_state: MinimizedState::Sticky { position },
-                output_geo: set.output.geometry(),
-                fullscreen: None,
-            });
-        } else if let Some(workspace) = self.workspaces.sets.values_mut().find_map(|set| {
-            set.workspaces
-                .iter_mut()
-                .find(|workspace| workspace.mapped().any(|m| m == mapped))
-        }) {
+            Some((
+                MinimizedWindow {
+                    window,
+                    previous_state: MinimizedState::Sticky { position },
+                    output_geo: set.output.geometry(),
+                    fullscreen: None,
+                },
+                set.output.clone(),
+                None,
+            ))
+        } else {
+            let workspace = self.workspaces.sets.values_mut().find_map(|set| {
+                set.workspaces
+                    .iter_mut()
+                    .find(|workspace| workspace.mapped().any(|m| m == mapped))
+            })?;
             let to = minimize_rectangle(workspace.output(), &mapped.active_window());
-            if let Some(minimized) = workspace.minimize(&mapped, to) {
-                workspace.minimized_windows.push(minimized);
+            let minimized = workspace.minimize(&mapped, to)?;
+            Some((minimized, workspace.output.clone(), Some(workspace.handle)))
+        }
+    }
+
+    /// Removes `mapped` from the minimized windows, returning where it was minimized from.
+    fn take_minimized(
+        &mut self,
+        mapped: &CosmicMapped,
+    ) -> Option<(MinimizedWindow, Output, Option<WorkspaceHandle>)> {
+        if let Some(set) = self
+            .workspaces
+            .sets
+            .values_mut()
+            .find(|set| set.minimized_windows.iter().any(|m| &m.window == mapped))
+        {
+            let idx = set
+                .minimized_windows
+                .iter()
+                .position(|m| &m.window == mapped)
+                .unwrap();
+            Some((set.minimized_windows.remove(idx), set.output.clone(), None))
+        } else {
+            let workspace = self
+                .workspaces
+                .spaces_mut()
+                .find(|w| w.minimized_windows.iter().any(|m| &m.window == mapped))?;
+            let idx = workspace
+                .minimized_windows
+                .iter()
+                .position(|m| &m.window == mapped)
+                .unwrap();
+            Some((
+                workspace.minimized_windows.remove(idx),
+                workspace.output.clone(),
+                Some(workspace.handle),
+            ))
+        }
+    }
+
+    /// Drops seats without input devices, their scratchpads are taken over by the last active seat
+    pub fn remove_empty_seats(&mut self) {
+        for seat in self.seats.remove_empty_seats() {
+            let Some(scratchpad) = self.scratchpads.remove(&seat) else {
+                continue;
+            };
+            let target = self
+                .scratchpads
+                .entry(self.seats.last_active().clone())
+                .or_default();
+            for mapped in scratchpad.windows {
+                if !target.windows.contains(&mapped) {
+                    target.windows.push(mapped);
+                }
             }
+            target.hidden.extend(scratchpad.hidden);
         }
     }
 
+    pub fn move_to_scratchpad(&mut self, mapped: &CosmicMapped, seat: &Seat<State>) {
+        let scratchpad = self.scratchpads.entry(seat.clone()).or_default();
+        if !scratchpad.windows.contains(mapped) {
+            scratchpad.windows.push(mapped.clone());
+        }
+        self.hide_scratchpad_window(mapped, seat);
+    }
+
+    fn hide_scratchpad_window(&mut self, mapped: &CosmicMapped, seat: &Seat<State>) {
+        if self.scratchpads.get(seat).is_some_and(|scratchpad| {
+            scratchpad
+                .hidden
+                .iter()
+                .any(|(m, _, _)| &m.window == mapped)
+        }) {
+            return;
+        }
+        // minimized windows are taken over as they are
+        if let Some(hidden) = self
+            .unmap_minimized(mapped)
+            .or_else(|| self.take_minimized(mapped))
+        {
+            self.scratchpads
+                .entry(seat.clone())
+                .or_default()
+                .hidden
+                .push(hidden);
+        }
+    }
+
+    /// Hides the scratchpad window shown on the active output of `seat`,
+    /// or shows the next hidden one as a centered floating window.
+    pub fn toggle_scratchpad(&mut self, seat: &Seat<State>) -> Option<KeyboardFocusTarget> {
+        let output = seat.active_output();
+        let scratchpad = self.scratchpads.entry(seat.clone()).or_default();
+        scratchpad.windows.retain(|mapped| mapped.alive());
+        scratchpad
+            .hidden
+            .retain(|(minimized, _, _)| minimized.window.alive());
+
+        let workspace = self.workspaces.active(&output).1;
+        let sticky_layer = self
+            .workspaces
+            .sets
+            .get(&output)
+            .map(|set| &set.sticky_layer);
+        if let Some(idx) = scratchpad.windows.iter().rposition(|mapped| {
+            workspace.mapped().any(|m| m == mapped)
+                || sticky_layer.is_some_and(|layer| layer.mapped().any(|m| m == mapped))
+        }) {
+            // move it to the front, so toggling again cycles through the scratchpad
+            let mapped = scratchpad.windows.remove(idx);
+            scratchpad.windows.insert(0, mapped.clone());
+            self.hide_scratchpad_window(&mapped, seat);
+            return None;
+        }
+
+        // shown on another output or workspace, or minimized, hide it first
+        let mapped = scratchpad.windows.last().cloned()?;
+        self.hide_scratchpad_window(&mapped, seat);
+
+        let hidden = &mut self.scratchpads.get_mut(seat).unwrap().hidden;
+        let idx = hidden.iter().position(|(m, _, _)| m.window == mapped)?;
+        let (mut minimized, previous_output, previous_workspace) = hidden.remove(idx);
+        let _ = minimized.unfullscreen();
+
+        let from = minimize_rectangle(&output, &mapped.active_window());
+        let workspace = self.workspaces.active_mut(&output);
+        for (window, _) in mapped.windows() {
+            if let Some(handle) = previous_workspace.as_ref() {
+                toplevel_leave_workspace(&window, handle);
+            }
+            toplevel_enter_workspace(&window, &workspace.handle);
+            if previous_output != output {
+                toplevel_leave_output(&window, &previous_output);
+                toplevel_enter_output(&window, &output);
+            }
+        }
+
+        let size = mapped.geometry().size;
+        let zone = layer_map_for_output(&output)
+            .non_exclusive_zone()
+            .as_local();
+        let position = Point::from((
+            zone.loc.x + (zone.size.w - size.w) / 2,
+            zone.loc.y + (zone.size.h - size.h) / 2,
+        ));
+        workspace
+            .floating_layer
+            .remap_minimized(mapped.clone(), from, position);
+
+        Some(KeyboardFocusTarget::from(mapped))
+    }
+
     pub fn unminimize_request(&mut self, mapped: &CosmicMapped, seat: &Seat<State>) {
         if let Some((set, window)) = self.workspaces.sets.values_mut().find_map(|set| {
             set.minimized_windows
@@ -3650,10 +4812,7 @@ impl Shell {
                 .unwrap_or(ManagedLayer::Floating)
             {
                 ManagedLayer::Tiling if workspace.tiling_enabled => {
-                    let focus_stack = workspace.focus_stack.get(seat);
-                    workspace
-                        .tiling_layer
-                        .map(mapped.clone(), Some(focus_stack.iter()), None);
+                    workspace.map_tiled(mapped.clone(), Some(seat), None);
                 }
                 ManagedLayer::Sticky => unreachable!(),
                 _ => workspace.floating_layer.map(mapped.clone(), geometry.loc),
@@ -3663,6 +4822,24 @@ impl Shell {
         self.append_focus_stack(&mapped, seat);
     }
 
+    /// Keeps a window above other floating windows, tiled windows are floated for that
+    pub fn toggle_always_on_top(&mut self, seat: &Seat<State>, mapped: &CosmicMapped) {
+        let on_top = !mapped.always_on_top.fetch_xor(true, Ordering::SeqCst);
+        if let Some(workspace) = self.space_for_mut(mapped) {
+            if on_top && workspace.is_tiled(mapped) {
+                workspace.toggle_floating_window(seat, mapped);
+            }
+            workspace.floating_layer.raise_always_on_top();
+        } else if let Some(set) = self
+            .workspaces
+            .sets
+            .values_mut()
+            .find(|set| set.sticky_layer.mapped().any(|m| m == mapped))
+        {
+            set.sticky_layer.raise_always_on_top();
+        }
+    }
+
     pub fn toggle_sticky_current(&mut self, seat: &Seat<State>) {
         let set = self.workspaces.sets.get_mut(&seat.active_output()).unwrap();
         let workspace = &mut set.workspaces[set.active];
@@ -3698,6 +4875,9 @@ impl Shell {
         workspace_state: &mut WorkspaceUpdateGuard<'_, State>,
     ) {
         self.theme = theme.clone();
+        if let Some(indicator) = self.capture_indicator.as_ref() {
+            indicator.set_theme(theme.clone());
+        }
         self.refresh(xdg_activation_state, workspace_state);
         self.workspaces
             .set_theme(theme.clone(), xdg_activation_state);
@@ -3714,6 +4894,32 @@ impl Shell {
         self.tiling_exceptions = layout::TilingExceptions::new(exceptions);
     }
 
+    pub fn update_window_rules<'a, I>(&mut self, rules: I)
+    where
+        I: Iterator<Item = &'a WindowRule>,
+    {
+        self.window_rules = layout::WindowRules::new(rules);
+    }
+
+    pub fn update_swallowing(&mut self, config: SwallowingConfig) {
+        self.swallowing = config;
+    }
+
+    pub fn update_decorations(
+        &mut self,
+        config: DecorationsConfig,
+        xdg_activation_state: &XdgActivationState,
+    ) {
+        self.decorations = config.clone();
+        for scratchpad in self.scratchpads.values() {
+            for (window, ..) in &scratchpad.hidden {
+                window.window.update_decorations(config.clone());
+            }
+        }
+        self.workspaces
+            .set_decorations(config, xdg_activation_state);
+    }
+
     pub fn take_presentation_feedback(
         &self,
         output: &Output,
@@ -3779,10 +4985,17 @@ fn workspace_set_idx(
     state: &mut WorkspaceUpdateGuard<'_, State>,
     idx: u8,
     output_pos: usize,
-    handle: &WorkspaceHandle,
+    workspace: &Workspace,
 ) {
-    state.set_workspace_name(handle, format!("{}", idx));
-    state.set_workspace_coordinates(handle, [Some(idx as u32), Some(output_pos as u32), None]);
+    let name = match workspace.name.as_ref() {
+        Some(name) => name.clone(),
+        None => format!("{}", idx),
+    };
+    state.set_workspace_name(&workspace.handle, name);
+    state.set_workspace_coordinates(
+        &workspace.handle,
+        [Some(idx as u32), Some(output_pos as u32), None],
+    );
 }
 
 pub fn check_grab_preconditions(
@@ -3828,3 +5041,168 @@ pub fn check_grab_preconditions(
 
     Some(start_data)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use smithay::{
+        output::{Mode, PhysicalProperties, Subpixel},
+        reexports::wayland_server::Display,
+    };
+
+    fn output(name: &str) -> Output {
+        let output = Output::new(
+            name.into(),
+            PhysicalProperties {
+                size: (0, 0).into(),
+                subpixel: Subpixel::Unknown,
+                make: "test".into(),
+                model: "test".into(),
+            },
+        );
+        let mode = Mode {
+            size: (1920, 1080).into(),
+            refresh: 60_000,
+        };
+        output.change_current_state(Some(mode), None, None, Some((0, 0).into()));
+        output
+    }
+
+    fn workspaces(mode: WorkspaceMode, named: &[&str]) -> Workspaces {
+        Workspaces {
+            sets: IndexMap::new(),
+            backup_set: None,
+            layout: WorkspaceLayout::Vertical,
+            mode,
+            autotile: false,
+            autotile_behavior: TileBehavior::default(),
+            tiling_algorithm: TilingAlgorithm::default(),
+            named: named
+                .iter()
+                .map(|name| NamedWorkspace {
+                    name: name.to_string(),
+                    output: None,
+                    tiling: None,
+                    orientation: None,
+                    algorithm: None,
+                })
+                .collect(),
+            theme: cosmic::Theme::dark(),
+            decorations: DecorationsConfig::default(),
+        }
+    }
+
+    fn names(set: &WorkspaceSet) -> Vec<Option<&str>> {
+        set.workspaces.iter().map(|w| w.name.as_deref()).collect()
+    }
+
+    #[test]
+    fn test_create_and_remove_named() {
+        let display = Display::<State>::new().unwrap();
+        let mut workspace_state = WorkspaceState::<State>::new(&display.handle(), |_| true);
+        let xdg_activation_state = XdgActivationState::new::<State>(&display.handle());
+        let mut guard = workspace_state.update();
+
+        let output = output("test");
+        let mut workspaces = workspaces(WorkspaceMode::OutputBound, &["Mail"]);
+        workspaces.add_output(&output, &mut guard, &xdg_activation_state);
+        workspaces.refresh(&mut guard, &xdg_activation_state);
+        assert_eq!(names(&workspaces.sets[0]), vec![Some("Mail"), None]);
+
+        let group = workspaces.sets[0].group;
+        assert!(workspaces.create_named(&group, "Chat".into(), &mut guard));
+        assert!(!workspaces.create_named(&group, "Chat".into(), &mut guard));
+        assert!(!workspaces.create_named(&group, String::new(), &mut guard));
+        // unused client workspaces are kept, even though they are empty and not active
+        workspaces.refresh(&mut guard, &xdg_activation_state);
+        assert_eq!(
+            names(&workspaces.sets[0]),
+            vec![Some("Mail"), Some("Chat"), None]
+        );
+        assert_eq!(workspaces.sets[0].active, 2);
+
+        // workspaces named in the config can't be removed
+        let mail = workspaces.sets[0].workspaces[0].handle;
+        assert!(!workspaces.remove(&mail, &mut guard));
+
+        let chat = workspaces.sets[0].workspaces[1].handle;
+        assert!(workspaces.remove(&chat, &mut guard));
+        assert_eq!(names(&workspaces.sets[0]), vec![Some("Mail"), None]);
+        assert_eq!(workspaces.sets[0].active, 1);
+    }
+
+    #[test]
+    fn test_used_named_workspace_is_cleaned_up() {
+        let display = Display::<State>::new().unwrap();
+        let mut workspace_state = WorkspaceState::<State>::new(&display.handle(), |_| true);
+        let xdg_activation_state = XdgActivationState::new::<State>(&display.handle());
+        let mut guard = workspace_state.update();
+
+        let output = output("test");
+        let mut workspaces = workspaces(WorkspaceMode::OutputBound, &[]);
+        workspaces.add_output(&output, &mut guard, &xdg_activation_state);
+        let group = workspaces.sets[0].group;
+        assert!(workspaces.create_named(&group, "Chat".into(), &mut guard));
+        workspaces.refresh(&mut guard, &xdg_activation_state);
+        assert_eq!(names(&workspaces.sets[0]), vec![Some("Chat"), None]);
+
+        // as if a window was mapped on it and closed again
+        workspaces.sets[0].workspaces[0].keep_until_used = false;
+        workspaces.refresh(&mut guard, &xdg_activation_state);
+        assert_eq!(names(&workspaces.sets[0]), vec![None]);
+    }
+
+    #[test]
+    fn test_remove_active_workspace() {
+        let display = Display::<State>::new().unwrap();
+        let mut workspace_state = WorkspaceState::<State>::new(&display.handle(), |_| true);
+        let xdg_activation_state = XdgActivationState::new::<State>(&display.handle());
+        let mut guard = workspace_state.update();
+
+        let output = output("test");
+        let mut workspaces = workspaces(WorkspaceMode::OutputBound, &[]);
+        workspaces.add_output(&output, &mut guard, &xdg_activation_state);
+
+        // the last workspace of an output can't be removed
+        let last = workspaces.sets[0].workspaces[0].handle;
+        assert!(!workspaces.remove(&last, &mut guard));
+
+        let group = workspaces.sets[0].group;
+        assert!(workspaces.create_named(&group, "Chat".into(), &mut guard));
+        assert_eq!(workspaces.sets[0].active, 1);
+        assert!(workspaces.remove(&last, &mut guard));
+        assert_eq!(names(&workspaces.sets[0]), vec![Some("Chat")]);
+        assert_eq!(workspaces.sets[0].active, 0);
+
+        // a new empty workspace follows the named one
+        workspaces.refresh(&mut guard, &xdg_activation_state);
+        assert_eq!(names(&workspaces.sets[0]), vec![Some("Chat"), None]);
+    }
+
+    #[test]
+    fn test_create_and_remove_named_global() {
+        let display = Display::<State>::new().unwrap();
+        let mut workspace_state = WorkspaceState::<State>::new(&display.handle(), |_| true);
+        let xdg_activation_state = XdgActivationState::new::<State>(&display.handle());
+        let mut guard = workspace_state.update();
+
+        let mut workspaces = workspaces(WorkspaceMode::Global, &[]);
+        for name in ["first", "second"] {
+            workspaces.add_output(&output(name), &mut guard, &xdg_activation_state);
+        }
+        workspaces.refresh(&mut guard, &xdg_activation_state);
+
+        let group = workspaces.sets[0].group;
+        assert!(workspaces.create_named(&group, "Chat".into(), &mut guard));
+        for set in workspaces.sets.values() {
+            assert_eq!(names(set), vec![Some("Chat"), None]);
+        }
+
+        // removing it on one output removes it everywhere
+        let chat = workspaces.sets[1].workspaces[0].handle;
+        assert!(workspaces.remove(&chat, &mut guard));
+        for set in workspaces.sets.values() {
+            assert_eq!(names(set), vec![None]);
+        }
+    }
+}
@@ -0,0 +1,499 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{
+    collections::HashSet,
+    time::{Duration, Instant},
+};
+
+use cosmic_comp_config::workspace::TilingOrientation;
+use serde::{Deserialize, Serialize};
+use smithay::{
+    reexports::calloop::{
+        timer::{TimeoutAction, Timer},
+        LoopHandle,
+    },
+    utils::{IsAlive, Rectangle},
+};
+use tracing::warn;
+
+use crate::{state::State, utils::prelude::*};
+
+use super::{element::CosmicMapped, layout, CosmicSurface, Shell};
+
+/// Interval in which the session state is written to disk, if it changed
+const SAVE_INTERVAL: Duration = Duration::from_secs(10);
+/// Saved windows, that didn't reappear in this time after startup, are forgotten
+const RESTORE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Window placement persisted across compositor restarts
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct SessionState {
+    pub workspaces: Vec<SavedWorkspace>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SavedWorkspace {
+    pub output: String,
+    pub idx: usize,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub tiling: Option<SavedNode>,
+    #[serde(default)]
+    pub floating: Vec<SavedFloating>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct WindowKey {
+    pub app_id: String,
+    pub title: String,
+}
+
+impl From<&CosmicSurface> for WindowKey {
+    fn from(window: &CosmicSurface) -> Self {
+        WindowKey {
+            app_id: window.app_id(),
+            title: window.title(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub enum SavedNode {
+    Group {
+        orientation: TilingOrientation,
+        ratios: Vec<f64>,
+        children: Vec<SavedNode>,
+    },
+    Window(WindowKey),
+    /// Windows of a stack, in tab order
+    Stack(Vec<WindowKey>),
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SavedFloating {
+    pub window: WindowKey,
+    /// Further windows stacked with `window`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stacked: Vec<WindowKey>,
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Slot {
+    Tiling(Vec<usize>),
+    Floating(usize),
+}
+
+/// Saved placement matched to a newly mapped window
+#[derive(Debug, Clone)]
+pub struct Restored {
+    workspace: usize,
+    slot: Slot,
+}
+
+#[derive(Debug, Clone)]
+pub struct TilingHint {
+    pub neighbor: CosmicMapped,
+    pub depth: usize,
+    pub orientation: layout::Orientation,
+    pub after: bool,
+    pub ratios: Vec<f64>,
+}
+
+/// Matches newly mapped windows against the state saved by the previous session
+#[derive(Debug)]
+pub struct SessionRestore {
+    saved: SessionState,
+    used: HashSet<(usize, Slot, usize)>,
+    restored: Vec<(usize, Slot, CosmicMapped)>,
+    started: Instant,
+}
+
+impl SessionRestore {
+    pub fn new(saved: SessionState) -> Self {
+        SessionRestore {
+            saved,
+            used: HashSet::new(),
+            restored: Vec::new(),
+            started: Instant::now(),
+        }
+    }
+
+    fn expired(&self) -> bool {
+        Instant::now().duration_since(self.started) > RESTORE_TIMEOUT
+    }
+
+    /// Whether windows of the previous session are still expected to show up
+    pub fn is_pending(&self) -> bool {
+        !self.expired()
+            && self
+                .slots()
+                .any(|(ws, slot, stack_idx, _)| !self.used.contains(&(ws, slot, stack_idx)))
+    }
+
+    fn slots(&self) -> impl Iterator<Item = (usize, Slot, usize, &WindowKey)> {
+        fn leaves<'a>(
+            node: &'a SavedNode,
+            path: Vec<usize>,
+            out: &mut Vec<(Vec<usize>, usize, &'a WindowKey)>,
+        ) {
+            match node {
+                SavedNode::Group { children, .. } => {
+                    for (i, child) in children.iter().enumerate() {
+                        let mut path = path.clone();
+                        path.push(i);
+                        leaves(child, path, out);
+                    }
+                }
+                SavedNode::Window(key) => out.push((path, 0, key)),
+                SavedNode::Stack(keys) => out.extend(
+                    keys.iter()
+                        .enumerate()
+                        .map(|(i, key)| (path.clone(), i, key)),
+                ),
+            }
+        }
+
+        self.saved
+            .workspaces
+            .iter()
+            .enumerate()
+            .flat_map(|(ws, workspace)| {
+                let mut tiled = Vec::new();
+                if let Some(node) = workspace.tiling.as_ref() {
+                    leaves(node, Vec::new(), &mut tiled);
+                }
+                tiled
+                    .into_iter()
+                    .map(move |(path, stack_idx, key)| (ws, Slot::Tiling(path), stack_idx, key))
+                    .chain(
+                        workspace
+                            .floating
+                            .iter()
+                            .enumerate()
+                            .flat_map(move |(i, floating)| {
+                                std::iter::once(&floating.window)
+                                    .chain(floating.stacked.iter())
+                                    .enumerate()
+                                    .map(move |(stack_idx, key)| {
+                                        (ws, Slot::Floating(i), stack_idx, key)
+                                    })
+                            }),
+                    )
+            })
+    }
+
+    /// Finds the saved placement for `window`, preferring an exact title match
+    pub fn take(&mut self, window: &CosmicSurface) -> Option<Restored> {
+        if self.expired() {
+            return None;
+        }
+        self.take_key(&WindowKey::from(window))
+    }
+
+    fn take_key(&mut self, key: &WindowKey) -> Option<Restored> {
+        let (ws, slot, stack_idx) = {
+            let mut candidates = self
+                .slots()
+                .filter(|(ws, slot, stack_idx, _)| {
+                    !self.used.contains(&(*ws, slot.clone(), *stack_idx))
+                })
+                .filter(|(_, _, _, saved)| saved.app_id == key.app_id)
+                .map(|(ws, slot, stack_idx, saved)| (ws, slot, stack_idx, saved.title == key.title))
+                .collect::<Vec<_>>();
+            let idx = candidates
+                .iter()
+                .position(|(_, _, _, exact)| *exact)
+                .or((!candidates.is_empty()).then_some(0))?;
+            let (ws, slot, stack_idx, _) = candidates.swap_remove(idx);
+            (ws, slot, stack_idx)
+        };
+
+        self.used.insert((ws, slot.clone(), stack_idx));
+        Some(Restored {
+            workspace: ws,
+            slot,
+        })
+    }
+
+    /// Output name, workspace index and name the window was on
+    pub fn workspace(&self, restored: &Restored) -> (&str, usize, Option<&str>) {
+        let workspace = &self.saved.workspaces[restored.workspace];
+        (&workspace.output, workspace.idx, workspace.name.as_deref())
+    }
+
+    /// Already restored window, that shared a stack with `restored`
+    pub fn stack_target(&self, restored: &Restored) -> Option<CosmicMapped> {
+        self.restored
+            .iter()
+            .find(|(ws, slot, mapped)| {
+                *ws == restored.workspace && slot == &restored.slot && mapped.alive()
+            })
+            .map(|(_, _, mapped)| mapped.clone())
+    }
+
+    pub fn is_floating(&self, restored: &Restored) -> bool {
+        matches!(restored.slot, Slot::Floating(_))
+    }
+
+    pub fn floating_geometry(&self, restored: &Restored) -> Option<Rectangle<i32, Local>> {
+        let Slot::Floating(i) = restored.slot else {
+            return None;
+        };
+        let saved = &self.saved.workspaces[restored.workspace].floating[i];
+        Some(Rectangle::from_loc_and_size(
+            (saved.x, saved.y),
+            (saved.width, saved.height),
+        ))
+    }
+
+    /// Finds an already restored window next to the saved position of `restored`
+    pub fn tiling_hint(&self, restored: &Restored) -> Option<TilingHint> {
+        let Slot::Tiling(path) = &restored.slot else {
+            return None;
+        };
+        let (i, parent_path) = path.split_last()?;
+
+        let mut parent = self.saved.workspaces[restored.workspace].tiling.as_ref()?;
+        for idx in parent_path {
+            let SavedNode::Group { children, .. } = parent else {
+                return None;
+            };
+            parent = children.get(*idx)?;
+        }
+        let SavedNode::Group {
+            orientation,
+            ratios,
+            children,
+        } = parent
+        else {
+            return None;
+        };
+
+        // try the closest siblings first
+        let mut siblings = (0..children.len()).filter(|j| j != i).collect::<Vec<_>>();
+        siblings.sort_by_key(|j| j.abs_diff(*i));
+        siblings.into_iter().find_map(|j| {
+            let mut prefix = parent_path.to_vec();
+            prefix.push(j);
+            self.restored
+                .iter()
+                .filter_map(|(ws, slot, mapped)| match slot {
+                    Slot::Tiling(path)
+                        if *ws == restored.workspace
+                            && path.starts_with(&prefix)
+                            && mapped.alive() =>
+                    {
+                        Some((path, mapped))
+                    }
+                    _ => None,
+                })
+                .min_by_key(|(path, _)| path.len())
+                .map(|(path, mapped)| TilingHint {
+                    neighbor: mapped.clone(),
+                    depth: path.len() - prefix.len(),
+                    orientation: layout::orientation_from_config(*orientation),
+                    after: *i > j,
+                    ratios: ratios.clone(),
+                })
+        })
+    }
+
+    /// Remembers where `mapped` was restored to, so later windows can be placed next or onto it
+    pub fn restored(&mut self, restored: &Restored, mapped: &CosmicMapped) {
+        // windows stacked onto a previously restored one replace its element
+        self.restored
+            .retain(|(ws, slot, _)| *ws != restored.workspace || slot != &restored.slot);
+        self.restored
+            .push((restored.workspace, restored.slot.clone(), mapped.clone()));
+    }
+}
+
+impl Shell {
+    pub fn session_state(&self) -> SessionState {
+        let mut workspaces = Vec::new();
+        for (output, set) in self.workspaces.sets.iter() {
+            for (idx, workspace) in set.workspaces.iter().enumerate() {
+                let tiling = workspace.tiling_layer.saved_tree();
+                let floating = workspace
+                    .floating_layer
+                    .mapped()
+                    .filter(|mapped| mapped.maximized_state.lock().unwrap().is_none())
+                    .filter_map(|mapped| {
+                        let geometry = workspace.floating_layer.element_geometry(mapped)?;
+                        let mut windows =
+                            mapped.windows().map(|(window, _)| WindowKey::from(&window));
+                        Some(SavedFloating {
+                            window: windows.next()?,
+                            stacked: windows.collect(),
+                            x: geometry.loc.x,
+                            y: geometry.loc.y,
+                            width: geometry.size.w,
+                            height: geometry.size.h,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+
+                if tiling.is_some() || !floating.is_empty() {
+                    workspaces.push(SavedWorkspace {
+                        output: output.name(),
+                        idx,
+                        name: workspace.name.clone(),
+                        tiling,
+                        floating,
+                    });
+                }
+            }
+        }
+
+        SessionState { workspaces }
+    }
+}
+
+/// Periodically persists the current window placement
+pub fn init(evlh: &LoopHandle<'static, State>) {
+    if let Err(err) = evlh.insert_source(Timer::from_duration(SAVE_INTERVAL), |_, _, state| {
+        let shell = state.common.shell.read().unwrap();
+        // don't overwrite the previous session, while its windows are still being restored
+        if !shell.session_restore.is_pending() {
+            let session = shell.session_state();
+            std::mem::drop(shell);
+            if state.common.config.dynamic_conf.session() != &session {
+                *state.common.config.dynamic_conf.session_mut() = session;
+            }
+        }
+        TimeoutAction::ToDuration(SAVE_INTERVAL)
+    }) {
+        warn!(?err, "Failed to schedule saving the session state");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn key(app_id: &str, title: &str) -> WindowKey {
+        WindowKey {
+            app_id: app_id.into(),
+            title: title.into(),
+        }
+    }
+
+    fn floating(window: WindowKey, stacked: Vec<WindowKey>) -> SavedFloating {
+        SavedFloating {
+            window,
+            stacked,
+            x: 10,
+            y: 20,
+            width: 800,
+            height: 600,
+        }
+    }
+
+    fn session() -> SessionState {
+        SessionState {
+            workspaces: vec![
+                SavedWorkspace {
+                    output: "DP-1".into(),
+                    idx: 0,
+                    name: None,
+                    tiling: Some(SavedNode::Group {
+                        orientation: TilingOrientation::Vertical,
+                        ratios: vec![0.5, 0.5],
+                        children: vec![
+                            SavedNode::Window(key("kitty", "~")),
+                            SavedNode::Stack(vec![key("firefox", "Docs"), key("firefox", "Mail")]),
+                        ],
+                    }),
+                    floating: Vec::new(),
+                },
+                SavedWorkspace {
+                    output: "DP-1".into(),
+                    idx: 2,
+                    name: Some("Chat".into()),
+                    tiling: None,
+                    floating: vec![floating(key("kitty", "htop"), vec![key("kitty", "~")])],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_take_prefers_exact_title() {
+        let mut restore = SessionRestore::new(session());
+
+        let restored = restore.take_key(&key("kitty", "htop")).unwrap();
+        assert_eq!(restore.workspace(&restored), ("DP-1", 2, Some("Chat")));
+        assert!(restore.is_floating(&restored));
+        assert_eq!(
+            restore.floating_geometry(&restored),
+            Some(Rectangle::from_loc_and_size((10, 20), (800, 600)))
+        );
+
+        let restored = restore.take_key(&key("kitty", "~")).unwrap();
+        assert_eq!(restore.workspace(&restored), ("DP-1", 0, None));
+        assert!(!restore.is_floating(&restored));
+        assert_eq!(restore.floating_geometry(&restored), None);
+    }
+
+    #[test]
+    fn test_take_falls_back_to_app_id() {
+        let mut restore = SessionRestore::new(session());
+
+        // three saved kitty windows, no title matches
+        for _ in 0..3 {
+            assert!(restore.take_key(&key("kitty", "vim")).is_some());
+        }
+        assert!(restore.take_key(&key("kitty", "vim")).is_none());
+        assert!(restore.take_key(&key("alacritty", "~")).is_none());
+        assert!(restore.is_pending());
+    }
+
+    #[test]
+    fn test_take_stacked_windows() {
+        let mut restore = SessionRestore::new(session());
+
+        let docs = restore.take_key(&key("firefox", "Docs")).unwrap();
+        let mail = restore.take_key(&key("firefox", "Mail")).unwrap();
+        assert_eq!(docs.workspace, mail.workspace);
+        assert_eq!(docs.slot, mail.slot);
+        assert_eq!(docs.slot, Slot::Tiling(vec![1]));
+        assert!(restore.take_key(&key("firefox", "Docs")).is_none());
+
+        let htop = restore.take_key(&key("kitty", "htop")).unwrap();
+        let shell = restore.take_key(&key("kitty", "~")).unwrap();
+        let other = restore.take_key(&key("kitty", "~")).unwrap();
+        assert_eq!(htop.slot, Slot::Floating(0));
+        assert_ne!(
+            (shell.workspace, &shell.slot),
+            (other.workspace, &other.slot)
+        );
+        assert!(!restore.is_pending());
+    }
+
+    #[test]
+    fn test_serialization() {
+        let session = session();
+        let serialized = ron::ser::to_string(&session).unwrap();
+        let deserialized = ron::de::from_str::<SessionState>(&serialized).unwrap();
+        assert_eq!(session, deserialized);
+    }
+
+    #[test]
+    fn test_deserialize_without_stacks() {
+        let deserialized = ron::de::from_str::<SessionState>(
+            r#"(workspaces: [(output: "DP-1", idx: 0, floating: [(window: (app_id: "kitty", title: "~"), x: 10, y: 20, width: 800, height: 600)])])"#,
+        )
+        .unwrap();
+        assert_eq!(
+            deserialized.workspaces[0].floating,
+            vec![floating(key("kitty", "~"), Vec::new())]
+        );
+        assert_eq!(deserialized.workspaces[0].tiling, None);
+        assert_eq!(deserialized.workspaces[0].name, None);
+    }
+}
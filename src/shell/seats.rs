@@ -3,7 +3,7 @@
 use std::{any::Any, cell::RefCell, collections::HashMap, sync::Mutex};
 
 use crate::{
-    backend::render::cursor::CursorState,
+    backend::render::cursor::{CursorState, TabletToolCursors},
     config::{xkb_config_to_wl, Config},
     input::{ModifiersShortcutQueue, SupressedButtons, SupressedKeys},
     state::State,
@@ -23,10 +23,13 @@ use smithay::{
 };
 use tracing::warn;
 
-use super::grabs::{SeatMenuGrabState, SeatMoveGrabState};
+use super::grabs::{SeatMenuGrabState, SeatMoveGrabState, SeatRegionGrabState};
 
 crate::utils::id_gen!(next_seat_id, SEAT_ID, SEAT_IDS);
 
+/// Name of the seat, that all input devices belong to by default
+pub const DEFAULT_SEAT_NAME: &str = "seat-0";
+
 // for more information on seats, see:
 // <https://wayland-book.com/print.html#seats-handling-input>
 /// Seats are an abstraction over a set of input devices grouped together, such as a keyboard, pointer and touch device.
@@ -61,6 +64,26 @@ impl Seats {
         }
     }
 
+    /// Drops seats without any input devices left, e.g. after devices got assigned to other seats.
+    ///
+    /// The last remaining seat is always kept. Returns the removed seats.
+    pub fn remove_empty_seats(&mut self) -> Vec<Seat<State>> {
+        let empty = self
+            .seats
+            .iter()
+            .filter(|seat| seat.devices().is_empty())
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut removed = Vec::new();
+        for seat in empty {
+            if self.seats.len() > 1 {
+                self.remove_seat(&seat);
+                removed.push(seat);
+            }
+        }
+        removed
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = &Seat<State>> {
         self.seats.iter()
     }
@@ -111,6 +134,10 @@ impl Devices {
         new_caps
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.capabilities.borrow().is_empty()
+    }
+
     pub fn has_device<D: Device>(&self, device: &D) -> bool {
         self.capabilities.borrow().contains_key(&device.id())
     }
@@ -182,7 +209,9 @@ pub fn create_seat(
     userdata.insert_if_missing(ModifiersShortcutQueue::default);
     userdata.insert_if_missing_threadsafe(SeatMoveGrabState::default);
     userdata.insert_if_missing_threadsafe(SeatMenuGrabState::default);
+    userdata.insert_if_missing_threadsafe(SeatRegionGrabState::default);
     userdata.insert_if_missing_threadsafe(CursorState::default);
+    userdata.insert_if_missing_threadsafe(TabletToolCursors::default);
     userdata.insert_if_missing_threadsafe(|| ActiveOutput(Mutex::new(output.clone())));
     userdata.insert_if_missing_threadsafe(|| FocusedOutput(Mutex::new(None)));
     userdata.insert_if_missing_threadsafe(|| Mutex::new(CursorImageStatus::default_named()));
@@ -219,6 +248,24 @@ pub fn create_seat(
     seat
 }
 
+/// Name of the seat an input device belongs to.
+///
+/// Devices are assigned by the `input_seats` config first. Otherwise libinput devices are grouped
+/// by their udev `WL_SEAT` property, as only devices of our session's `ID_SEAT` are handed to us.
+pub fn seat_name_for_device<D: Device + 'static>(config: &Config, device: &D) -> String {
+    if let Some(name) = config.cosmic_conf.input_seats.get(&device.name()) {
+        return name.clone();
+    }
+    if let Some(device) = <dyn Any>::downcast_ref::<InputDevice>(device) {
+        let name = device.seat().logical_name().to_string();
+        // libinput's name for devices without a `WL_SEAT`
+        if name != "default" {
+            return name;
+        }
+    }
+    DEFAULT_SEAT_NAME.to_string()
+}
+
 pub trait SeatExt {
     fn id(&self) -> usize;
 
@@ -234,6 +281,7 @@ pub trait SeatExt {
     fn supressed_keys(&self) -> &SupressedKeys;
     fn supressed_buttons(&self) -> &SupressedButtons;
     fn modifiers_shortcut_queue(&self) -> &ModifiersShortcutQueue;
+    fn tablet_tool_cursors(&self) -> &TabletToolCursors;
 
     fn cursor_geometry(
         &self,
@@ -308,6 +356,10 @@ impl SeatExt for Seat<State> {
         self.user_data().get::<ModifiersShortcutQueue>().unwrap()
     }
 
+    fn tablet_tool_cursors(&self) -> &TabletToolCursors {
+        self.user_data().get::<TabletToolCursors>().unwrap()
+    }
+
     fn cursor_geometry(
         &self,
         loc: impl Into<Point<f64, Buffer>>,
@@ -0,0 +1,145 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use cosmic_comp_config::SwallowingConfig;
+
+use super::{CosmicMapped, CosmicSurface, Workspace, WorkspaceHandle};
+
+/// A terminal, whose tile was taken over by a window launched from it
+#[derive(Debug)]
+pub struct SwallowedWindow {
+    pub child: CosmicSurface,
+    pub terminal: CosmicMapped,
+    /// Workspace the terminal is restored to, when its child goes away
+    pub workspace: WorkspaceHandle,
+}
+
+/// Finds the tiled terminal on `workspace`, that `window` was launched from
+pub fn swallow_target(
+    config: &SwallowingConfig,
+    window: &CosmicSurface,
+    workspace: &Workspace,
+) -> Option<CosmicMapped> {
+    if !may_swallow(config, &window.app_id()) {
+        return None;
+    }
+
+    let ancestors = ancestor_pids(window.pid()?);
+    let terminals = workspace
+        .tiling_layer
+        .mapped()
+        .chain(workspace.scrolling_layer.mapped())
+        .map(|(mapped, _)| mapped)
+        .filter(|mapped| mapped.is_window())
+        .filter_map(|mapped| {
+            let terminal = mapped.active_window();
+            if !config.terminals.contains(&terminal.app_id()) {
+                return None;
+            }
+            Some((terminal.pid()?, mapped))
+        });
+    closest_terminal(&ancestors, terminals).cloned()
+}
+
+/// Whether a window with `app_id` may take over the tile of its terminal
+fn may_swallow(config: &SwallowingConfig, app_id: &str) -> bool {
+    let listed = |app_ids: &[String]| app_ids.iter().any(|id| id == app_id);
+    config.enabled && !listed(&config.terminals) && !listed(&config.exceptions)
+}
+
+/// Picks the terminal closest in the process tree, if terminals were launched from each other
+fn closest_terminal<T>(ancestors: &[i32], terminals: impl Iterator<Item = (i32, T)>) -> Option<T> {
+    terminals
+        .filter_map(|(pid, terminal)| {
+            let depth = ancestors.iter().position(|ancestor| *ancestor == pid)?;
+            Some((depth, terminal))
+        })
+        .min_by_key(|(depth, _)| *depth)
+        .map(|(_, terminal)| terminal)
+}
+
+/// Parent process ids of `pid`, starting with its direct parent
+fn ancestor_pids(pid: i32) -> Vec<i32> {
+    let mut ancestors = Vec::new();
+    let mut current = pid;
+    while let Some(parent) = parent_pid(current) {
+        if parent <= 1 || ancestors.contains(&parent) {
+            break;
+        }
+        ancestors.push(parent);
+        current = parent;
+    }
+    ancestors
+}
+
+fn parent_pid(pid: i32) -> Option<i32> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    parse_parent_pid(&stat)
+}
+
+fn parse_parent_pid(stat: &str) -> Option<i32> {
+    // the command name may contain spaces and parentheses, so skip past the last one
+    let (_, fields) = stat.rsplit_once(')')?;
+    fields.split_whitespace().nth(1)?.parse().ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn config() -> SwallowingConfig {
+        SwallowingConfig {
+            enabled: true,
+            terminals: vec![String::from("Alacritty")],
+            exceptions: vec![String::from("org.gnome.Nautilus")],
+        }
+    }
+
+    #[test]
+    fn test_may_swallow() {
+        assert!(may_swallow(&config(), "mpv"));
+        assert!(!may_swallow(&config(), "Alacritty"));
+        assert!(!may_swallow(&config(), "org.gnome.Nautilus"));
+        assert!(!may_swallow(
+            &SwallowingConfig {
+                enabled: false,
+                ..config()
+            },
+            "mpv"
+        ));
+    }
+
+    #[test]
+    fn test_closest_terminal() {
+        let ancestors = [30, 20, 10];
+        let terminals = || [(10, "outer"), (20, "inner"), (40, "unrelated")].into_iter();
+        assert_eq!(closest_terminal(&ancestors, terminals()), Some("inner"));
+        assert_eq!(
+            closest_terminal(&ancestors[2..], terminals()),
+            Some("outer")
+        );
+        assert_eq!(closest_terminal(&[50], terminals()), None);
+        assert_eq!(closest_terminal(&[], terminals()), None);
+    }
+
+    #[test]
+    fn test_parse_parent_pid() {
+        assert_eq!(parse_parent_pid("1234 (mpv) S 42 1234 1234 0 -1"), Some(42));
+        assert_eq!(
+            parse_parent_pid("1234 (a) b (c)) R 7 1234 1234 0 -1"),
+            Some(7)
+        );
+        assert_eq!(parse_parent_pid("1234 (mpv"), None);
+        assert_eq!(parse_parent_pid("1234 (mpv) S"), None);
+    }
+
+    #[test]
+    fn test_parent_pid() {
+        let pid = std::process::id() as i32;
+        let parent = std::os::unix::process::parent_id() as i32;
+        assert_eq!(parent_pid(pid), Some(parent));
+        if parent > 1 {
+            assert_eq!(ancestor_pids(pid).first(), Some(&parent));
+        }
+        assert_eq!(parent_pid(-1), None);
+    }
+}
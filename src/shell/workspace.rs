@@ -4,7 +4,7 @@ use crate::{
         BackdropShader, SplitRenderElements,
     },
     shell::{
-        layout::{floating::FloatingLayout, tiling::TilingLayout},
+        layout::{floating::FloatingLayout, scrolling::ScrollingLayout, tiling::TilingLayout},
         OverviewMode, ANIMATION_DURATION,
     },
     state::State,
@@ -19,6 +19,7 @@ use crate::{
 };
 
 use cosmic::theme::CosmicTheme;
+use cosmic_comp_config::DecorationsConfig;
 use cosmic_protocols::workspace::v1::server::zcosmic_workspace_handle_v1::TilingState;
 use id_tree::Tree;
 use indexmap::IndexSet;
@@ -67,7 +68,7 @@ use super::{
     },
     grabs::ResizeEdge,
     layout::tiling::{Data, MinimizedTilingState, NodeDesc},
-    CosmicMappedRenderElement, CosmicSurface, ResizeDirection, ResizeMode,
+    CosmicMappedRenderElement, CosmicSurface, Direction, ResizeDirection, ResizeMode,
 };
 
 const FULLSCREEN_ANIMATION_DURATION: Duration = Duration::from_millis(200);
@@ -77,9 +78,16 @@ pub struct Workspace {
     pub output: Output,
     pub tiling_layer: TilingLayout,
     pub floating_layer: FloatingLayout,
+    pub scrolling_layer: ScrollingLayout,
     pub minimized_windows: Vec<MinimizedWindow>,
     pub tiling_enabled: bool,
+    /// Tiled windows are placed in the scrolling layer instead of the tiling tree
+    pub scrolling_enabled: bool,
     pub fullscreen: Option<FullscreenSurface>,
+    /// Name of the workspace, workspaces named in the config are persistent
+    pub name: Option<String>,
+    /// Created by a client, kept even while empty until a window is mapped on it
+    pub(super) keep_until_used: bool,
 
     pub handle: WorkspaceHandle,
     pub focus_stack: FocusStacks,
@@ -127,7 +135,7 @@ impl MinimizedWindow {
         }
     }
 
-    fn unfullscreen(&mut self) -> Option<(ManagedLayer, WorkspaceHandle)> {
+    pub(super) fn unfullscreen(&mut self) -> Option<(ManagedLayer, WorkspaceHandle)> {
         let fullscreen = self.fullscreen.take()?;
         self.window.set_fullscreen(false);
         self.window.set_geometry(fullscreen.original_geometry);
@@ -238,18 +246,24 @@ impl Workspace {
         output: Output,
         tiling_enabled: bool,
         theme: cosmic::Theme,
+        decorations: DecorationsConfig,
     ) -> Workspace {
-        let tiling_layer = TilingLayout::new(theme.clone(), &output);
-        let floating_layer = FloatingLayout::new(theme, &output);
+        let tiling_layer = TilingLayout::new(theme.clone(), decorations.clone(), &output);
+        let scrolling_layer = ScrollingLayout::new(theme.clone(), decorations.clone(), &output);
+        let floating_layer = FloatingLayout::new(theme, decorations, &output);
         let output_name = output.name();
 
         Workspace {
             output,
             tiling_layer,
             floating_layer,
+            scrolling_layer,
             tiling_enabled,
+            scrolling_enabled: false,
             minimized_windows: Vec::new(),
             fullscreen: None,
+            name: None,
+            keep_until_used: false,
             handle,
             focus_stack: FocusStacks::default(),
             screencopy: ScreencopySessions::default(),
@@ -273,6 +287,7 @@ impl Workspace {
 
         self.floating_layer.refresh();
         self.tiling_layer.refresh();
+        self.scrolling_layer.refresh();
 
         self.pending_tokens
             .retain(|token| xdg_activation_state.data_for_token(token).is_some());
@@ -288,6 +303,7 @@ impl Workspace {
     pub fn animations_going(&self) -> bool {
         self.tiling_layer.animations_going()
             || self.floating_layer.animations_going()
+            || self.scrolling_layer.animations_going()
             || self
                 .fullscreen
                 .as_ref()
@@ -339,6 +355,7 @@ impl Workspace {
 
         clients.extend(self.tiling_layer.update_animation_state());
         self.floating_layer.update_animation_state();
+        self.scrolling_layer.update_animation_state();
         clients
     }
 
@@ -349,6 +366,7 @@ impl Workspace {
     pub fn set_output(&mut self, output: &Output) {
         self.tiling_layer.set_output(output);
         self.floating_layer.set_output(output);
+        self.scrolling_layer.set_output(output);
         for mapped in self.mapped() {
             for (surface, _) in mapped.windows() {
                 toplevel_leave_output(&surface, &self.output);
@@ -393,7 +411,8 @@ impl Workspace {
         }
 
         let mut was_floating = self.floating_layer.unmap(&mapped).is_some();
-        let mut was_tiling = self.tiling_layer.unmap(&mapped);
+        let mut was_tiling =
+            self.tiling_layer.unmap(&mapped) || self.scrolling_layer.unmap(&mapped);
         if was_floating || was_tiling {
             assert!(was_floating != was_tiling);
         }
@@ -441,6 +460,7 @@ impl Workspace {
         self.floating_layer
             .mapped()
             .chain(self.tiling_layer.mapped().map(|(w, _)| w))
+            .chain(self.scrolling_layer.mapped().map(|(w, _)| w))
             .chain(self.minimized_windows.iter().map(|w| &w.window))
             .find(|e| e.windows().any(|(w, _)| &w == surface))
     }
@@ -449,6 +469,7 @@ impl Workspace {
         let location = location.to_local(&self.output);
         self.floating_layer
             .element_under(location)
+            .or_else(|| self.scrolling_layer.element_under(location))
             .or_else(|| self.tiling_layer.element_under(location))
     }
 
@@ -460,6 +481,7 @@ impl Workspace {
         let location = location.to_local(&self.output);
         self.floating_layer
             .surface_under(location)
+            .or_else(|| self.scrolling_layer.surface_under(location))
             .or_else(|| self.tiling_layer.surface_under(location, overview))
             .map(|(m, p)| (m, p.to_global(&self.output)))
     }
@@ -468,13 +490,64 @@ impl Workspace {
         self.floating_layer
             .element_geometry(elem)
             .or_else(|| self.tiling_layer.element_geometry(elem))
+            .or_else(|| self.scrolling_layer.element_geometry(elem))
     }
 
     pub fn recalculate(&mut self) {
         self.tiling_layer.recalculate();
+        self.scrolling_layer.recalculate();
         self.floating_layer.recalculate();
     }
 
+    /// Maps `window` into the layer used for tiled windows on this workspace
+    pub fn map_tiled(
+        &mut self,
+        window: CosmicMapped,
+        seat: Option<&Seat<State>>,
+        direction: Option<Direction>,
+    ) {
+        let focus_stack = seat.map(|seat| self.focus_stack.get(seat));
+        if self.scrolling_enabled {
+            self.scrolling_layer
+                .map(window, focus_stack.as_ref().map(|x| x.iter()));
+        } else {
+            self.tiling_layer
+                .map(window, focus_stack.as_ref().map(|x| x.iter()), direction);
+        }
+    }
+
+    pub fn toggle_scrolling(&mut self, seat: &Seat<State>) {
+        self.set_scrolling(!self.scrolling_enabled, seat)
+    }
+
+    /// Moves all tiled windows between the tiling tree and the scrolling layer
+    pub fn set_scrolling(&mut self, scrolling: bool, seat: &Seat<State>) {
+        if self.scrolling_enabled == scrolling {
+            return;
+        }
+
+        let windows = if scrolling {
+            self.tiling_layer
+                .mapped()
+                .map(|(m, _)| m.clone())
+                .collect::<Vec<_>>()
+        } else {
+            self.scrolling_layer
+                .mapped()
+                .map(|(m, _)| m.clone())
+                .collect::<Vec<_>>()
+        };
+        self.scrolling_enabled = scrolling;
+        for window in windows {
+            if scrolling {
+                self.tiling_layer.unmap(&window);
+            } else {
+                self.scrolling_layer.unmap(&window);
+            }
+            self.map_tiled(window, Some(seat), None);
+        }
+    }
+
     pub fn unmaximize_request(&mut self, elem: &CosmicMapped) -> Option<Size<i32, Logical>> {
         let mut state = elem.maximized_state.lock().unwrap();
         if let Some(state) = state.take() {
@@ -495,8 +568,10 @@ impl Workspace {
                         elem.set_geometry(state.original_geometry.to_global(&self.output));
                         elem.configure();
                         self.tiling_layer.recalculate();
+                        self.scrolling_layer.recalculate();
                         self.tiling_layer
                             .element_geometry(&elem)
+                            .or_else(|| self.scrolling_layer.element_geometry(&elem))
                             .map(|geo| geo.size.as_logical())
                     }
                     ManagedLayer::Sticky => unreachable!(),
@@ -547,7 +622,19 @@ impl Workspace {
             None
         };
 
-        if self.tiling_layer.mapped().any(|(m, _)| m == elem) {
+        if self.scrolling_layer.mapped().any(|(m, _)| m == elem) {
+            let was_maximized = self.floating_layer.unmap(&elem).is_some();
+            self.scrolling_layer.unmap(elem);
+            Some(MinimizedWindow {
+                window: elem.clone(),
+                previous_state: MinimizedState::Tiling {
+                    tiling_state: None,
+                    was_maximized,
+                },
+                output_geo: self.output.geometry(),
+                fullscreen,
+            })
+        } else if self.tiling_layer.mapped().any(|(m, _)| m == elem) {
             let was_maximized = self.floating_layer.unmap(&elem).is_some();
             let tiling_state = self.tiling_layer.unmap_minimize(elem, to);
             Some(MinimizedWindow {
@@ -598,7 +685,17 @@ impl Workspace {
                 tiling_state,
                 was_maximized,
             } => {
-                if self.tiling_enabled {
+                if self.tiling_enabled && self.scrolling_enabled {
+                    self.map_tiled(window.window.clone(), Some(seat), None);
+                    if was_maximized {
+                        let previous_geometry = self
+                            .scrolling_layer
+                            .element_geometry(&window.window)
+                            .unwrap();
+                        self.floating_layer
+                            .map_maximized(window.window, previous_geometry, true);
+                    }
+                } else if self.tiling_enabled {
                     let focus_stack = self.focus_stack.get(seat);
                     self.tiling_layer.remap_minimized(
                         window.window.clone(),
@@ -811,6 +908,12 @@ impl Workspace {
         }
 
         if !self.floating_layer.resize(focused, direction, edge, amount) {
+            // columns of the scrolling layer have a fixed width
+            if let KeyboardFocusTarget::Element(mapped) = focused {
+                if self.scrolling_layer.mapped().any(|(m, _)| m == mapped) {
+                    return false;
+                }
+            }
             self.tiling_layer.resize(focused, direction, edge, amount)
         } else {
             true
@@ -844,11 +947,9 @@ impl Workspace {
                 maximized_windows.push((window.clone(), ManagedLayer::Tiling, original_geometry));
             }
 
-            let focus_stack = self.focus_stack.get(seat);
             for window in floating_windows.into_iter() {
                 self.floating_layer.unmap(&window);
-                self.tiling_layer
-                    .map(window, Some(focus_stack.iter()), None)
+                self.map_tiled(window, Some(seat), None);
             }
             workspace_state.set_workspace_tiling_state(&self.handle, TilingState::TilingEnabled);
             self.tiling_enabled = true;
@@ -856,6 +957,7 @@ impl Workspace {
             for window in self
                 .tiling_layer
                 .mapped()
+                .chain(self.scrolling_layer.mapped())
                 .map(|(m, _)| m.clone())
                 .collect::<Vec<_>>()
                 .into_iter()
@@ -872,7 +974,9 @@ impl Workspace {
                         original_geometry,
                     ));
                 }
-                self.tiling_layer.unmap(&window);
+                if !self.tiling_layer.unmap(&window) {
+                    self.scrolling_layer.unmap(&window);
+                }
                 self.floating_layer.map(window, None);
             }
             workspace_state.set_workspace_tiling_state(&self.handle, TilingState::FloatingOnly);
@@ -896,14 +1000,16 @@ impl Workspace {
             if window.is_maximized(false) {
                 self.unmaximize_request(window);
             }
-            if self.tiling_layer.mapped().any(|(m, _)| m == window) {
-                self.tiling_layer.unmap(window);
+            if self.tiling_layer.mapped().any(|(m, _)| m == window)
+                || self.scrolling_layer.mapped().any(|(m, _)| m == window)
+            {
+                if !self.tiling_layer.unmap(window) {
+                    self.scrolling_layer.unmap(window);
+                }
                 self.floating_layer.map(window.clone(), None);
             } else if self.floating_layer.mapped().any(|w| w == window) {
-                let focus_stack = self.focus_stack.get(seat);
                 self.floating_layer.unmap(&window);
-                self.tiling_layer
-                    .map(window.clone(), Some(focus_stack.iter()), None)
+                self.map_tiled(window.clone(), Some(seat), None);
             }
         }
     }
@@ -919,6 +1025,7 @@ impl Workspace {
         self.floating_layer
             .mapped()
             .chain(self.tiling_layer.mapped().map(|(w, _)| w))
+            .chain(self.scrolling_layer.mapped().map(|(w, _)| w))
     }
 
     pub fn outputs(&self) -> impl Iterator<Item = &Output> {
@@ -928,6 +1035,7 @@ impl Workspace {
     pub fn is_empty(&self) -> bool {
         self.floating_layer.mapped().next().is_none()
             && self.tiling_layer.mapped().next().is_none()
+            && self.scrolling_layer.mapped().next().is_none()
             && self.minimized_windows.is_empty()
             && self.pending_tokens.is_empty()
     }
@@ -957,6 +1065,7 @@ impl Workspace {
     pub fn is_tiled(&self, mapped: &CosmicMapped) -> bool {
         !self.is_fullscreen(mapped)
             && (self.tiling_layer.mapped().any(|(m, _)| m == mapped)
+                || self.scrolling_layer.mapped().any(|(m, _)| m == mapped)
                 || self.minimized_windows.iter().any(|m| {
                     &m.window == mapped && matches!(m.previous_state, MinimizedState::Tiling { .. })
                 }))
@@ -1038,6 +1147,7 @@ impl Workspace {
                         self.floating_layer
                             .element_geometry(elem)
                             .or_else(|| self.tiling_layer.element_geometry(elem))
+                            .or_else(|| self.scrolling_layer.element_geometry(elem))
                             .map(|mut geo| {
                                 geo.loc -= elem.geometry().loc.as_local();
                                 geo
@@ -1167,6 +1277,18 @@ impl Workspace {
                 WorkspaceRenderElement::from,
             );
 
+            // scrolling surfaces
+            elements.extend_map(
+                self.scrolling_layer.render::<R>(
+                    renderer,
+                    focused.as_ref(),
+                    indicator_thickness,
+                    alpha,
+                    theme,
+                ),
+                WorkspaceRenderElement::from,
+            );
+
             let alpha = match &overview.0 {
                 OverviewMode::Started(_, start) => Some(
                     (Instant::now().duration_since(*start).as_millis() as f64 / 100.0).min(1.0)
@@ -2,6 +2,7 @@
 
 use crate::{
     backend::{
+        headless::HeadlessState,
         kms::KmsState,
         render::{GlMultiError, RendererRef},
         winit::WinitState,
@@ -10,19 +11,23 @@ use crate::{
     config::{Config, OutputConfig, OutputState},
     input::{gestures::GestureState, PointerFocusState},
     shell::{grabs::SeatMoveGrabState, CosmicSurface, SeatExt, Shell},
-    utils::prelude::OutputExt,
-    wayland::protocols::{
-        drm::WlDrmState,
-        image_source::ImageSourceState,
-        output_configuration::OutputConfigurationState,
-        screencopy::ScreencopyState,
-        toplevel_info::ToplevelInfoState,
-        toplevel_management::{ManagementCapabilities, ToplevelManagementState},
-        workspace::{WorkspaceClientState, WorkspaceState, WorkspaceUpdateGuard},
+    utils::{prelude::OutputExt, recording::Recording},
+    wayland::{
+        handlers::screencopy::ScreencopyConsent,
+        protocols::{
+            drm::WlDrmState,
+            image_source::ImageSourceState,
+            output_configuration::OutputConfigurationState,
+            screencopy::ScreencopyState,
+            toplevel_info::ToplevelInfoState,
+            toplevel_management::{ManagementCapabilities, ToplevelManagementState},
+            workspace::{WorkspaceClientState, WorkspaceState, WorkspaceUpdateGuard},
+        },
     },
     xwayland::XWaylandState,
 };
 use anyhow::Context;
+use cosmic_comp_config::security::Privilege;
 use i18n_embed::{
     fluent::{fluent_language_loader, FluentLanguageLoader},
     DesktopLanguageRequester,
@@ -109,8 +114,10 @@ use std::{
     cell::RefCell,
     collections::HashSet,
     ffi::OsString,
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
     process::Child,
-    sync::{atomic::AtomicBool, Arc, Mutex, Once, RwLock},
+    sync::{atomic::AtomicBool, Arc, Mutex, Once, OnceLock, RwLock},
     time::Duration,
 };
 
@@ -135,10 +142,22 @@ pub struct ClientState {
     pub compositor_client_state: CompositorClientState,
     pub workspace_client_state: WorkspaceClientState,
     pub advertised_drm_node: Option<DrmNode>,
-    pub privileged: bool,
+    pub privileges: HashSet<Privilege>,
+    /// Process id of the client, if it could be determined when connecting
+    pub pid: Option<i32>,
+    /// Path of the client's executable, see [`ClientState::executable`]
+    pub executable: OnceLock<Option<PathBuf>>,
     pub evls: LoopSignal,
     pub security_context: Option<SecurityContext>,
 }
+impl ClientState {
+    /// Path of the client's executable, looked up on first use
+    pub fn executable(&self) -> Option<&Path> {
+        self.executable
+            .get_or_init(|| self.pid.and_then(client_executable))
+            .as_deref()
+    }
+}
 impl ClientData for ClientState {
     fn initialized(&self, _client_id: ClientId) {}
     fn disconnected(&self, _client_id: ClientId, _reason: DisconnectReason) {
@@ -187,6 +206,10 @@ pub struct Common {
     pub should_stop: bool,
     pub local_offset: time::UtcOffset,
     pub gesture_state: Option<GestureState>,
+    pub lid_closed: bool,
+    pub lid_disabled_output: Option<String>,
+    pub screencopy_consent: ScreencopyConsent,
+    pub recording: Option<Recording>,
 
     pub kiosk_child: Option<Child>,
     pub theme: cosmic::Theme,
@@ -236,6 +259,7 @@ pub enum BackendData {
     X11(X11State),
     Winit(WinitState),
     Kms(KmsState),
+    Headless(HeadlessState),
     // TODO
     // Wayland(WaylandState),
     Unset,
@@ -281,6 +305,13 @@ impl BackendData {
         }
     }
 
+    pub fn headless(&mut self) -> &mut HeadlessState {
+        match self {
+            BackendData::Headless(ref mut headless_state) => headless_state,
+            _ => unreachable!("Called headless in non headless backend"),
+        }
+    }
+
     pub fn apply_config_for_outputs(
         &mut self,
         test_only: bool,
@@ -296,6 +327,7 @@ impl BackendData {
             }
             BackendData::Winit(ref mut state) => state.apply_config_for_outputs(test_only),
             BackendData::X11(ref mut state) => state.apply_config_for_outputs(test_only),
+            BackendData::Headless(ref mut state) => state.apply_config_for_outputs(test_only),
             _ => unreachable!("No backend set when applying output config"),
         }?;
 
@@ -328,6 +360,7 @@ impl BackendData {
             output.change_current_state(mode, transform, scale.map(Scale::Fractional), location);
 
             output.set_adaptive_sync(final_config.vrr);
+            output.set_mirroring_mode(final_config.mirroring_mode);
             output.set_mirroring(match &final_config.enabled {
                 OutputState::Mirroring(conn) => shell
                     .outputs()
@@ -370,6 +403,7 @@ impl BackendData {
             // Swapping with damage (which should be empty on these frames) is likely good enough anyway.
             BackendData::X11(ref mut state) => state.schedule_render(output),
             BackendData::Kms(ref mut state) => state.schedule_render(output),
+            BackendData::Headless(ref mut state) => state.schedule_render(output),
             _ => unreachable!("No backend was initialized"),
         }
     }
@@ -392,6 +426,9 @@ impl BackendData {
             BackendData::X11(ref mut state) => {
                 state.renderer.import_dmabuf(&dmabuf, None)?;
             }
+            BackendData::Headless(ref mut state) => {
+                state.renderer.import_dmabuf(&dmabuf, None)?;
+            }
             _ => unreachable!("No backend set when importing dmabuf"),
         };
         Ok(None)
@@ -425,6 +462,11 @@ impl BackendData {
             }
             BackendData::Winit(winit) => Ok(RendererRef::Glow(winit.backend.renderer())),
             BackendData::X11(x11) => Ok(RendererRef::Glow(&mut x11.renderer)),
+            BackendData::Headless(headless) => headless
+                .software_renderer
+                .as_mut()
+                .map(RendererRef::Glow)
+                .ok_or(GlMultiError::DeviceMissing),
             _ => unreachable!("No backend set when getting offscreen renderer"),
         }
     }
@@ -453,10 +495,21 @@ pub fn client_has_no_security_context(client: &Client) -> bool {
         .map_or(true, |client_state| client_state.security_context.is_none())
 }
 
-pub fn client_is_privileged(client: &Client) -> bool {
+pub fn client_has_privilege(client: &Client, privilege: Privilege) -> bool {
     client
         .get_data::<ClientState>()
-        .map_or(false, |client_state| client_state.privileged)
+        .map_or(false, |client_state| {
+            client_state.privileges.contains(&privilege)
+        })
+}
+
+fn client_pid(stream: &UnixStream) -> Option<i32> {
+    let cred = rustix::net::sockopt::get_socket_peercred(stream).ok()?;
+    Some(cred.pid.as_raw_nonzero().get())
+}
+
+fn client_executable(pid: i32) -> Option<PathBuf> {
+    std::fs::read_link(format!("/proc/{}/exe", pid)).ok()
 }
 
 fn enable_wayland_security() -> bool {
@@ -497,15 +550,23 @@ impl State {
         let data_device_state = DataDeviceState::new::<Self>(dh);
         let dmabuf_state = DmabufState::new();
         let foreign_toplevel_list =
-            ForeignToplevelListState::new_with_filter::<State>(dh, client_is_privileged);
+            ForeignToplevelListState::new_with_filter::<State>(dh, |client| {
+                client_has_privilege(client, Privilege::ToplevelManagement)
+            });
         let fractional_scale_state = FractionalScaleManagerState::new::<State>(dh);
         let keyboard_shortcuts_inhibit_state = KeyboardShortcutsInhibitState::new::<Self>(dh);
         let output_state = OutputManagerState::new_with_xdg_output::<Self>(dh);
-        let output_configuration_state = OutputConfigurationState::new(dh, client_is_privileged);
+        let output_configuration_state = OutputConfigurationState::new(dh, |client| {
+            client_has_privilege(client, Privilege::OutputConfiguration)
+        });
         let presentation_state = PresentationState::new::<Self>(dh, clock.id() as u32);
         let primary_selection_state = PrimarySelectionState::new::<Self>(dh);
-        let image_source_state = ImageSourceState::new::<Self, _>(dh, client_is_privileged);
-        let screencopy_state = ScreencopyState::new::<Self, _>(dh, client_is_privileged);
+        let image_source_state = ImageSourceState::new::<Self, _>(dh, |client| {
+            client_has_privilege(client, Privilege::Screencopy)
+        });
+        let screencopy_state = ScreencopyState::new::<Self, _>(dh, |client| {
+            client_has_privilege(client, Privilege::Screencopy)
+        });
         let shm_state =
             ShmState::new::<Self>(dh, vec![wl_shm::Format::Xbgr8888, wl_shm::Format::Abgr8888]);
         let cursor_shape_manager_state = CursorShapeManagerState::new::<State>(dh);
@@ -514,17 +575,22 @@ impl State {
         let wl_drm_state = WlDrmState::<Option<DrmNode>>::default();
         let kde_decoration_state = KdeDecorationState::new::<Self>(&dh, Mode::Client);
         let xdg_decoration_state = XdgDecorationState::new::<Self>(&dh);
-        let session_lock_manager_state =
-            SessionLockManagerState::new::<Self, _>(&dh, client_is_privileged);
+        let session_lock_manager_state = SessionLockManagerState::new::<Self, _>(&dh, |client| {
+            client_has_privilege(client, Privilege::SessionLock)
+        });
         XWaylandKeyboardGrabState::new::<Self>(&dh);
         let xwayland_shell_state = XWaylandShellState::new::<Self>(&dh);
         PointerConstraintsState::new::<Self>(&dh);
         PointerGesturesState::new::<Self>(&dh);
         TabletManagerState::new::<Self>(&dh);
         SecurityContextState::new::<Self, _>(&dh, client_has_no_security_context);
-        InputMethodManagerState::new::<Self, _>(&dh, client_is_privileged);
+        InputMethodManagerState::new::<Self, _>(&dh, |client| {
+            client_has_privilege(client, Privilege::VirtualKeyboard)
+        });
         TextInputManagerState::new::<Self>(&dh);
-        VirtualKeyboardManagerState::new::<State, _>(&dh, client_is_privileged);
+        VirtualKeyboardManagerState::new::<State, _>(&dh, |client| {
+            client_has_privilege(client, Privilege::VirtualKeyboard)
+        });
         AlphaModifierState::new::<Self>(&dh);
         SinglePixelBufferState::new::<Self>(&dh);
 
@@ -540,8 +606,9 @@ impl State {
 
         let shell = Arc::new(RwLock::new(Shell::new(&config)));
 
-        let layer_shell_state =
-            WlrLayerShellState::new_with_filter::<State, _>(dh, client_is_privileged);
+        let layer_shell_state = WlrLayerShellState::new_with_filter::<State, _>(dh, |client| {
+            client_has_privilege(client, Privilege::LayerShell)
+        });
         let xdg_shell_state = XdgShellState::new_with_capabilities::<State>(
             dh,
             [
@@ -553,7 +620,9 @@ impl State {
         );
         let xdg_activation_state = XdgActivationState::new::<State>(dh);
         let xdg_foreign_state = XdgForeignState::new::<State>(dh);
-        let toplevel_info_state = ToplevelInfoState::new(dh, client_is_privileged);
+        let toplevel_info_state = ToplevelInfoState::new(dh, |client| {
+            client_has_privilege(client, Privilege::ToplevelManagement)
+        });
         let toplevel_management_state = ToplevelManagementState::new::<State, _>(
             dh,
             vec![
@@ -563,9 +632,11 @@ impl State {
                 ManagementCapabilities::Minimize,
                 ManagementCapabilities::MoveToWorkspace,
             ],
-            client_is_privileged,
+            |client| client_has_privilege(client, Privilege::ToplevelManagement),
         );
-        let workspace_state = WorkspaceState::new(dh, client_is_privileged);
+        let workspace_state = WorkspaceState::new(dh, |client| {
+            client_has_privilege(client, Privilege::Workspaces)
+        });
 
         if let Err(err) = crate::dbus::init(&handle) {
             tracing::warn!(?err, "Failed to initialize dbus handlers");
@@ -588,6 +659,10 @@ impl State {
                 startup_done: Arc::new(AtomicBool::new(false)),
                 should_stop: false,
                 gesture_state: None,
+                lid_closed: false,
+                lid_disabled_output: None,
+                screencopy_consent: ScreencopyConsent::default(),
+                recording: None,
 
                 kiosk_child: None,
                 theme: cosmic::theme::system_preference(),
@@ -633,18 +708,54 @@ impl State {
         }
     }
 
-    pub fn new_client_state(&self) -> ClientState {
-        ClientState {
+    pub fn new_client_state(&self, stream: &UnixStream) -> ClientState {
+        let mut client_state = ClientState {
             compositor_client_state: CompositorClientState::default(),
             workspace_client_state: WorkspaceClientState::default(),
             advertised_drm_node: match &self.backend {
                 BackendData::Kms(kms_state) => kms_state.primary_node,
                 _ => None,
             },
-            privileged: !enable_wayland_security(),
+            privileges: HashSet::new(),
+            pid: client_pid(stream),
+            executable: OnceLock::new(),
             evls: self.common.event_loop_signal.clone(),
             security_context: None,
+        };
+        client_state.privileges = self.client_privileges(&client_state, None);
+        client_state
+    }
+
+    /// Privileges a newly connected client is granted by the configured security rules.
+    ///
+    /// Clients without a security context get all privileges, unless
+    /// `COSMIC_ENABLE_WAYLAND_SECURITY` is set.
+    pub fn client_privileges(
+        &self,
+        client: &ClientState,
+        security_context: Option<&SecurityContext>,
+    ) -> HashSet<Privilege> {
+        let mut privileges = if security_context.is_none() && !enable_wayland_security() {
+            Privilege::ALL.into_iter().collect()
+        } else {
+            HashSet::new()
+        };
+
+        for rule in &self.common.config.cosmic_conf.security_rules {
+            if rule.matches(
+                security_context.and_then(|context| context.sandbox_engine.as_deref()),
+                security_context.and_then(|context| context.app_id.as_deref()),
+                // only hit procfs for rules that care about the executable
+                rule.executable
+                    .is_some()
+                    .then(|| client.executable())
+                    .flatten(),
+            ) {
+                privileges.extend(rule.privileges.iter().copied());
+            }
         }
+
+        privileges
     }
 }
 
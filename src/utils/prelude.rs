@@ -4,7 +4,7 @@ use smithay::{
 };
 
 pub use super::geometry::*;
-use crate::config::{OutputConfig, OutputState};
+use crate::config::{MirroringMode, OutputConfig, OutputState};
 pub use crate::shell::{SeatExt, Shell, Workspace};
 pub use crate::state::{Common, State};
 pub use crate::wayland::handlers::xdg_shell::popup::update_reactive_popups;
@@ -23,6 +23,10 @@ pub trait OutputExt {
     fn set_adaptive_sync(&self, vrr: bool);
     fn mirroring(&self) -> Option<Output>;
     fn set_mirroring(&self, output: Option<Output>);
+    fn mirroring_mode(&self) -> MirroringMode;
+    fn set_mirroring_mode(&self, mode: MirroringMode);
+    fn serial_number(&self) -> Option<String>;
+    fn set_serial_number(&self, serial: String);
 
     fn is_enabled(&self) -> bool;
     fn config(&self) -> Ref<'_, OutputConfig>;
@@ -33,6 +37,10 @@ struct Vrr(AtomicBool);
 
 struct Mirroring(Mutex<Option<WeakOutput>>);
 
+struct MirroringModeData(Mutex<MirroringMode>);
+
+struct SerialNumber(String);
+
 impl OutputExt for Output {
     fn geometry(&self) -> Rectangle<i32, Global> {
         Rectangle::from_loc_and_size(self.current_location(), {
@@ -82,6 +90,33 @@ impl OutputExt for Output {
             output.map(|output| output.downgrade());
     }
 
+    fn mirroring_mode(&self) -> MirroringMode {
+        self.user_data()
+            .get::<MirroringModeData>()
+            .map(|mode| *mode.0.lock().unwrap())
+            .unwrap_or_default()
+    }
+    fn set_mirroring_mode(&self, mode: MirroringMode) {
+        let user_data = self.user_data();
+        user_data.insert_if_missing_threadsafe(|| MirroringModeData(Mutex::new(mode)));
+        *user_data
+            .get::<MirroringModeData>()
+            .unwrap()
+            .0
+            .lock()
+            .unwrap() = mode;
+    }
+
+    fn serial_number(&self) -> Option<String> {
+        self.user_data()
+            .get::<SerialNumber>()
+            .map(|serial| serial.0.clone())
+    }
+    fn set_serial_number(&self, serial: String) {
+        self.user_data()
+            .insert_if_missing_threadsafe(|| SerialNumber(serial));
+    }
+
     fn is_enabled(&self) -> bool {
         self.user_data()
             .get::<RefCell<OutputConfig>>()
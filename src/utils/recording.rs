@@ -0,0 +1,464 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError},
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+use calloop::{
+    timer::{TimeoutAction, Timer},
+    RegistrationToken,
+};
+use smithay::{
+    backend::{
+        allocator::{dmabuf::Dmabuf, Fourcc},
+        renderer::{
+            damage::{Error as DTError, OutputDamageTracker},
+            element::RenderElement,
+            gles::GlesRenderbuffer,
+            Bind, ExportMem, ImportAll, ImportMem, Offscreen, Renderer,
+        },
+    },
+    desktop::space::SpaceElement,
+    input::Seat,
+    output::Output,
+    utils::{Buffer as BufferCoords, IsAlive, Physical, Rectangle, Size, Transform},
+    wayland::seat::WaylandFocus,
+};
+use tracing::{info, warn};
+
+use crate::{
+    backend::render::{
+        element::{AsGlowRenderer, CosmicElement, FromGlesError},
+        render_workspace, CursorMode, ElementFilter, RendererRef, CLEAR_COLOR,
+    },
+    config::RecordingTarget,
+    shell::{
+        element::CosmicSurface, focus::target::KeyboardFocusTarget, CosmicMappedRenderElement,
+        WorkspaceRenderElement,
+    },
+    state::advertised_node_for_surface,
+    utils::{prelude::*, screenshot},
+    wayland::{
+        handlers::screencopy::{window_capture_elements, WindowCaptureElement},
+        protocols::workspace::WorkspaceHandle,
+    },
+};
+
+/// Frames waiting for the encoder, before new ones get dropped
+const QUEUED_FRAMES: usize = 8;
+
+/// A running recording started by [`CompAction::ToggleRecording`](crate::config::CompAction)
+#[derive(Debug)]
+pub struct Recording {
+    token: RegistrationToken,
+    writer: JoinHandle<()>,
+}
+
+enum Source {
+    /// Whatever workspace is active on the output
+    Output(Output),
+    Workspace(WorkspaceHandle),
+    Window(CosmicSurface),
+}
+
+/// Renders the source, while the recording runs
+struct Recorder {
+    source: Source,
+    size: Size<i32, Physical>,
+    damage_tracker: OutputDamageTracker,
+    /// Keeps the previous frame, so damage tracking can skip unchanged frames
+    buffer: Option<GlesRenderbuffer>,
+    frames: SyncSender<(Vec<u8>, Instant)>,
+    /// Frames skipped, because the encoder couldn't keep up
+    dropped_frames: usize,
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        if self.dropped_frames > 0 {
+            warn!(
+                dropped_frames = self.dropped_frames,
+                "Encoding couldn't keep up with the recording"
+            );
+        }
+    }
+}
+
+impl State {
+    pub fn toggle_recording(&mut self, target: RecordingTarget, seat: &Seat<State>) {
+        if let Some(recording) = self.common.recording.take() {
+            // dropping the recorder finishes the file
+            self.common.event_loop_handle.remove(recording.token);
+            self.update_capture_indicator();
+            return;
+        }
+
+        if let Err(err) = self.start_recording(target, seat) {
+            warn!(?err, "Failed to start recording");
+        }
+    }
+
+    /// Stops a running recording and waits for its file to be written, used when shutting down
+    pub fn finish_recording(&mut self) {
+        if let Some(recording) = self.common.recording.take() {
+            self.common.event_loop_handle.remove(recording.token);
+            let _ = recording.writer.join();
+        }
+    }
+
+    fn start_recording(
+        &mut self,
+        target: RecordingTarget,
+        seat: &Seat<State>,
+    ) -> anyhow::Result<()> {
+        let output = seat.active_output();
+        let (source, name, size, scale) = match target {
+            RecordingTarget::Output => {
+                let size = output_size(&output);
+                let scale = output.current_scale().fractional_scale();
+                (Source::Output(output.clone()), output.name(), size, scale)
+            }
+            RecordingTarget::Workspace => {
+                let shell = self.common.shell.read().unwrap();
+                let workspace = shell.active_space(&output);
+                let (_, idx) = shell.workspaces.active_num(&output);
+                let name = workspace
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| format!("{}_workspace_{}", output.name(), idx + 1));
+                let handle = workspace.handle;
+                std::mem::drop(shell);
+                let size = output_size(&output);
+                let scale = output.current_scale().fractional_scale();
+                (Source::Workspace(handle), name, size, scale)
+            }
+            RecordingTarget::Window => {
+                let Some(KeyboardFocusTarget::Element(mapped)) =
+                    seat.get_keyboard().unwrap().current_focus()
+                else {
+                    anyhow::bail!("No window focused");
+                };
+                let window = mapped.active_window();
+                // the canvas keeps the size the window had when the recording started
+                let size = window.geometry().size.to_physical(1);
+                (Source::Window(window.clone()), window.title(), size, 1.0)
+            }
+        };
+        if size.w <= 0 || size.h <= 0 {
+            anyhow::bail!("Nothing to record");
+        }
+
+        let config = &self.common.config.cosmic_conf.recording;
+        let directory = match config.directory.as_ref() {
+            Some(directory) => PathBuf::from(directory),
+            None => xdg_user::videos()
+                .ok()
+                .flatten()
+                .context("No videos directory")?,
+        };
+        let path = directory.join(screenshot::file_name(
+            &config.filename_template,
+            &name,
+            &self.common.local_offset,
+        ));
+        let interval = Duration::from_secs_f64(1.0 / config.frame_rate.clamp(1, 240) as f64);
+
+        let (tx, rx) = sync_channel(QUEUED_FRAMES);
+        let buffer_size = size.to_logical(1).to_buffer(1, Transform::Normal);
+        let writer = std::thread::Builder::new()
+            .name("recording".into())
+            .spawn(move || {
+                if let Err(err) = write_recording(rx, &path, buffer_size) {
+                    warn!(?err, path = %path.display(), "Failed to write recording");
+                } else {
+                    info!(path = %path.display(), "Recording saved");
+                }
+            })?;
+
+        let mut recorder = Recorder {
+            source,
+            size,
+            damage_tracker: OutputDamageTracker::new(size, scale, Transform::Normal),
+            buffer: None,
+            frames: tx,
+            dropped_frames: 0,
+        };
+        let mut deadline = Instant::now();
+        let token = self
+            .common
+            .event_loop_handle
+            .insert_source(Timer::immediate(), move |_, _, state| {
+                if let Err(err) = recorder.record_frame(state) {
+                    warn!(?err, "Stopped recording");
+                    state.common.recording = None;
+                    state.update_capture_indicator();
+                    return TimeoutAction::Drop;
+                }
+
+                // keep a steady rate, even if rendering a frame takes a while
+                deadline += interval;
+                let now = Instant::now();
+                if deadline < now {
+                    deadline = now;
+                }
+                TimeoutAction::ToInstant(deadline)
+            })
+            .map_err(|err| anyhow::anyhow!("Failed to schedule recording: {}", err))?;
+
+        self.common.recording = Some(Recording { token, writer });
+        self.update_capture_indicator();
+        Ok(())
+    }
+}
+
+fn output_size(output: &Output) -> Size<i32, Physical> {
+    // recordings are upright, regardless of the output's transform
+    output
+        .geometry()
+        .size
+        .as_logical()
+        .to_physical_precise_round(output.current_scale().fractional_scale())
+}
+
+impl Recorder {
+    /// Renders the source and hands the frame to the encoder, if anything changed
+    fn record_frame(&mut self, state: &mut State) -> anyhow::Result<()> {
+        let shell = state.common.shell.read().unwrap();
+        let workspace = match &self.source {
+            Source::Output(output) => {
+                if !output.is_enabled() {
+                    anyhow::bail!("Recorded output was disabled");
+                }
+                let (_, workspace) = shell.workspaces.active(output);
+                let (_, idx) = shell.workspaces.active_num(output);
+                Some((output.clone(), (workspace.handle, idx)))
+            }
+            Source::Workspace(handle) => {
+                let workspace = shell
+                    .workspaces
+                    .space_for_handle(handle)
+                    .context("Recorded workspace was removed")?;
+                let output = workspace.output().clone();
+                let idx = shell.workspaces.idx_for_handle(&output, handle).unwrap();
+                Some((output, (*handle, idx)))
+            }
+            Source::Window(window) => {
+                if !window.alive() {
+                    anyhow::bail!("Recorded window was closed");
+                }
+                None
+            }
+        };
+        std::mem::drop(shell);
+
+        if let Some((output, _)) = workspace.as_ref() {
+            if output_size(output) != self.size {
+                anyhow::bail!("Size of the recorded output changed");
+            }
+        }
+
+        let common = &state.common;
+        let renderer = state.backend.offscreen_renderer(|kms| match &workspace {
+            Some((output, _)) => kms.target_node_for_output(output).or(kms.primary_node),
+            None => match &self.source {
+                Source::Window(window) => window
+                    .wl_surface()
+                    .and_then(|surface| {
+                        advertised_node_for_surface(&surface, &common.display_handle)
+                    })
+                    .or(kms.primary_node),
+                _ => kms.primary_node,
+            },
+        })?;
+
+        let frame = match renderer {
+            RendererRef::Glow(renderer) => self.render(renderer, common, workspace),
+            RendererRef::GlMulti(mut renderer) => self.render(&mut renderer, common, workspace),
+        }?;
+        if let Some(data) = frame {
+            match self.frames.try_send((data, Instant::now())) {
+                // the previous frame is just shown for longer
+                Err(TrySendError::Full(_)) => self.dropped_frames += 1,
+                // the encoder only goes away, if it failed and already logged why
+                Ok(()) | Err(TrySendError::Disconnected(_)) => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn render<R>(
+        &mut self,
+        renderer: &mut R,
+        common: &Common,
+        workspace: Option<(Output, (WorkspaceHandle, usize))>,
+    ) -> anyhow::Result<Option<Vec<u8>>>
+    where
+        R: Renderer
+            + ImportAll
+            + ImportMem
+            + ExportMem
+            + Bind<Dmabuf>
+            + Offscreen<GlesRenderbuffer>
+            + AsGlowRenderer,
+        <R as Renderer>::TextureId: Send + Clone + 'static,
+        <R as Renderer>::Error: FromGlesError + Send + Sync + 'static,
+        CosmicElement<R>: RenderElement<R>,
+        CosmicMappedRenderElement<R>: RenderElement<R>,
+        WorkspaceRenderElement<R>: RenderElement<R>,
+        WindowCaptureElement<R>: RenderElement<R>,
+    {
+        let format = Fourcc::Abgr8888;
+        let buffer_size = self.size.to_logical(1).to_buffer(1, Transform::Normal);
+        let (buffer, age) = match self.buffer.as_ref() {
+            Some(buffer) => (buffer.clone(), 1),
+            None => {
+                let buffer =
+                    Offscreen::<GlesRenderbuffer>::create_buffer(renderer, format, buffer_size)?;
+                self.buffer = Some(buffer.clone());
+                (buffer, 0)
+            }
+        };
+
+        let res = match (workspace, &self.source) {
+            (Some((output, handle)), _) => render_workspace::<_, _, GlesRenderbuffer>(
+                None,
+                renderer,
+                buffer,
+                &mut self.damage_tracker,
+                age,
+                None,
+                &common.shell,
+                common.clock.now(),
+                &output,
+                None,
+                handle,
+                CursorMode::All,
+                ElementFilter::ExcludeWorkspaceOverview,
+            )
+            .map(|res| res.0),
+            (None, Source::Window(window)) => {
+                let elements =
+                    window_capture_elements(renderer, common, window, window.geometry(), true);
+                renderer.bind(buffer)?;
+                self.damage_tracker
+                    .render_output(renderer, age, &elements, CLEAR_COLOR)
+            }
+            (None, _) => unreachable!(),
+        };
+        let res = res.map_err(|err| match err {
+            DTError::Rendering(err) => anyhow::Error::new(err),
+            DTError::OutputNoMode(_) => anyhow::anyhow!("Output has no mode"),
+        })?;
+
+        if res.damage.map_or(true, |damage| damage.is_empty()) {
+            return Ok(None);
+        }
+        renderer.wait(&res.sync)?;
+
+        let mapping =
+            renderer.copy_framebuffer(Rectangle::from_loc_and_size((0, 0), buffer_size), format)?;
+        let data = renderer.map_texture(&mapping)?;
+        Ok(Some(data.to_vec()))
+    }
+}
+
+/// Collects frames until the recording stops and writes them as an animated png.
+///
+/// The number of frames has to be known upfront, so they are spooled to a
+/// temporary file next to the recording first.
+fn write_recording(
+    frames: Receiver<(Vec<u8>, Instant)>,
+    path: &Path,
+    size: Size<i32, BufferCoords>,
+) -> anyhow::Result<()> {
+    let spool_path = path.with_extension("part");
+    let res = spool_frames(frames, &spool_path, size).and_then(|delays| {
+        if delays.is_empty() {
+            return Ok(());
+        }
+        assemble_apng(&spool_path, path, size, &delays)
+    });
+    let _ = std::fs::remove_file(&spool_path);
+    res
+}
+
+/// Writes every frame as a length prefixed png, returns how long each one is shown
+fn spool_frames(
+    frames: Receiver<(Vec<u8>, Instant)>,
+    spool_path: &Path,
+    size: Size<i32, BufferCoords>,
+) -> anyhow::Result<Vec<Duration>> {
+    let mut spool = BufWriter::new(
+        File::create(spool_path)
+            .with_context(|| format!("Failed to create {}", spool_path.display()))?,
+    );
+    let mut delays = Vec::new();
+    let mut previous = None;
+    for (data, time) in frames {
+        if let Some(previous) = previous.replace(time) {
+            delays.push(time.duration_since(previous));
+        }
+
+        let mut png = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut png, size.w as u32, size.h as u32);
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+            encoder.set_compression(png::Compression::Fast);
+            encoder.write_header()?.write_image_data(&data)?;
+        }
+        spool.write_all(&(png.len() as u64).to_le_bytes())?;
+        spool.write_all(&png)?;
+    }
+    // the channel closes, when the recording is stopped
+    if let Some(previous) = previous {
+        delays.push(Instant::now().duration_since(previous));
+    }
+    spool.flush()?;
+
+    Ok(delays)
+}
+
+fn assemble_apng(
+    spool_path: &Path,
+    path: &Path,
+    size: Size<i32, BufferCoords>,
+    delays: &[Duration],
+) -> anyhow::Result<()> {
+    let mut spool = BufReader::new(File::open(spool_path)?);
+    let file =
+        File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+
+    let mut encoder = png::Encoder::new(BufWriter::new(file), size.w as u32, size.h as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_animated(delays.len() as u32, 0)?;
+    let mut writer = encoder.write_header()?;
+
+    let mut image = Vec::new();
+    for delay in delays {
+        let mut len = [0u8; 8];
+        spool.read_exact(&mut len)?;
+        let mut png = vec![0u8; u64::from_le_bytes(len) as usize];
+        spool.read_exact(&mut png)?;
+
+        let mut reader = png::Decoder::new(png.as_slice()).read_info()?;
+        image.resize(reader.output_buffer_size(), 0);
+        reader.next_frame(&mut image)?;
+
+        // the delay is stored in milliseconds, longer pauses get shortened
+        let delay = delay.as_millis().min(u16::MAX as u128) as u16;
+        writer.set_frame_delay(delay, 1000)?;
+        writer.write_image_data(&image)?;
+    }
+    writer.finish()?;
+
+    Ok(())
+}
@@ -1,32 +1,52 @@
+use std::{cell::Cell, path::PathBuf, rc::Rc, sync::Arc};
+
 use anyhow::Context;
+use calloop::channel::{channel, Event as ChannelEvent};
+use cosmic_comp_config::ScreenshotConfig;
 use smithay::{
     backend::{
-        allocator::Fourcc,
+        allocator::{dmabuf::Dmabuf, Fourcc},
         renderer::{
             damage::OutputDamageTracker,
-            element::{surface::WaylandSurfaceRenderElement, AsRenderElements},
+            element::{surface::WaylandSurfaceRenderElement, AsRenderElements, RenderElement},
             gles::GlesRenderbuffer,
-            ExportMem, ImportAll, Offscreen, Renderer,
+            Bind, ExportMem, ImportAll, ImportMem, Offscreen, Renderer,
         },
     },
     desktop::utils::bbox_from_surface_tree,
-    utils::{Scale, Transform},
-    wayland::seat::WaylandFocus,
+    input::Seat,
+    output::Output,
+    utils::{Buffer as BufferCoords, Rectangle, Scale, Size, Transform},
+    wayland::{seat::WaylandFocus, selection::data_device::set_data_device_selection},
 };
 use tracing::warn;
 
 use crate::{
-    backend::render::RendererRef,
-    shell::element::CosmicSurface,
-    state::{advertised_node_for_surface, State},
+    backend::render::{
+        element::{AsGlowRenderer, CosmicElement, FromGlesError},
+        render_workspace, CursorMode, ElementFilter, RendererRef,
+    },
+    shell::{element::CosmicSurface, CosmicMappedRenderElement, WorkspaceRenderElement},
+    state::{advertised_node_for_surface, Common, State},
+    utils::prelude::*,
+    wayland::handlers::selection::SelectionData,
 };
 
+/// Rendered image in 8-bit RGBA
+struct Image {
+    size: Size<i32, BufferCoords>,
+    data: Vec<u8>,
+}
+
+/// Image of an output, rendered at its scale
+struct Capture {
+    geometry: Rectangle<i32, Global>,
+    scale: f64,
+    image: Image,
+}
+
 pub fn screenshot_window(state: &mut State, surface: &CosmicSurface) {
-    fn render_window<R>(
-        renderer: &mut R,
-        window: &CosmicSurface,
-        offset: &time::UtcOffset,
-    ) -> anyhow::Result<()>
+    fn render_window<R>(renderer: &mut R, window: &CosmicSurface) -> anyhow::Result<Image>
     where
         R: Renderer + ImportAll + Offscreen<GlesRenderbuffer> + ExportMem,
         <R as Renderer>::TextureId: Clone + 'static,
@@ -61,39 +81,10 @@ pub fn screenshot_window(state: &mut State, surface: &CosmicSurface) {
             renderer.copy_framebuffer(bbox.to_buffer(1, Transform::Normal, &bbox.size), format)?;
         let gl_data = renderer.map_texture(&mapping)?;
 
-        if let Ok(Some(path)) = xdg_user::pictures() {
-            let local_timestamp = time::OffsetDateTime::now_utc().to_offset(*offset);
-            let mut title = window.title();
-            title.truncate(227); // 255 - time - png
-            let name = sanitize_filename::sanitize(format!(
-                "{}_{}.png",
-                title,
-                local_timestamp
-                    .format(time::macros::format_description!(
-                        "[year]-[month]-[day]_[hour]:[minute]:[second]_[subsecond digits:4]"
-                    ))
-                    .unwrap(),
-            ));
-            let file = std::fs::File::create(path.join(name))?;
-
-            let ref mut writer = std::io::BufWriter::new(file);
-            let mut encoder = png::Encoder::new(writer, bbox.size.w as u32, bbox.size.h as u32);
-            encoder.set_color(png::ColorType::Rgba);
-            encoder.set_depth(png::BitDepth::Eight);
-            encoder.set_source_gamma(png::ScaledFloat::new(1.0 / 2.2)); // 1.0 / 2.2, unscaled, but rounded
-            let source_chromaticities = png::SourceChromaticities::new(
-                // Using unscaled instantiation here
-                (0.31270, 0.32900),
-                (0.64000, 0.33000),
-                (0.30000, 0.60000),
-                (0.15000, 0.06000),
-            );
-            encoder.set_source_chromaticities(source_chromaticities);
-            let mut writer = encoder.write_header()?;
-            writer.write_image_data(&gl_data)?;
-        }
-
-        Ok(())
+        Ok(Image {
+            size: bbox.size.to_buffer(1, Transform::Normal),
+            data: gl_data.to_vec(),
+        })
     }
 
     if let Some(wl_surface) = surface.wl_surface() {
@@ -105,15 +96,403 @@ pub fn screenshot_window(state: &mut State, surface: &CosmicSurface) {
             })
             .with_context(|| "Failed to get renderer for screenshot")
             .and_then(|renderer| match renderer {
-                RendererRef::Glow(renderer) => {
-                    render_window(renderer, surface, &state.common.local_offset)
+                RendererRef::Glow(renderer) => render_window(renderer, surface),
+                RendererRef::GlMulti(mut renderer) => render_window(&mut renderer, surface),
+            });
+        match res {
+            Ok(image) => save_screenshot(state, &surface.title(), None, move || image),
+            Err(err) => warn!(?err, "Failed to take screenshot"),
+        }
+    }
+}
+
+/// Takes a screenshot of everything visible inside of `region`, which may span multiple outputs.
+///
+/// Outputs are rendered at their own scale and combined at the highest scale among them.
+pub fn screenshot_region(
+    state: &mut State,
+    region: Rectangle<i32, Global>,
+    name: &str,
+    clipboard: bool,
+    seat: &Seat<State>,
+) {
+    let outputs = state
+        .common
+        .shell
+        .read()
+        .unwrap()
+        .outputs()
+        .filter(|output| output.geometry().overlaps(region))
+        .cloned()
+        .collect::<Vec<_>>();
+    if outputs.is_empty() || region.size.w <= 0 || region.size.h <= 0 {
+        return;
+    }
+
+    let mut captures = Vec::with_capacity(outputs.len());
+    for output in outputs {
+        let common = &state.common;
+        let res = state
+            .backend
+            .offscreen_renderer(|kms| kms.target_node_for_output(&output).or(kms.primary_node))
+            .with_context(|| "Failed to get renderer for screenshot")
+            .and_then(|renderer| match renderer {
+                RendererRef::Glow(renderer) => render_output(renderer, common, &output),
+                RendererRef::GlMulti(mut renderer) => render_output(&mut renderer, common, &output),
+            });
+        match res {
+            Ok(image) => captures.push(Capture {
+                geometry: output.geometry(),
+                scale: output.current_scale().fractional_scale(),
+                image,
+            }),
+            Err(err) => {
+                warn!(?err, output = output.name(), "Failed to take screenshot");
+                return;
+            }
+        }
+    }
+
+    let clipboard = clipboard.then_some(seat);
+    save_screenshot(state, name, clipboard, move || compose(region, &captures));
+}
+
+fn render_output<R>(renderer: &mut R, common: &Common, output: &Output) -> anyhow::Result<Image>
+where
+    R: Renderer
+        + ImportAll
+        + ImportMem
+        + ExportMem
+        + Bind<Dmabuf>
+        + Offscreen<GlesRenderbuffer>
+        + AsGlowRenderer,
+    <R as Renderer>::TextureId: Send + Clone + 'static,
+    <R as Renderer>::Error: FromGlesError + Send + Sync + 'static,
+    CosmicElement<R>: RenderElement<R>,
+    CosmicMappedRenderElement<R>: RenderElement<R>,
+    WorkspaceRenderElement<R>: RenderElement<R>,
+{
+    let shell = common.shell.read().unwrap();
+    let (_, workspace) = shell.workspaces.active(output);
+    let (_, idx) = shell.workspaces.active_num(output);
+    let workspace = (workspace.handle, idx);
+    std::mem::drop(shell);
+
+    // render upright, regardless of the output's transform
+    let scale = output.current_scale().fractional_scale();
+    let size = output
+        .geometry()
+        .size
+        .as_logical()
+        .to_physical_precise_round(scale);
+    let buffer_size = size.to_logical(1).to_buffer(1, Transform::Normal);
+
+    let format = Fourcc::Abgr8888;
+    let render_buffer =
+        Offscreen::<GlesRenderbuffer>::create_buffer(renderer, format, buffer_size)?;
+    let mut damage_tracker = OutputDamageTracker::new(size, scale, Transform::Normal);
+    render_workspace::<_, _, GlesRenderbuffer>(
+        None,
+        renderer,
+        render_buffer,
+        &mut damage_tracker,
+        0,
+        None,
+        &common.shell,
+        common.clock.now(),
+        output,
+        None,
+        workspace,
+        CursorMode::None,
+        ElementFilter::ExcludeWorkspaceOverview,
+    )
+    .map_err(|err| match err {
+        smithay::backend::renderer::damage::Error::Rendering(err) => anyhow::Error::new(err),
+        smithay::backend::renderer::damage::Error::OutputNoMode(_) => {
+            anyhow::anyhow!("Output has no mode")
+        }
+    })?;
+
+    let mapping =
+        renderer.copy_framebuffer(Rectangle::from_loc_and_size((0, 0), buffer_size), format)?;
+    let data = renderer.map_texture(&mapping)?;
+
+    Ok(Image {
+        size: buffer_size,
+        data: data.to_vec(),
+    })
+}
+
+/// Copies the parts of the output images inside of `region` into one image
+fn compose(region: Rectangle<i32, Global>, captures: &[Capture]) -> Image {
+    let scale = captures
+        .iter()
+        .map(|capture| capture.scale)
+        .fold(1.0, f64::max);
+    let size = Size::<i32, BufferCoords>::from((
+        (region.size.w as f64 * scale).round() as i32,
+        (region.size.h as f64 * scale).round() as i32,
+    ));
+    let mut data = vec![0u8; size.w as usize * size.h as usize * 4];
+
+    for capture in captures {
+        let (geometry, image) = (capture.geometry, &capture.image);
+        let Some(area) = geometry.intersection(region) else {
+            continue;
+        };
+        let output_scale = image.size.w as f64 / geometry.size.w as f64;
+
+        let x_range = ((area.loc.x - region.loc.x) as f64 * scale).round() as i32
+            ..((area.loc.x + area.size.w - region.loc.x) as f64 * scale).round() as i32;
+        let y_range = ((area.loc.y - region.loc.y) as f64 * scale).round() as i32
+            ..((area.loc.y + area.size.h - region.loc.y) as f64 * scale).round() as i32;
+        for y in y_range {
+            let src_y =
+                (((region.loc.y - geometry.loc.y) as f64 + y as f64 / scale) * output_scale) as i32;
+            let src_y = src_y.clamp(0, image.size.h - 1) as usize;
+            for x in x_range.clone() {
+                let src_x = (((region.loc.x - geometry.loc.x) as f64 + x as f64 / scale)
+                    * output_scale) as i32;
+                let src_x = src_x.clamp(0, image.size.w - 1) as usize;
+
+                let src = (src_y * image.size.w as usize + src_x) * 4;
+                let dst = (y as usize * size.w as usize + x as usize) * 4;
+                data[dst..dst + 4].copy_from_slice(&image.data[src..src + 4]);
+            }
+        }
+    }
+
+    Image { size, data }
+}
+
+fn screenshot_path(
+    config: &ScreenshotConfig,
+    name: &str,
+    offset: &time::UtcOffset,
+) -> Option<PathBuf> {
+    let directory = match config.directory.as_ref() {
+        Some(directory) => PathBuf::from(directory),
+        None => xdg_user::pictures().ok().flatten()?,
+    };
+
+    Some(directory.join(file_name(&config.filename_template, name, offset)))
+}
+
+/// Fills in the `{name}` and `{timestamp}` placeholders of a filename template
+pub fn file_name(template: &str, name: &str, offset: &time::UtcOffset) -> String {
+    let local_timestamp = time::OffsetDateTime::now_utc().to_offset(*offset);
+    let timestamp = local_timestamp
+        .format(time::macros::format_description!(
+            "[year]-[month]-[day]_[hour]:[minute]:[second]_[subsecond digits:4]"
+        ))
+        .unwrap();
+    let mut name = name.to_string();
+    // 255 - time - png, without splitting a character
+    let mut len = name.len().min(227);
+    while !name.is_char_boundary(len) {
+        len -= 1;
+    }
+    name.truncate(len);
+    let file_name = template
+        .replace("{name}", &name)
+        .replace("{timestamp}", &timestamp);
+
+    sanitize_filename::sanitize(file_name)
+}
+
+/// Composes, encodes and saves the image on a separate thread.
+///
+/// If a seat is given, the png is put into its clipboard once it is done.
+fn save_screenshot(
+    state: &State,
+    name: &str,
+    clipboard: Option<&Seat<State>>,
+    image: impl FnOnce() -> Image + Send + 'static,
+) {
+    let config = state.common.config.cosmic_conf.screenshot.clone();
+    let offset = state.common.local_offset;
+    let name = name.to_string();
+
+    let (tx, rx) = channel();
+    if let Some(seat) = clipboard.cloned() {
+        let token = Rc::new(Cell::new(None));
+        let token_clone = token.clone();
+        match state
+            .common
+            .event_loop_handle
+            .insert_source(rx, move |event, _, state| match event {
+                ChannelEvent::Msg(png) => set_data_device_selection(
+                    &state.common.display_handle,
+                    &seat,
+                    vec![String::from("image/png")],
+                    SelectionData::Image(Arc::new(png)),
+                ),
+                ChannelEvent::Closed => {
+                    if let Some(token) = token_clone.take() {
+                        state
+                            .common
+                            .event_loop_handle
+                            .insert_idle(move |state| state.common.event_loop_handle.remove(token));
+                    }
                 }
-                RendererRef::GlMulti(mut renderer) => {
-                    render_window(&mut renderer, surface, &state.common.local_offset)
+            }) {
+            Ok(registration) => token.set(Some(registration)),
+            Err(err) => warn!(?err, "Failed to copy screenshot to the clipboard"),
+        }
+    }
+
+    let res = std::thread::Builder::new()
+        .name("screenshot".into())
+        .spawn(move || {
+            let png = encode_png(&image()).and_then(|png| {
+                if let Some(path) = screenshot_path(&config, &name, &offset) {
+                    std::fs::write(&path, &png)
+                        .with_context(|| format!("Failed to write {}", path.display()))?;
                 }
+                Ok(png)
             });
-        if let Err(err) = res {
-            warn!(?err, "Failed to take screenshot")
+            match png {
+                // fails without a clipboard to copy to, which is fine
+                Ok(png) => {
+                    let _ = tx.send(png);
+                }
+                Err(err) => warn!(?err, "Failed to save screenshot"),
+            }
+        });
+    if let Err(err) = res {
+        warn!(?err, "Failed to save screenshot");
+    }
+}
+
+fn encode_png(image: &Image) -> anyhow::Result<Vec<u8>> {
+    let mut png = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut png, image.size.w as u32, image.size.h as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_source_gamma(png::ScaledFloat::new(1.0 / 2.2)); // 1.0 / 2.2, unscaled, but rounded
+        let source_chromaticities = png::SourceChromaticities::new(
+            // Using unscaled instantiation here
+            (0.31270, 0.32900),
+            (0.64000, 0.33000),
+            (0.30000, 0.60000),
+            (0.15000, 0.06000),
+        );
+        encoder.set_source_chromaticities(source_chromaticities);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&image.data)?;
+    }
+
+    Ok(png)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Image filled with one value per pixel, repeated over all channels
+    fn image(w: i32, h: i32, pixel: impl Fn(i32, i32) -> u8) -> Image {
+        let mut data = Vec::new();
+        for y in 0..h {
+            for x in 0..w {
+                data.extend_from_slice(&[pixel(x, y); 4]);
+            }
+        }
+        Image {
+            size: (w, h).into(),
+            data,
         }
     }
+
+    fn pixel(image: &Image, x: i32, y: i32) -> u8 {
+        image.data[((y * image.size.w + x) * 4) as usize]
+    }
+
+    fn capture(x: i32, y: i32, w: i32, h: i32, scale: f64, value: u8) -> Capture {
+        let size = (w as f64 * scale).round() as i32;
+        let height = (h as f64 * scale).round() as i32;
+        Capture {
+            geometry: Rectangle::from_loc_and_size((x, y), (w, h)),
+            scale,
+            image: image(size, height, |_, _| value),
+        }
+    }
+
+    #[test]
+    fn test_compose_crops_to_region() {
+        let captures = [Capture {
+            geometry: Rectangle::from_loc_and_size((0, 0), (4, 4)),
+            scale: 1.0,
+            image: image(4, 4, |x, y| (y * 4 + x) as u8),
+        }];
+        let image = compose(Rectangle::from_loc_and_size((1, 2), (2, 2)), &captures);
+        assert_eq!(image.size, Size::from((2, 2)));
+        assert_eq!(pixel(&image, 0, 0), 9);
+        assert_eq!(pixel(&image, 1, 0), 10);
+        assert_eq!(pixel(&image, 0, 1), 13);
+        assert_eq!(pixel(&image, 1, 1), 14);
+    }
+
+    #[test]
+    fn test_compose_spanning_outputs() {
+        let captures = [capture(0, 0, 4, 4, 1.0, 1), capture(4, 0, 4, 4, 1.0, 2)];
+        let image = compose(Rectangle::from_loc_and_size((2, 0), (4, 2)), &captures);
+        assert_eq!(image.size, Size::from((4, 2)));
+        assert_eq!(pixel(&image, 0, 0), 1);
+        assert_eq!(pixel(&image, 1, 1), 1);
+        assert_eq!(pixel(&image, 2, 0), 2);
+        assert_eq!(pixel(&image, 3, 1), 2);
+    }
+
+    #[test]
+    fn test_compose_mixed_scales() {
+        // combined at the highest scale, the low dpi output gets upscaled
+        let captures = [capture(0, 0, 4, 4, 1.0, 1), capture(4, 0, 4, 4, 2.0, 2)];
+        let image = compose(Rectangle::from_loc_and_size((0, 0), (8, 4)), &captures);
+        assert_eq!(image.size, Size::from((16, 8)));
+        assert_eq!(pixel(&image, 7, 7), 1);
+        assert_eq!(pixel(&image, 8, 0), 2);
+        assert_eq!(pixel(&image, 15, 7), 2);
+    }
+
+    #[test]
+    fn test_compose_leaves_gaps_transparent() {
+        let captures = [capture(0, 0, 2, 2, 1.0, 255)];
+        let image = compose(Rectangle::from_loc_and_size((0, 0), (4, 2)), &captures);
+        assert_eq!(pixel(&image, 1, 1), 255);
+        assert_eq!(pixel(&image, 2, 0), 0);
+        assert_eq!(pixel(&image, 3, 1), 0);
+    }
+
+    #[test]
+    fn test_screenshot_path() {
+        let config = ScreenshotConfig {
+            directory: Some(String::from("/tmp/screenshots")),
+            filename_template: String::from("shot-{name}.png"),
+        };
+        assert_eq!(
+            screenshot_path(&config, "DP-1", &time::UtcOffset::UTC),
+            Some(PathBuf::from("/tmp/screenshots/shot-DP-1.png"))
+        );
+        // window titles may contain path separators
+        assert_eq!(
+            screenshot_path(&config, "a/b", &time::UtcOffset::UTC),
+            Some(PathBuf::from("/tmp/screenshots/shot-ab.png"))
+        );
+    }
+
+    #[test]
+    fn test_file_name_timestamp() {
+        let name = file_name("{name}_{timestamp}.png", "DP-1", &time::UtcOffset::UTC);
+        assert!(name.starts_with("DP-1_"));
+        assert!(name.ends_with(".png"));
+        assert!(!name.contains("{timestamp}"));
+    }
+
+    #[test]
+    fn test_file_name_truncates_on_char_boundary() {
+        // 'ä' takes two bytes, so byte 227 is in the middle of a character
+        let name = file_name("{name}", &"ä".repeat(200), &time::UtcOffset::UTC);
+        assert_eq!(name, "ä".repeat(113));
+    }
 }
@@ -93,6 +93,7 @@ impl State {
 
                 if let OutputConfiguration::Enabled {
                     mirroring,
+                    mirroring_mode,
                     mode,
                     scale,
                     transform,
@@ -122,6 +123,9 @@ impl State {
                     if let Some(vrr) = adaptive_sync {
                         current_config.vrr = *vrr;
                     }
+                    if let Some(mirroring_mode) = mirroring_mode {
+                        current_config.mirroring_mode = *mirroring_mode;
+                    }
                     if let Some(mirror) = mirroring {
                         current_config.enabled = OutputState::Mirroring(mirror.name());
                     } else {
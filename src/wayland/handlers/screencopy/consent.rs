@@ -0,0 +1,411 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{
+    cell::Cell,
+    collections::{BTreeSet, HashSet},
+    rc::Rc,
+    time::Duration,
+};
+
+use calloop::{
+    channel::{channel, Event as ChannelEvent},
+    timer::{TimeoutAction, Timer},
+};
+use serde::{Deserialize, Serialize};
+use smithay::{
+    input::{
+        pointer::{Focus, GrabStartData as PointerGrabStartData, PointerHandle},
+        Seat,
+    },
+    reexports::wayland_server::{backend::ClientId, Client},
+    utils::{IsAlive, SERIAL_COUNTER},
+};
+use tracing::{debug, warn};
+
+use crate::{
+    fl,
+    shell::grabs::{Item, MenuGrab},
+    state::{ClientState, State},
+    utils::prelude::*,
+    wayland::protocols::screencopy::{CursorSession, Frame, ScreencopyHandler, Session},
+};
+
+const BTN_LEFT: u32 = 0x110;
+/// Delay before trying to show a prompt again, while another pointer grab is active
+const PROMPT_RETRY: Duration = Duration::from_millis(500);
+
+/// Apps the user always allows to capture the screen
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ScreencopyPermissions {
+    pub allowed: BTreeSet<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Consent {
+    /// Allow until the client disconnects
+    AllowOnce,
+    AllowAlways,
+    Deny,
+    /// The prompt was dismissed, deny the pending sessions but ask again next time
+    DenyOnce,
+}
+
+/// Screencopy sessions held back until the user decides, and the ones already allowed
+#[derive(Debug, Default)]
+pub struct ScreencopyConsent {
+    pending: Vec<PendingSession>,
+    allowed_clients: Vec<ClientId>,
+    /// Clients and apps denied until the compositor restarts, so they can't keep prompting
+    denied_clients: Vec<ClientId>,
+    denied_apps: HashSet<String>,
+    /// Client whose prompt is shown right now, prompts of other clients wait until it closes
+    prompting: Option<ClientId>,
+    retry_scheduled: bool,
+    active: Vec<Session>,
+    active_cursors: Vec<CursorSession>,
+}
+
+#[derive(Debug)]
+struct PendingSession {
+    client: Client,
+    session: Session,
+    frames: Vec<Frame>,
+}
+
+impl ScreencopyConsent {
+    /// Whether any client is capturing the screen or cursor right now
+    pub fn capture_active(&self) -> bool {
+        self.active.iter().any(|session| session.alive())
+            || self.active_cursors.iter().any(|session| session.alive())
+    }
+}
+
+/// Identifies the app across connections, preferring the app id of a sandboxed client
+fn app_id(client: &Client) -> Option<String> {
+    let data = client.get_data::<ClientState>()?;
+    data.security_context
+        .as_ref()
+        .and_then(|context| context.app_id.clone())
+        .or_else(|| data.executable().map(|path| path.display().to_string()))
+}
+
+impl State {
+    /// Holds back sessions, that need the user's consent.
+    ///
+    /// Returns the session again, if it may start right away.
+    pub fn check_screencopy_consent(&mut self, session: Session) -> Option<Session> {
+        self.common
+            .screencopy_consent
+            .active
+            .retain(|session| session.alive());
+        if !self.common.config.cosmic_conf.screencopy_consent {
+            self.common.screencopy_consent.active.push(session.clone());
+            self.update_capture_indicator();
+            return Some(session);
+        }
+        let Some(client) = session.client() else {
+            session.stop();
+            return None;
+        };
+
+        let handle = self.common.display_handle.backend_handle();
+        let consent = &mut self.common.screencopy_consent;
+        consent
+            .allowed_clients
+            .retain(|id| handle.get_client_data(id.clone()).is_ok());
+        consent
+            .denied_clients
+            .retain(|id| handle.get_client_data(id.clone()).is_ok());
+
+        let app = app_id(&client);
+        if consent.denied_clients.contains(&client.id())
+            || app
+                .as_ref()
+                .is_some_and(|app| consent.denied_apps.contains(app))
+        {
+            session.stop();
+            return None;
+        }
+
+        let allowed = consent.allowed_clients.contains(&client.id())
+            || app.map_or(false, |app| {
+                self.common
+                    .config
+                    .dynamic_conf
+                    .screencopy()
+                    .allowed
+                    .contains(&app)
+            });
+        if allowed {
+            consent.active.push(session.clone());
+            self.update_capture_indicator();
+            return Some(session);
+        }
+
+        consent.pending.push(PendingSession {
+            client,
+            session,
+            frames: Vec::new(),
+        });
+        self.show_next_screencopy_prompt();
+
+        None
+    }
+
+    /// Keeps frames of sessions waiting for consent around, until they are allowed.
+    ///
+    /// Returns the frame again, if the session isn't waiting.
+    pub fn hold_screencopy_frame(&mut self, session: &Session, frame: Frame) -> Option<Frame> {
+        match self
+            .common
+            .screencopy_consent
+            .pending
+            .iter_mut()
+            .find(|pending| &pending.session == session)
+        {
+            Some(pending) => {
+                pending.frames.push(frame);
+                None
+            }
+            None => Some(frame),
+        }
+    }
+
+    /// Drops a destroyed session, returns whether it was still waiting for consent
+    pub fn forget_screencopy_session(&mut self, session: &Session) -> bool {
+        let consent = &mut self.common.screencopy_consent;
+        let len = consent.pending.len();
+        consent
+            .pending
+            .retain(|pending| &pending.session != session);
+        let was_pending = consent.pending.len() != len;
+
+        consent.active.retain(|s| s != session && s.alive());
+        self.update_capture_indicator();
+
+        was_pending
+    }
+
+    /// Tracks a cursor capture session for the capture indicator.
+    ///
+    /// These don't need consent, as they only expose the cursor image and position.
+    pub fn track_cursor_session(&mut self, session: &CursorSession) {
+        let consent = &mut self.common.screencopy_consent;
+        consent.active_cursors.retain(|session| session.alive());
+        consent.active_cursors.push(session.clone());
+        self.update_capture_indicator();
+    }
+
+    pub fn forget_cursor_session(&mut self, session: &CursorSession) {
+        self.common
+            .screencopy_consent
+            .active_cursors
+            .retain(|s| s != session && s.alive());
+        self.update_capture_indicator();
+    }
+
+    /// Shows the capture indicator, while a capture session or a built-in recording runs
+    pub fn update_capture_indicator(&mut self) {
+        let active =
+            self.common.screencopy_consent.capture_active() || self.common.recording.is_some();
+        let mut shell = self.common.shell.write().unwrap();
+        if shell.capture_indicator().is_some() == active {
+            return;
+        }
+        shell.set_capture_indicator(active, &self.common.event_loop_handle);
+        let outputs = shell.outputs().cloned().collect::<Vec<_>>();
+        std::mem::drop(shell);
+
+        for output in outputs {
+            self.backend.schedule_render(&output);
+        }
+    }
+
+    /// Asks for the consent of the client waiting the longest, unless a prompt is already shown
+    fn show_next_screencopy_prompt(&mut self) {
+        let consent = &self.common.screencopy_consent;
+        if consent.prompting.is_some() || consent.retry_scheduled {
+            return;
+        }
+        let Some(client) = consent
+            .pending
+            .first()
+            .map(|pending| pending.client.clone())
+        else {
+            return;
+        };
+
+        let seat = self
+            .common
+            .shell
+            .read()
+            .unwrap()
+            .seats
+            .last_active()
+            .clone();
+        let Some(pointer) = seat.get_pointer() else {
+            self.screencopy_consent_given(client.id(), Consent::DenyOnce);
+            return self.show_next_screencopy_prompt();
+        };
+
+        // don't break e.g. an ongoing move, the prompt is shown once it ends
+        if pointer.is_grabbed() {
+            self.common.screencopy_consent.retry_scheduled = true;
+            if let Err(err) = self.common.event_loop_handle.insert_source(
+                Timer::from_duration(PROMPT_RETRY),
+                |_, _, state| {
+                    state.common.screencopy_consent.retry_scheduled = false;
+                    state.show_next_screencopy_prompt();
+                    TimeoutAction::Drop
+                },
+            ) {
+                warn!(?err, "Failed to schedule screencopy prompt");
+                self.common.screencopy_consent.retry_scheduled = false;
+                self.screencopy_consent_given(client.id(), Consent::DenyOnce);
+            }
+            return;
+        }
+
+        if self.show_screencopy_prompt(&client, &seat, &pointer) {
+            self.common.screencopy_consent.prompting = Some(client.id());
+        } else {
+            self.screencopy_consent_given(client.id(), Consent::DenyOnce);
+            self.show_next_screencopy_prompt();
+        }
+    }
+
+    fn show_screencopy_prompt(
+        &mut self,
+        client: &Client,
+        seat: &Seat<State>,
+        pointer: &PointerHandle<State>,
+    ) -> bool {
+        let (tx, rx) = channel();
+        let client_id = client.id();
+        let mut answered = false;
+        let token = Rc::new(Cell::new(None));
+        let token_clone = token.clone();
+        match self
+            .common
+            .event_loop_handle
+            .insert_source(rx, move |event, _, state| match event {
+                ChannelEvent::Msg(consent) => {
+                    answered = true;
+                    state.screencopy_consent_given(client_id.clone(), consent);
+                }
+                ChannelEvent::Closed => {
+                    // the menu was dismissed without picking anything
+                    if !answered {
+                        answered = true;
+                        state.screencopy_consent_given(client_id.clone(), Consent::DenyOnce);
+                    }
+                    if let Some(token) = token_clone.take() {
+                        state
+                            .common
+                            .event_loop_handle
+                            .insert_idle(move |state| state.common.event_loop_handle.remove(token));
+                    }
+                    state.common.screencopy_consent.prompting = None;
+                    state.show_next_screencopy_prompt();
+                }
+            }) {
+            Ok(registration) => token.set(Some(registration)),
+            Err(err) => {
+                warn!(?err, "Failed to show screencopy prompt");
+                return false;
+            }
+        }
+
+        let app = app_id(client);
+        let name = app.clone().unwrap_or_else(|| fl!("screencopy-unknown-app"));
+        let entry = |title: String, consent: Consent| {
+            let tx = tx.clone();
+            Item::new(title, move |_| {
+                let _ = tx.send(consent);
+            })
+        };
+        let mut items = vec![
+            Item::new(
+                i18n_embed_fl::fl!(crate::state::LANG_LOADER, "screencopy-consent", app = name),
+                |_| {},
+            )
+            .disabled(true),
+            Item::Separator,
+            entry(fl!("screencopy-allow-once"), Consent::AllowOnce),
+        ];
+        // without a stable identity there is nothing to remember
+        if app.is_some() {
+            items.push(entry(fl!("screencopy-allow-always"), Consent::AllowAlways));
+        }
+        items.push(entry(fl!("screencopy-deny"), Consent::Deny));
+        std::mem::drop(tx);
+
+        let location = pointer.current_location();
+        let grab = MenuGrab::new(
+            PointerGrabStartData {
+                focus: None,
+                button: BTN_LEFT,
+                location,
+            },
+            seat,
+            items.into_iter(),
+            location.to_i32_round().as_global(),
+            self.common.event_loop_handle.clone(),
+            self.common.theme.clone(),
+        );
+        pointer.set_grab(self, grab, SERIAL_COUNTER.next_serial(), Focus::Clear);
+
+        true
+    }
+
+    fn screencopy_consent_given(&mut self, client: ClientId, consent: Consent) {
+        let (sessions, pending) = std::mem::take(&mut self.common.screencopy_consent.pending)
+            .into_iter()
+            .partition::<Vec<_>, _>(|pending| pending.client.id() == client);
+        self.common.screencopy_consent.pending = pending;
+        debug!(?client, ?consent, "Screencopy consent given");
+
+        if matches!(consent, Consent::Deny | Consent::DenyOnce) {
+            if consent == Consent::Deny {
+                let consent = &mut self.common.screencopy_consent;
+                match sessions.first().and_then(|pending| app_id(&pending.client)) {
+                    Some(app) => {
+                        consent.denied_apps.insert(app);
+                    }
+                    None => consent.denied_clients.push(client),
+                }
+            }
+            for pending in sessions {
+                pending.session.stop();
+            }
+            return;
+        }
+
+        if consent == Consent::AllowAlways {
+            if let Some(app) = sessions.first().and_then(|pending| app_id(&pending.client)) {
+                self.common
+                    .config
+                    .dynamic_conf
+                    .screencopy_mut()
+                    .allowed
+                    .insert(app);
+            }
+        }
+        self.common.screencopy_consent.allowed_clients.push(client);
+
+        for pending in sessions {
+            if !pending.session.alive() {
+                continue;
+            }
+            self.common
+                .screencopy_consent
+                .active
+                .push(pending.session.clone());
+            self.start_session(pending.session.clone());
+            for frame in pending.frames {
+                self.frame(pending.session.clone(), frame);
+            }
+        }
+        self.update_capture_indicator();
+    }
+}
@@ -33,8 +33,10 @@ use crate::{
     },
 };
 
+mod consent;
 mod render;
 mod user_data;
+pub use self::consent::{ScreencopyConsent, ScreencopyPermissions};
 pub use self::render::*;
 use self::user_data::*;
 pub use self::user_data::{FrameHolder, ScreencopySessions, SessionData, SessionHolder};
@@ -83,50 +85,12 @@ impl ScreencopyHandler for State {
     }
 
     fn new_session(&mut self, session: Session) {
-        match session.source() {
-            ImageSourceData::Output(weak) => {
-                let Some(mut output) = weak.upgrade() else {
-                    session.stop();
-                    return;
-                };
-
-                session.user_data().insert_if_missing_threadsafe(|| {
-                    Mutex::new(SessionUserData::new(OutputDamageTracker::from_output(
-                        &output,
-                    )))
-                });
-
-                output.add_session(session);
-            }
-            ImageSourceData::Workspace(handle) => {
-                let mut shell = self.common.shell.write().unwrap();
-                let Some(workspace) = shell.workspaces.space_for_handle_mut(&handle) else {
-                    session.stop();
-                    return;
-                };
-
-                session.user_data().insert_if_missing_threadsafe(|| {
-                    Mutex::new(SessionUserData::new(OutputDamageTracker::from_output(
-                        workspace.output(),
-                    )))
-                });
-                workspace.add_session(session);
-            }
-            ImageSourceData::Toplevel(mut toplevel) => {
-                let size = toplevel.geometry().size.to_physical(1);
-                session.user_data().insert_if_missing_threadsafe(|| {
-                    Mutex::new(SessionUserData::new(OutputDamageTracker::new(
-                        size,
-                        1.0,
-                        Transform::Normal,
-                    )))
-                });
-                toplevel.add_session(session);
-            }
-            ImageSourceData::Destroyed => unreachable!(),
+        if let Some(session) = self.check_screencopy_consent(session) {
+            self.start_session(session);
         }
     }
     fn new_cursor_session(&mut self, session: CursorSession) {
+        self.track_cursor_session(&session);
         let (pointer_loc, pointer_size, hotspot) = {
             let seat = self
                 .common
@@ -250,6 +214,10 @@ impl ScreencopyHandler for State {
     }
 
     fn frame(&mut self, session: Session, frame: Frame) {
+        let Some(frame) = self.hold_screencopy_frame(&session, frame) else {
+            return;
+        };
+
         match session.source() {
             ImageSourceData::Output(weak) => {
                 let Some(mut output) = weak.upgrade() else {
@@ -295,6 +263,10 @@ impl ScreencopyHandler for State {
     }
 
     fn session_destroyed(&mut self, session: Session) {
+        if self.forget_screencopy_session(&session) {
+            return;
+        }
+
         match session.source() {
             ImageSourceData::Output(weak) => {
                 if let Some(mut output) = weak.upgrade() {
@@ -319,6 +291,7 @@ impl ScreencopyHandler for State {
     }
 
     fn cursor_session_destroyed(&mut self, session: CursorSession) {
+        self.forget_cursor_session(&session);
         match session.source() {
             ImageSourceData::Output(weak) => {
                 if let Some(mut output) = weak.upgrade() {
@@ -343,6 +316,53 @@ impl ScreencopyHandler for State {
     }
 }
 
+impl State {
+    fn start_session(&mut self, session: Session) {
+        match session.source() {
+            ImageSourceData::Output(weak) => {
+                let Some(mut output) = weak.upgrade() else {
+                    session.stop();
+                    return;
+                };
+
+                session.user_data().insert_if_missing_threadsafe(|| {
+                    Mutex::new(SessionUserData::new(OutputDamageTracker::from_output(
+                        &output,
+                    )))
+                });
+
+                output.add_session(session);
+            }
+            ImageSourceData::Workspace(handle) => {
+                let mut shell = self.common.shell.write().unwrap();
+                let Some(workspace) = shell.workspaces.space_for_handle_mut(&handle) else {
+                    session.stop();
+                    return;
+                };
+
+                session.user_data().insert_if_missing_threadsafe(|| {
+                    Mutex::new(SessionUserData::new(OutputDamageTracker::from_output(
+                        workspace.output(),
+                    )))
+                });
+                workspace.add_session(session);
+            }
+            ImageSourceData::Toplevel(mut toplevel) => {
+                let size = toplevel.geometry().size.to_physical(1);
+                session.user_data().insert_if_missing_threadsafe(|| {
+                    Mutex::new(SessionUserData::new(OutputDamageTracker::new(
+                        size,
+                        1.0,
+                        Transform::Normal,
+                    )))
+                });
+                toplevel.add_session(session);
+            }
+            ImageSourceData::Destroyed => unreachable!(),
+        }
+    }
+}
+
 fn constraints_for_output(output: &Output, backend: &mut BackendData) -> Option<BufferConstraints> {
     let mode = match output.current_mode() {
         Some(mode) => mode.size.to_logical(1).to_buffer(1, Transform::Normal),
@@ -425,6 +425,76 @@ smithay::render_elements! {
     AdditionalDamage=DamageElement,
 }
 
+/// Elements of a window and, if requested, the cursor hovering it, relative to the window geometry
+pub fn window_capture_elements<R>(
+    renderer: &mut R,
+    common: &Common,
+    window: &CosmicSurface,
+    geometry: Rectangle<i32, Logical>,
+    draw_cursor: bool,
+) -> Vec<WindowCaptureElement<R>>
+where
+    R: Renderer + ImportAll + ImportMem + AsGlowRenderer,
+    <R as Renderer>::TextureId: Send + Clone + 'static,
+{
+    let mut elements = AsRenderElements::<R>::render_elements::<WindowCaptureElement<R>>(
+        window,
+        renderer,
+        (-geometry.loc.x, -geometry.loc.y).into(),
+        Scale::from(1.0),
+        1.0,
+    );
+
+    let shell = common.shell.read().unwrap();
+    let seat = shell.seats.last_active().clone();
+    let location = if let Some(mapped) = shell.element_for_surface(window) {
+        mapped.cursor_position(&seat).and_then(|mut p| {
+            p -= mapped.active_window_offset().to_f64();
+            if p.x < 0. || p.y < 0. {
+                None
+            } else {
+                Some(p)
+            }
+        })
+    } else {
+        None
+    };
+    std::mem::drop(shell);
+
+    if let Some(location) = location {
+        if draw_cursor {
+            elements.extend(
+                cursor::draw_cursor(
+                    renderer,
+                    &seat,
+                    location,
+                    1.0.into(),
+                    common.clock.now(),
+                    true,
+                )
+                .into_iter()
+                .map(|(elem, hotspot)| {
+                    WindowCaptureElement::CursorElement(RelocateRenderElement::from_element(
+                        elem,
+                        Point::from((-hotspot.x, -hotspot.y)),
+                        Relocate::Relative,
+                    ))
+                }),
+            );
+        }
+
+        if let Some(wl_surface) = get_dnd_icon(&seat) {
+            elements.extend(
+                cursor::draw_dnd_icon(renderer, &wl_surface, location.to_i32_round(), 1.0)
+                    .into_iter()
+                    .map(WindowCaptureElement::from),
+            );
+        }
+    }
+
+    elements
+}
+
 pub fn render_window_to_buffer(
     state: &mut State,
     session: Session,
@@ -479,13 +549,7 @@ pub fn render_window_to_buffer(
         CosmicElement<R>: RenderElement<R>,
         CosmicMappedRenderElement<R>: RenderElement<R>,
     {
-        let mut elements = AsRenderElements::<R>::render_elements::<WindowCaptureElement<R>>(
-            window,
-            renderer,
-            (-geometry.loc.x, -geometry.loc.y).into(),
-            Scale::from(1.0),
-            1.0,
-        );
+        let mut elements = window_capture_elements(renderer, common, window, geometry, draw_cursor);
 
         elements.extend(
             additional_damage
@@ -502,53 +566,6 @@ pub fn render_window_to_buffer(
                 .map(Into::<WindowCaptureElement<R>>::into),
         );
 
-        let shell = common.shell.read().unwrap();
-        let seat = shell.seats.last_active().clone();
-        let location = if let Some(mapped) = shell.element_for_surface(window) {
-            mapped.cursor_position(&seat).and_then(|mut p| {
-                p -= mapped.active_window_offset().to_f64();
-                if p.x < 0. || p.y < 0. {
-                    None
-                } else {
-                    Some(p)
-                }
-            })
-        } else {
-            None
-        };
-        std::mem::drop(shell);
-
-        if let Some(location) = location {
-            if draw_cursor {
-                elements.extend(
-                    cursor::draw_cursor(
-                        renderer,
-                        &seat,
-                        location,
-                        1.0.into(),
-                        common.clock.now(),
-                        true,
-                    )
-                    .into_iter()
-                    .map(|(elem, hotspot)| {
-                        WindowCaptureElement::CursorElement(RelocateRenderElement::from_element(
-                            elem,
-                            Point::from((-hotspot.x, -hotspot.y)),
-                            Relocate::Relative,
-                        ))
-                    }),
-                );
-            }
-
-            if let Some(wl_surface) = get_dnd_icon(&seat) {
-                elements.extend(
-                    cursor::draw_dnd_icon(renderer, &wl_surface, location.to_i32_round(), 1.0)
-                        .into_iter()
-                        .map(WindowCaptureElement::from),
-                );
-            }
-        }
-
         if let Ok(dmabuf) = get_dmabuf(buffer) {
             renderer.bind(dmabuf.clone()).map_err(DTError::Rendering)?;
         } else {
@@ -26,13 +26,20 @@ impl SecurityContextHandler for State {
                     .get_client_data(security_context.creator_client_id.clone())
                     .ok();
 
-                let privileged = client_data
+                let new_state = state.new_client_state(&client_stream);
+
+                // a sandboxed client never gets more privileges than its creator
+                let privileges = client_data
                     .as_ref()
                     .and_then(|data| data.downcast_ref::<ClientState>())
-                    .map(|data| data.privileged)
-                    .unwrap_or(false);
-
-                let new_state = state.new_client_state();
+                    .map(|data| {
+                        state
+                            .client_privileges(&new_state, Some(&security_context))
+                            .intersection(&data.privileges)
+                            .copied()
+                            .collect()
+                    })
+                    .unwrap_or_default();
 
                 let drm_node = client_data
                     .as_ref()
@@ -50,9 +57,7 @@ impl SecurityContextHandler for State {
                     client_stream,
                     Arc::new(ClientState {
                         security_context: Some(security_context.clone()),
-                        privileged: privileged
-                            && security_context.sandbox_engine.as_deref()
-                                == Some("com.system76.CosmicPanel"),
+                        privileges,
                         advertised_drm_node: drm_node,
                         ..new_state
                     }),
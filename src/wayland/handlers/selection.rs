@@ -6,11 +6,19 @@ use smithay::{
     wayland::selection::{SelectionHandler, SelectionSource, SelectionTarget},
     xwayland::xwm::XwmId,
 };
-use std::os::unix::io::OwnedFd;
+use std::{io::Write, os::unix::io::OwnedFd, sync::Arc};
 use tracing::warn;
 
+/// Owner of a selection, that wasn't set by a wayland client
+#[derive(Debug, Clone, PartialEq)]
+pub enum SelectionData {
+    Xwayland(XwmId),
+    /// Encoded image of a screenshot
+    Image(Arc<Vec<u8>>),
+}
+
 impl SelectionHandler for State {
-    type SelectionUserData = XwmId;
+    type SelectionUserData = SelectionData;
 
     fn new_selection(
         &mut self,
@@ -40,18 +48,34 @@ impl SelectionHandler for State {
         mime_type: String,
         fd: OwnedFd,
         _seat: Seat<State>,
-        _user_data: &Self::SelectionUserData,
+        user_data: &Self::SelectionUserData,
     ) {
-        if let Some(xwm) = self
-            .common
-            .xwayland_state
-            .as_mut()
-            .and_then(|xstate| xstate.xwm.as_mut())
-        {
-            if let Err(err) =
-                xwm.send_selection(target, mime_type, fd, self.common.event_loop_handle.clone())
-            {
-                warn!(?err, "Failed to send selection (X11 -> Wayland).");
+        match user_data {
+            SelectionData::Xwayland(_) => {
+                if let Some(xwm) = self
+                    .common
+                    .xwayland_state
+                    .as_mut()
+                    .and_then(|xstate| xstate.xwm.as_mut())
+                {
+                    if let Err(err) = xwm.send_selection(
+                        target,
+                        mime_type,
+                        fd,
+                        self.common.event_loop_handle.clone(),
+                    ) {
+                        warn!(?err, "Failed to send selection (X11 -> Wayland).");
+                    }
+                }
+            }
+            SelectionData::Image(data) => {
+                // the receiving end might read slowly, don't block the event loop on it
+                let data = data.clone();
+                std::thread::spawn(move || {
+                    if let Err(err) = std::fs::File::from(fd).write_all(&data) {
+                        warn!(?err, "Failed to send image selection.");
+                    }
+                });
             }
         }
     }
@@ -1,14 +1,19 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
-use crate::state::State;
+use crate::{shell::SeatExt, state::State};
 use smithay::{
     backend::input::TabletToolDescriptor, delegate_tablet_manager,
     input::pointer::CursorImageStatus, wayland::tablet_manager::TabletSeatHandler,
 };
 
 impl TabletSeatHandler for State {
-    fn tablet_tool_image(&mut self, _tool: &TabletToolDescriptor, _image: CursorImageStatus) {
-        // TODO display cursor for each tablet tool
+    fn tablet_tool_image(&mut self, tool: &TabletToolDescriptor, image: CursorImageStatus) {
+        let shell = self.common.shell.read().unwrap();
+        for seat in shell.seats.iter() {
+            if seat.tablet_tool_cursors().set_image(tool, image.clone()) {
+                break;
+            }
+        }
     }
 }
 
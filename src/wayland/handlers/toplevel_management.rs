@@ -35,6 +35,11 @@ impl ToplevelManagementHandler for State {
         self.unminimize(dh, window);
 
         let mut shell = self.common.shell.write().unwrap();
+        // a terminal hidden by a window launched from it can't be activated, so bring it back
+        let active_seat = seat
+            .clone()
+            .unwrap_or_else(|| shell.seats.last_active().clone());
+        shell.unswallow(window, &active_seat);
         for output in shell.outputs().cloned().collect::<Vec<_>>().iter() {
             let maybe = shell
                 .workspaces
@@ -11,6 +11,7 @@ use crate::{
 };
 use cosmic_protocols::workspace::v1::server::zcosmic_workspace_handle_v1::TilingState;
 use smithay::reexports::wayland_server::DisplayHandle;
+use tracing::warn;
 
 impl WorkspaceClientHandler for ClientState {
     fn workspace_state(&self) -> &WorkspaceClientState {
@@ -28,6 +29,7 @@ impl WorkspaceHandler for State {
     }
 
     fn commit_requests(&mut self, _dh: &DisplayHandle, requests: Vec<Request>) {
+        let mut changed = false;
         for request in requests.into_iter() {
             match request {
                 Request::Activate(handle) => {
@@ -64,9 +66,52 @@ impl WorkspaceHandler for State {
                         );
                     }
                 }
-                _ => {}
+                Request::Deactivate(handle) => {
+                    let mut shell = self.common.shell.write().unwrap();
+                    shell.deactivate(&handle, &mut self.common.workspace_state.update());
+                }
+                Request::Create { in_group, name } => {
+                    let mut shell = self.common.shell.write().unwrap();
+                    if shell.workspaces.create_named(
+                        &in_group,
+                        name.clone(),
+                        &mut self.common.workspace_state.update(),
+                    ) {
+                        changed = true;
+                    } else {
+                        warn!(name, "Failed to create workspace");
+                    }
+                }
+                Request::Remove(handle) => {
+                    let mut shell = self.common.shell.write().unwrap();
+                    if shell.remove_workspace(&handle, &mut self.common.workspace_state.update()) {
+                        changed = true;
+                    } else {
+                        warn!(
+                            "Failed to remove workspace, it is either named in the config or the last one of its output"
+                        );
+                    }
+                }
+                Request::Rename { workspace, name } => {
+                    let mut shell = self.common.shell.write().unwrap();
+                    if !shell.workspaces.rename(
+                        &workspace,
+                        name.clone(),
+                        &mut self.common.workspace_state.update(),
+                    ) {
+                        warn!(
+                            name,
+                            "Failed to rename workspace, the name is already taken"
+                        );
+                    }
+                }
             }
         }
+
+        if changed {
+            // drops dynamic workspaces, that aren't needed anymore
+            self.common.refresh();
+        }
     }
 }
 
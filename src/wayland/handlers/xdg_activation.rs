@@ -1,5 +1,6 @@
 use crate::{shell::ActivationKey, state::ClientState, utils::prelude::*};
 use crate::{state::State, wayland::protocols::workspace::WorkspaceHandle};
+use cosmic_comp_config::security::Privilege;
 use cosmic_protocols::workspace::v1::server::zcosmic_workspace_handle_v1::State as WState;
 use smithay::{
     delegate_xdg_activation,
@@ -35,7 +36,7 @@ impl XdgActivationHandler for State {
             })
             .and_then(|data| {
                 data.downcast_ref::<ClientState>()
-                    .map(|data| data.privileged)
+                    .map(|data| data.privileges.contains(&Privilege::Activation))
             })
             .unwrap_or(false)
         {
@@ -264,6 +264,7 @@ impl XdgShellHandler for State {
                         surface,
                         self.common.event_loop_handle.clone(),
                         self.common.theme.clone(),
+                        self.common.config.cosmic_conf.decorations.clone(),
                     ))
                 } else {
                     set.sticky_layer.unmap(&mapped);
@@ -303,6 +304,7 @@ impl XdgShellHandler for State {
                                 surface,
                                 self.common.event_loop_handle.clone(),
                                 self.common.theme.clone(),
+                                self.common.config.cosmic_conf.decorations.clone(),
                             )),
                             if workspace.is_tiled(&mapped) {
                                 ManagedLayer::Tiling
@@ -21,7 +21,19 @@ use cosmic_protocols::output_management::v1::server::{
     zcosmic_output_manager_v1::{self, ZcosmicOutputManagerV1},
 };
 
-use crate::wayland::protocols::output_configuration::*;
+use crate::{
+    config::MirroringMode,
+    wayland::protocols::output_configuration::{
+        mirroring::{
+            zcosmic_output_mirroring_configuration_head_v1::{
+                self, ZcosmicOutputMirroringConfigurationHeadV1,
+            },
+            zcosmic_output_mirroring_head_v1::{self, ZcosmicOutputMirroringHeadV1},
+            zcosmic_output_mirroring_manager_v1::{self, ZcosmicOutputMirroringManagerV1},
+        },
+        *,
+    },
+};
 
 impl<D> GlobalDispatch<ZcosmicOutputManagerV1, OutputMngrGlobalData, D>
     for OutputConfigurationState<D>
@@ -274,3 +286,180 @@ where
         }
     }
 }
+
+impl From<MirroringMode> for zcosmic_output_mirroring_manager_v1::Mode {
+    fn from(mode: MirroringMode) -> Self {
+        match mode {
+            MirroringMode::Letterbox => zcosmic_output_mirroring_manager_v1::Mode::Letterbox,
+            MirroringMode::Stretch => zcosmic_output_mirroring_manager_v1::Mode::Stretch,
+            MirroringMode::Crop => zcosmic_output_mirroring_manager_v1::Mode::Crop,
+            MirroringMode::IntegerScale => zcosmic_output_mirroring_manager_v1::Mode::IntegerScale,
+        }
+    }
+}
+
+impl From<zcosmic_output_mirroring_manager_v1::Mode> for MirroringMode {
+    fn from(mode: zcosmic_output_mirroring_manager_v1::Mode) -> Self {
+        match mode {
+            zcosmic_output_mirroring_manager_v1::Mode::Letterbox => MirroringMode::Letterbox,
+            zcosmic_output_mirroring_manager_v1::Mode::Stretch => MirroringMode::Stretch,
+            zcosmic_output_mirroring_manager_v1::Mode::Crop => MirroringMode::Crop,
+            zcosmic_output_mirroring_manager_v1::Mode::IntegerScale => MirroringMode::IntegerScale,
+        }
+    }
+}
+
+impl<D> GlobalDispatch<ZcosmicOutputMirroringManagerV1, OutputMngrGlobalData, D>
+    for OutputConfigurationState<D>
+where
+    D: GlobalDispatch<ZcosmicOutputMirroringManagerV1, OutputMngrGlobalData>
+        + Dispatch<ZcosmicOutputMirroringManagerV1, ()>
+        + OutputConfigurationHandler
+        + 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _dh: &DisplayHandle,
+        _client: &Client,
+        resource: New<ZcosmicOutputMirroringManagerV1>,
+        _global_data: &OutputMngrGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(resource, ());
+    }
+
+    fn can_view(client: Client, global_data: &OutputMngrGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl<D> Dispatch<ZcosmicOutputMirroringManagerV1, (), D> for OutputConfigurationState<D>
+where
+    D: GlobalDispatch<ZwlrOutputManagerV1, OutputMngrGlobalData>
+        + Dispatch<ZwlrOutputManagerV1, ()>
+        + Dispatch<ZwlrOutputHeadV1, Output>
+        + Dispatch<ZwlrOutputModeV1, Mode>
+        + Dispatch<ZwlrOutputConfigurationV1, PendingConfiguration>
+        + Dispatch<ZwlrOutputConfigurationHeadV1, PendingOutputConfiguration>
+        + Dispatch<ZcosmicOutputMirroringManagerV1, ()>
+        + Dispatch<ZcosmicOutputMirroringHeadV1, Weak<ZwlrOutputHeadV1>>
+        + Dispatch<ZcosmicOutputMirroringConfigurationHeadV1, Weak<ZwlrOutputConfigurationHeadV1>>
+        + OutputConfigurationHandler
+        + 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        _obj: &ZcosmicOutputMirroringManagerV1,
+        request: zcosmic_output_mirroring_manager_v1::Request,
+        _data: &(),
+        dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zcosmic_output_mirroring_manager_v1::Request::GetHead { id, head } => {
+                let obj = data_init.init(id, head.downgrade());
+                let inner = state.output_configuration_state();
+                if let Some(mngr) = inner
+                    .instances
+                    .iter_mut()
+                    .find(|instance| instance.heads.iter().any(|instance| instance.obj == head))
+                {
+                    let head_data = mngr
+                        .heads
+                        .iter_mut()
+                        .find(|instance| instance.obj == head)
+                        .unwrap();
+                    head_data.mirroring_obj = Some(obj);
+                    let output = head_data.output.clone();
+
+                    send_head_to_client::<D>(dh, mngr, &output);
+                    mngr.obj.done(inner.serial_counter);
+                }
+            }
+            zcosmic_output_mirroring_manager_v1::Request::GetConfigurationHead {
+                id,
+                config_head,
+            } => {
+                data_init.init(id, config_head.downgrade());
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<D> Dispatch<ZcosmicOutputMirroringHeadV1, Weak<ZwlrOutputHeadV1>, D>
+    for OutputConfigurationState<D>
+where
+    D: OutputConfigurationHandler + 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        obj: &ZcosmicOutputMirroringHeadV1,
+        request: zcosmic_output_mirroring_head_v1::Request,
+        _data: &Weak<ZwlrOutputHeadV1>,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zcosmic_output_mirroring_head_v1::Request::Release => {
+                let inner = state.output_configuration_state();
+                if let Some(head) = inner
+                    .instances
+                    .iter_mut()
+                    .flat_map(|instance| instance.heads.iter_mut())
+                    .find(|head| head.mirroring_obj.as_ref().is_some_and(|o| o == obj))
+                {
+                    head.mirroring_obj.take();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<D> Dispatch<ZcosmicOutputMirroringConfigurationHeadV1, Weak<ZwlrOutputConfigurationHeadV1>, D>
+    for OutputConfigurationState<D>
+where
+    D: OutputConfigurationHandler + 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        extension_obj: &ZcosmicOutputMirroringConfigurationHeadV1,
+        request: zcosmic_output_mirroring_configuration_head_v1::Request,
+        obj: &Weak<ZwlrOutputConfigurationHeadV1>,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zcosmic_output_mirroring_configuration_head_v1::Request::SetMode { mode } => {
+                let mode = match mode.into_result() {
+                    Ok(mode) => MirroringMode::from(mode),
+                    Err(err) => {
+                        extension_obj.post_error(
+                            zcosmic_output_mirroring_configuration_head_v1::Error::InvalidMode,
+                            format!("{:?}", err),
+                        );
+                        return;
+                    }
+                };
+                if let Ok(obj) = obj.upgrade() {
+                    if let Some(data) = obj.data::<PendingOutputConfiguration>() {
+                        let mut pending = data.lock().unwrap();
+                        if pending.mirroring_mode.is_some() {
+                            extension_obj.post_error(
+                                zcosmic_output_mirroring_configuration_head_v1::Error::AlreadySet,
+                                format!("{:?} already had a mirroring mode configured", obj),
+                            );
+                            return;
+                        }
+                        pending.mirroring_mode = Some(mode);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
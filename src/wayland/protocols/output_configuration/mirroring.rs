@@ -0,0 +1,31 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+// Generated code of the mirroring mode extension, see `handlers/cosmic.rs` for the implementation
+pub use generated::{
+    zcosmic_output_mirroring_configuration_head_v1, zcosmic_output_mirroring_head_v1,
+    zcosmic_output_mirroring_manager_v1,
+};
+
+#[allow(non_snake_case, non_upper_case_globals, non_camel_case_types)]
+mod generated {
+    use smithay::reexports::{
+        wayland_protocols_wlr::output_management::v1::server::*,
+        wayland_server::{self, protocol::*},
+    };
+
+    pub mod __interfaces {
+        use smithay::reexports::{
+            wayland_protocols_wlr::output_management::v1::__interfaces::*,
+            wayland_server::protocol::__interfaces::*,
+        };
+        use wayland_backend;
+        wayland_scanner::generate_interfaces!(
+            "resources/protocols/cosmic-output-mirroring-unstable-v1.xml"
+        );
+    }
+    use self::__interfaces::*;
+
+    wayland_scanner::generate_server_code!(
+        "resources/protocols/cosmic-output-mirroring-unstable-v1.xml"
+    );
+}
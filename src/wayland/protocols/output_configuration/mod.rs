@@ -25,7 +25,16 @@ use smithay::{
 };
 use std::{convert::TryFrom, sync::Mutex};
 
+use crate::config::MirroringMode;
+
+use self::mirroring::{
+    zcosmic_output_mirroring_configuration_head_v1::ZcosmicOutputMirroringConfigurationHeadV1,
+    zcosmic_output_mirroring_head_v1::ZcosmicOutputMirroringHeadV1,
+    zcosmic_output_mirroring_manager_v1::ZcosmicOutputMirroringManagerV1,
+};
+
 mod handlers;
+pub mod mirroring;
 
 #[derive(Debug)]
 pub struct OutputConfigurationState<D> {
@@ -60,6 +69,7 @@ struct OutputMngrInstance {
 struct OutputHeadInstance {
     obj: ZwlrOutputHeadV1,
     extension_obj: Option<ZcosmicOutputHeadV1>,
+    mirroring_obj: Option<ZcosmicOutputMirroringHeadV1>,
     output: Output,
     modes: Vec<ZwlrOutputModeV1>,
     finished: bool,
@@ -87,6 +97,7 @@ pub enum ModeConfiguration<M: Clone> {
 #[derive(Debug, Default, Clone)]
 pub struct PendingOutputConfigurationInner {
     mirroring: Option<Output>,
+    mirroring_mode: Option<MirroringMode>,
     mode: Option<ModeConfiguration<ZwlrOutputModeV1>>,
     position: Option<Point<i32, Logical>>,
     transform: Option<Transform>,
@@ -99,6 +110,7 @@ pub type PendingOutputConfiguration = Mutex<PendingOutputConfigurationInner>;
 pub enum OutputConfiguration {
     Enabled {
         mirroring: Option<Output>,
+        mirroring_mode: Option<MirroringMode>,
         mode: Option<ModeConfiguration<Mode>>,
         position: Option<Point<i32, Logical>>,
         transform: Option<Transform>,
@@ -128,6 +140,7 @@ impl<'a> TryFrom<&'a mut PendingOutputConfigurationInner> for OutputConfiguratio
         Ok(OutputConfiguration::Enabled {
             mode,
             mirroring: pending.mirroring.clone(),
+            mirroring_mode: pending.mirroring_mode,
             position: pending.position,
             transform: pending.transform,
             scale: pending.scale,
@@ -156,6 +169,10 @@ where
         + Dispatch<ZcosmicOutputHeadV1, Weak<ZwlrOutputHeadV1>>
         + Dispatch<ZcosmicOutputConfigurationV1, Weak<ZwlrOutputConfigurationV1>>
         + Dispatch<ZcosmicOutputConfigurationHeadV1, Weak<ZwlrOutputConfigurationHeadV1>>
+        + GlobalDispatch<ZcosmicOutputMirroringManagerV1, OutputMngrGlobalData>
+        + Dispatch<ZcosmicOutputMirroringManagerV1, ()>
+        + Dispatch<ZcosmicOutputMirroringHeadV1, Weak<ZwlrOutputHeadV1>>
+        + Dispatch<ZcosmicOutputMirroringConfigurationHeadV1, Weak<ZwlrOutputConfigurationHeadV1>>
         + OutputConfigurationHandler
         + 'static,
 {
@@ -171,6 +188,14 @@ where
         );
 
         let extension_global = dh.create_global::<D, ZcosmicOutputManagerV1, _>(
+            1,
+            OutputMngrGlobalData {
+                filter: Box::new(client_filter.clone()),
+            },
+        );
+
+        // lives as long as the compositor, so it is never removed
+        dh.create_global::<D, ZcosmicOutputMirroringManagerV1, _>(
             1,
             OutputMngrGlobalData {
                 filter: Box::new(client_filter),
@@ -320,6 +345,7 @@ where
                     let data = OutputHeadInstance {
                         obj: head,
                         extension_obj: None,
+                        mirroring_obj: None,
                         modes: Vec::new(),
                         output: output.clone(),
                         finished: false,
@@ -423,6 +449,11 @@ where
 
             extension_obj.mirroring(output.mirroring().map(|o| o.name()));
         }
+        if let Some(mirroring_obj) = instance.mirroring_obj.as_ref() {
+            if output.mirroring().is_some() {
+                mirroring_obj.mode(output.mirroring_mode().into());
+            }
+        }
 
         if instance.obj.version() >= zwlr_output_head_v1::EVT_ADAPTIVE_SYNC_SINCE {
             instance.obj.adaptive_sync(if output.adaptive_sync() {
@@ -478,6 +509,18 @@ macro_rules! delegate_output_configuration {
         smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
             cosmic_protocols::output_management::v1::server::zcosmic_output_configuration_head_v1::ZcosmicOutputConfigurationHeadV1: smithay::reexports::wayland_server::Weak<smithay::reexports::wayland_protocols_wlr::output_management::v1::server::zwlr_output_configuration_head_v1::ZwlrOutputConfigurationHeadV1>
         ] => $crate::wayland::protocols::output_configuration::OutputConfigurationState<Self>);
+        smithay::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::wayland::protocols::output_configuration::mirroring::zcosmic_output_mirroring_manager_v1::ZcosmicOutputMirroringManagerV1: $crate::wayland::protocols::output_configuration::OutputMngrGlobalData
+        ] => $crate::wayland::protocols::output_configuration::OutputConfigurationState<Self>);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::wayland::protocols::output_configuration::mirroring::zcosmic_output_mirroring_manager_v1::ZcosmicOutputMirroringManagerV1: ()
+        ] => $crate::wayland::protocols::output_configuration::OutputConfigurationState<Self>);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::wayland::protocols::output_configuration::mirroring::zcosmic_output_mirroring_head_v1::ZcosmicOutputMirroringHeadV1: smithay::reexports::wayland_server::Weak<smithay::reexports::wayland_protocols_wlr::output_management::v1::server::zwlr_output_head_v1::ZwlrOutputHeadV1>
+        ] => $crate::wayland::protocols::output_configuration::OutputConfigurationState<Self>);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::wayland::protocols::output_configuration::mirroring::zcosmic_output_mirroring_configuration_head_v1::ZcosmicOutputMirroringConfigurationHeadV1: smithay::reexports::wayland_server::Weak<smithay::reexports::wayland_protocols_wlr::output_management::v1::server::zwlr_output_configuration_head_v1::ZwlrOutputConfigurationHeadV1>
+        ] => $crate::wayland::protocols::output_configuration::OutputConfigurationState<Self>);
     };
 }
 pub(crate) use delegate_output_configuration;
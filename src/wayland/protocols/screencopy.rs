@@ -167,6 +167,10 @@ impl Session {
         self.inner.lock().unwrap().draw_cursors
     }
 
+    pub fn client(&self) -> Option<Client> {
+        self.obj.client()
+    }
+
     pub fn user_data(&self) -> &UserDataMap {
         &*self.user_data
     }
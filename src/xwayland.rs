@@ -2,14 +2,12 @@ use std::{ffi::OsString, os::unix::io::OwnedFd, process::Stdio};
 
 use crate::{
     backend::render::cursor::{load_cursor_theme, Cursor},
-    shell::{
-        element::surface::SSD_HEIGHT, focus::target::KeyboardFocusTarget, grabs::ReleaseMode,
-        CosmicSurface, Shell,
-    },
+    shell::{focus::target::KeyboardFocusTarget, grabs::ReleaseMode, CosmicSurface, Shell},
     state::State,
     utils::prelude::*,
     wayland::handlers::{
-        toplevel_management::minimize_rectangle, xdg_activation::ActivationContext,
+        selection::SelectionData, toplevel_management::minimize_rectangle,
+        xdg_activation::ActivationContext,
     },
 };
 use smithay::{
@@ -479,7 +477,7 @@ impl XwmHandler for State {
             };
 
             if let Some(current_geo) = current_geo {
-                let ssd_height = if window.is_decorated() { 0 } else { SSD_HEIGHT };
+                let ssd_height = mapped.header_height().unwrap_or(0);
                 mapped.set_geometry(Rectangle::from_loc_and_size(
                     current_geo.loc,
                     (
@@ -746,12 +744,18 @@ impl XwmHandler for State {
                 .last_active()
                 .clone();
             match selection {
-                SelectionTarget::Clipboard => {
-                    set_data_device_selection(&self.common.display_handle, &seat, mime_types, xwm)
-                }
-                SelectionTarget::Primary => {
-                    set_primary_selection(&self.common.display_handle, &seat, mime_types, xwm)
-                }
+                SelectionTarget::Clipboard => set_data_device_selection(
+                    &self.common.display_handle,
+                    &seat,
+                    mime_types,
+                    SelectionData::Xwayland(xwm),
+                ),
+                SelectionTarget::Primary => set_primary_selection(
+                    &self.common.display_handle,
+                    &seat,
+                    mime_types,
+                    SelectionData::Xwayland(xwm),
+                ),
             }
         }
     }
@@ -761,12 +765,16 @@ impl XwmHandler for State {
         for seat in shell.seats.iter() {
             match selection {
                 SelectionTarget::Clipboard => {
-                    if current_data_device_selection_userdata(seat).as_deref() == Some(&xwm) {
+                    if current_data_device_selection_userdata(seat).as_deref()
+                        == Some(&SelectionData::Xwayland(xwm))
+                    {
                         clear_data_device_selection(&self.common.display_handle, seat)
                     }
                 }
                 SelectionTarget::Primary => {
-                    if current_primary_selection_userdata(seat).as_deref() == Some(&xwm) {
+                    if current_primary_selection_userdata(seat).as_deref()
+                        == Some(&SelectionData::Xwayland(xwm))
+                    {
                         clear_primary_selection(&self.common.display_handle, seat)
                     }
                 }